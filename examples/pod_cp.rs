@@ -3,10 +3,9 @@ use k8s_openapi::api::core::v1::Pod;
 use tracing::*;
 
 use kube::{
-    api::{Api, AttachParams, DeleteParams, PostParams, ResourceExt, WatchEvent, WatchParams},
+    api::{Api, AttachParams, CopyParams, DeleteParams, PostParams, ResourceExt, WatchEvent, WatchParams},
     Client,
 };
-use tokio::io::AsyncWriteExt;
 
 // A `kubectl cp` analog example.
 
@@ -53,32 +52,19 @@ async fn main() -> anyhow::Result<()> {
     }
 
     let data = "data for pod";
-    let file_name = "foo.txt";
+    let local_dir = std::env::temp_dir().join("kube-pod-cp-example");
+    std::fs::create_dir_all(&local_dir)?;
+    std::fs::write(local_dir.join("foo.txt"), data)?;
 
-    // Write the data to pod
-    {
-        let mut header = tar::Header::new_gnu();
-        header.set_path(file_name).unwrap();
-        header.set_size(data.len() as u64);
-        header.set_cksum();
-
-        let mut ar = tar::Builder::new(Vec::new());
-        ar.append(&header, &mut data.as_bytes()).unwrap();
-        let data = ar.into_inner().unwrap();
-
-        let ap = AttachParams::default().stdin(true).stderr(false);
-        let mut tar = pods
-            .exec("example", vec!["tar", "xf", "-", "-C", "/"], &ap)
-            .await?;
-        tar.stdin().unwrap().write_all(&data).await?;
-    }
+    // Copy the directory into the pod, reporting each copied entry
+    let cp = CopyParams::default().on_progress(|p| info!("copied {} ({} bytes)", p.path.display(), p.bytes));
+    let summary = pods.copy_to("example", &local_dir, "/data", &cp).await?;
+    info!("copied {} files to the pod", summary.files);
 
     // Check that the file was written
     {
         let ap = AttachParams::default().stderr(false);
-        let mut cat = pods
-            .exec("example", vec!["cat", &format!("/{file_name}")], &ap)
-            .await?;
+        let mut cat = pods.exec("example", vec!["cat", "/data/foo.txt"], &ap).await?;
         let mut cat_out = tokio_util::io::ReaderStream::new(cat.stdout().unwrap());
         let next_stdout = cat_out.next().await.unwrap()?;
 
@@ -86,6 +72,12 @@ async fn main() -> anyhow::Result<()> {
         assert_eq!(next_stdout, data);
     }
 
+    // Copy it back out again
+    let roundtrip = std::env::temp_dir().join("kube-pod-cp-example-roundtrip");
+    pods.copy_from("example", "/data", &roundtrip, &CopyParams::default())
+        .await?;
+    assert_eq!(std::fs::read_to_string(roundtrip.join("foo.txt"))?, data);
+
     // Clean up the pod
     pods.delete("example", &DeleteParams::default())
         .await?
@@ -1,3 +1,4 @@
+use futures::TryStreamExt;
 use k8s_openapi::api::core::v1::Pod;
 use kube::{
     api::{Api, ListParams, ResourceExt},
@@ -17,31 +18,12 @@ async fn main() -> anyhow::Result<()> {
     let client = Client::try_default().await?;
     let api = Api::<Pod>::default_namespaced(client);
 
-    let mut continue_token: Option<String> = None;
-    for page in 1.. {
-        info!("Fetching Page #{page}");
-        continue_token = fetch_page(&api, continue_token).await?;
-
-        if continue_token.is_none() {
-            info!("End of list");
-            break;
-        }
-    }
-
-    Ok(())
-}
-
-async fn fetch_page(api: &Api<Pod>, continue_token: Option<String>) -> anyhow::Result<Option<String>> {
-    let mut lp = ListParams::default().limit(PAGE_SIZE);
-    if let Some(token) = continue_token {
-        lp = lp.continue_token(&token);
-    }
-
-    let pods = api.list(&lp).await?;
-    let continue_token = pods.metadata.continue_.clone();
-    for p in pods {
+    // Pages are fetched as the stream is consumed, following continue tokens
+    let mut pods = api.list_stream(&ListParams::default().limit(PAGE_SIZE));
+    while let Some(p) = pods.try_next().await? {
         info!("Found Pod: {}", p.name_any());
     }
+    info!("End of list at resourceVersion {:?}", pods.resource_version());
 
-    Ok(continue_token)
+    Ok(())
 }
@@ -0,0 +1,661 @@
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::Status;
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio_util::io::SyncIoBridge;
+
+use crate::api::{Api, AttachParams, Execute};
+
+/// GNU tar reads in records of 20 blocks, so archives are padded to a full record
+/// to let the remote `tar` finish without waiting for more input.
+const TAR_RECORD_SIZE: u64 = 20 * 512;
+
+/// Errors from copying files to and from a pod
+#[derive(Debug, Error)]
+pub enum CopyError {
+    /// Failed to start `tar` in the container
+    #[error("failed to exec tar in the container: {0}")]
+    Exec(#[source] Box<crate::Error>),
+
+    /// Failed to communicate with the `tar` process in the container
+    #[error("failed to communicate with the remote process: {0}")]
+    Attach(#[source] Box<super::remote_command::Error>),
+
+    /// The `tar` process in the container failed
+    #[error("tar failed in the container: {0}")]
+    Remote(String),
+
+    /// Failed to read or write local files, or to read or write the archive
+    #[error("failed to copy {path:?}: {source}")]
+    Io {
+        /// The local path that was being copied
+        path: PathBuf,
+        /// The underlying IO error
+        #[source]
+        source: io::Error,
+    },
+
+    /// The remote path has no file name to copy
+    #[error("invalid remote path {0:?}")]
+    InvalidRemotePath(String),
+
+    /// Failed to complete the background task
+    #[error("failed to complete the background task: {0}")]
+    Spawn(#[source] tokio::task::JoinError),
+}
+
+/// Progress of a copy, reported to [`CopyParams::on_progress`] after each entry
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CopyProgress {
+    /// The local path that was just copied
+    pub path: PathBuf,
+    /// Number of regular files copied so far
+    pub files: u64,
+    /// Number of bytes of file contents copied so far
+    pub bytes: u64,
+}
+
+/// The result of a copy
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CopySummary {
+    /// Number of regular files copied
+    pub files: u64,
+    /// Number of bytes of file contents copied
+    pub bytes: u64,
+    /// Archive entries that were not extracted, because they were unsafe or unsupported
+    ///
+    /// This includes symlinks pointing outside of the destination, hard links, and special files.
+    pub skipped: Vec<PathBuf>,
+}
+
+type ProgressFn = Arc<dyn Fn(&CopyProgress) + Send + Sync>;
+
+/// Parameters for [`Api::copy_to`] and [`Api::copy_from`]
+#[derive(Clone, Default)]
+pub struct CopyParams {
+    /// The container to copy to or from, required for pods with multiple containers
+    pub container: Option<String>,
+    /// Do not preserve file permissions and modification times, like `kubectl cp --no-preserve`
+    pub no_preserve: bool,
+    progress: Option<ProgressFn>,
+}
+
+impl fmt::Debug for CopyParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CopyParams")
+            .field("container", &self.container)
+            .field("no_preserve", &self.no_preserve)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CopyParams {
+    /// Copy to or from a specific container
+    #[must_use]
+    pub fn container<T: Into<String>>(mut self, container: T) -> Self {
+        self.container = Some(container.into());
+        self
+    }
+
+    /// Do not preserve file permissions and modification times
+    #[must_use]
+    pub fn no_preserve(mut self, no_preserve: bool) -> Self {
+        self.no_preserve = no_preserve;
+        self
+    }
+
+    /// Call `f` after each file, directory or symlink is copied
+    ///
+    /// The callback runs on a blocking thread, and should return quickly.
+    #[must_use]
+    pub fn on_progress(mut self, f: impl Fn(&CopyProgress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(f));
+        self
+    }
+
+    fn attach_params(&self) -> AttachParams {
+        AttachParams {
+            container: self.container.clone(),
+            ..AttachParams::default()
+        }
+    }
+}
+
+impl<K> Api<K>
+where
+    K: Clone + DeserializeOwned + Execute,
+{
+    /// Copy a local file or directory into a container, like `kubectl cp <src> <pod>:<dest>`
+    ///
+    /// `src` is copied to `dest`. If `dest` is an existing directory, the copied files are merged into it,
+    /// overwriting files with the same names and leaving other files in place. The files are streamed as a tar
+    /// archive to `tar` running in the container, which must therefore be available in the image. Symlinks are
+    /// copied as symlinks, rather than being followed.
+    ///
+    /// ```no_run
+    /// use k8s_openapi::api::core::v1::Pod;
+    /// use kube::{api::CopyParams, Api, Client};
+    /// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client: Client = todo!();
+    /// let pods: Api<Pod> = Api::default_namespaced(client);
+    /// let cp = CopyParams::default().on_progress(|p| println!("{} ({} bytes)", p.path.display(), p.bytes));
+    /// let summary = pods.copy_to("example", "./config", "/etc/app/config", &cp).await?;
+    /// println!("copied {} files", summary.files);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn copy_to(
+        &self,
+        name: &str,
+        src: impl AsRef<Path>,
+        dest: &str,
+        cp: &CopyParams,
+    ) -> Result<CopySummary, CopyError> {
+        let (dest_dir, dest_name) = split_remote_path(dest)?;
+        let ap = cp.attach_params().stdin(true).stdout(false);
+        let command = ["tar", "-x", "-m", "-f", "-", "-C", dest_dir];
+        let mut process = self
+            .exec(name, command, &ap)
+            .await
+            .map_err(|err| CopyError::Exec(Box::new(err)))?;
+        let close_stdin = process.can_close_stdin();
+        let mut stdin = process.stdin().expect("stdin was requested");
+        let stderr = process.stderr().expect("stderr was requested");
+        let status = process.take_status().expect("status was not taken");
+
+        // The archive is written by a blocking task, and pumped into stdin here.
+        // Stdin is closed after the archive where the connection allows it, and otherwise stays open until
+        // the remote process exits, because closing it closes the whole connection.
+        let (archive_writer, archive_reader) = tokio::io::duplex(64 * 1024);
+        let src = src.as_ref().to_path_buf();
+        let dest_name = PathBuf::from(dest_name);
+        let (progress, no_preserve) = (cp.progress.clone(), cp.no_preserve);
+        let writer = tokio::task::spawn_blocking(move || {
+            write_archive(
+                SyncIoBridge::new(archive_writer),
+                &src,
+                &dest_name,
+                no_preserve,
+                progress,
+            )
+        });
+        // Owning the reader means that it is dropped (failing the writer) if stdin breaks
+        let pump = async move {
+            let mut archive_reader = archive_reader;
+            let pumped = tokio::io::copy(&mut archive_reader, &mut stdin).await;
+            (pumped, (!close_stdin).then_some(stdin))
+        };
+        let (summary, (pumped, stdin), stderr) = tokio::join!(writer, pump, read_stderr(stderr));
+        // A failing remote process explains local errors better than the errors themselves
+        check_status(status.await, stderr)?;
+        let summary = summary.map_err(CopyError::Spawn)??;
+        pumped.map_err(|source| CopyError::Io {
+            path: PathBuf::from(dest),
+            source,
+        })?;
+        drop(stdin);
+        process
+            .join()
+            .await
+            .map_err(|err| CopyError::Attach(Box::new(err)))?;
+        Ok(summary)
+    }
+
+    /// Copy a file or directory out of a container, like `kubectl cp <pod>:<src> <dest>`
+    ///
+    /// `src` is copied to the local path `dest`. The files are streamed as a tar archive from `tar` running in the
+    /// container, which must therefore be available in the image.
+    ///
+    /// Extraction never writes outside of `dest`: entries with absolute paths or `..` components, symlinks pointing
+    /// outside of `dest`, hard links, and special files are skipped and listed in [`CopySummary::skipped`].
+    ///
+    /// ```no_run
+    /// use k8s_openapi::api::core::v1::Pod;
+    /// use kube::{api::CopyParams, Api, Client};
+    /// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client: Client = todo!();
+    /// let pods: Api<Pod> = Api::default_namespaced(client);
+    /// let summary = pods.copy_from("example", "/var/log/app", "./logs", &CopyParams::default()).await?;
+    /// for skipped in &summary.skipped {
+    ///     println!("skipped {}", skipped.display());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn copy_from(
+        &self,
+        name: &str,
+        src: &str,
+        dest: impl AsRef<Path>,
+        cp: &CopyParams,
+    ) -> Result<CopySummary, CopyError> {
+        let (src_dir, src_name) = split_remote_path(src)?;
+        let ap = cp.attach_params();
+        let command = ["tar", "-c", "-f", "-", "-C", src_dir, src_name];
+        let mut process = self
+            .exec(name, command, &ap)
+            .await
+            .map_err(|err| CopyError::Exec(Box::new(err)))?;
+        let stdout = process.stdout().expect("stdout was requested");
+        let stderr = process.stderr().expect("stderr was requested");
+        let status = process.take_status().expect("status was not taken");
+
+        let dest = dest.as_ref().to_path_buf();
+        let src_name = PathBuf::from(src_name);
+        let (progress, no_preserve) = (cp.progress.clone(), cp.no_preserve);
+        let reader = SyncIoBridge::new(stdout);
+        let extractor = tokio::task::spawn_blocking(move || {
+            extract_archive(reader, &src_name, &dest, no_preserve, progress)
+        });
+        let (summary, stderr) = tokio::join!(extractor, read_stderr(stderr));
+        check_status(status.await, stderr)?;
+        let summary = summary.map_err(CopyError::Spawn)??;
+        process
+            .join()
+            .await
+            .map_err(|err| CopyError::Attach(Box::new(err)))?;
+        Ok(summary)
+    }
+}
+
+/// Split a remote path into the directory to run `tar` in, and the name to archive
+fn split_remote_path(path: &str) -> Result<(&str, &str), CopyError> {
+    let trimmed = path.trim_end_matches('/');
+    let (dir, name) = match trimmed.rsplit_once('/') {
+        Some(("", name)) => ("/", name),
+        Some((dir, name)) => (dir, name),
+        None => (".", trimmed),
+    };
+    if name.is_empty() || name == "." || name == ".." {
+        return Err(CopyError::InvalidRemotePath(path.to_string()));
+    }
+    Ok((dir, name))
+}
+
+async fn read_stderr(mut stderr: impl AsyncRead + Unpin) -> String {
+    let mut buf = String::new();
+    // Only used to explain failures, so a broken stderr is not an error in itself
+    let _ = stderr.read_to_string(&mut buf).await;
+    buf
+}
+
+fn check_status(status: Option<Status>, stderr: String) -> Result<(), CopyError> {
+    match status {
+        Some(status) if status.status.as_deref() != Some("Success") => {
+            let stderr = stderr.trim();
+            Err(CopyError::Remote(if stderr.is_empty() {
+                status.message.unwrap_or_default()
+            } else {
+                stderr.to_string()
+            }))
+        }
+        _ => Ok(()),
+    }
+}
+
+struct Progress {
+    callback: Option<ProgressFn>,
+    summary: CopySummary,
+}
+
+impl Progress {
+    fn new(callback: Option<ProgressFn>) -> Self {
+        Self {
+            callback,
+            summary: CopySummary::default(),
+        }
+    }
+
+    fn copied(&mut self, path: &Path, file_size: Option<u64>) {
+        if let Some(size) = file_size {
+            self.summary.files += 1;
+            self.summary.bytes += size;
+        }
+        if let Some(callback) = &self.callback {
+            callback(&CopyProgress {
+                path: path.to_path_buf(),
+                files: self.summary.files,
+                bytes: self.summary.bytes,
+            });
+        }
+    }
+}
+
+fn io_error(path: &Path) -> impl FnOnce(io::Error) -> CopyError + '_ {
+    move |source| CopyError::Io {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+/// Archive `src` under the name `name`, without following symlinks
+fn write_archive<W: Write>(
+    writer: W,
+    src: &Path,
+    name: &Path,
+    no_preserve: bool,
+    progress: Option<ProgressFn>,
+) -> Result<CopySummary, CopyError> {
+    let mut counter = CountingWriter {
+        inner: writer,
+        count: 0,
+    };
+    let mut builder = tar::Builder::new(&mut counter);
+    builder.follow_symlinks(false);
+    if no_preserve {
+        builder.mode(tar::HeaderMode::Deterministic);
+    }
+    let mut progress = Progress::new(progress);
+    let mut pending = vec![(src.to_path_buf(), name.to_path_buf())];
+    while let Some((path, name)) = pending.pop() {
+        let meta = fs::symlink_metadata(&path).map_err(io_error(&path))?;
+        if meta.is_dir() {
+            builder.append_dir(&name, &path).map_err(io_error(&path))?;
+            let mut children = fs::read_dir(&path)
+                .and_then(Iterator::collect::<io::Result<Vec<_>>>)
+                .map_err(io_error(&path))?;
+            // Reversed, so that entries are archived in order
+            children.sort_by_key(|child| std::cmp::Reverse(child.file_name()));
+            pending.extend(
+                children
+                    .into_iter()
+                    .map(|child| (child.path(), name.join(child.file_name()))),
+            );
+            progress.copied(&path, None);
+        } else {
+            builder
+                .append_path_with_name(&path, &name)
+                .map_err(io_error(&path))?;
+            progress.copied(&path, meta.is_file().then_some(meta.len()));
+        }
+    }
+    builder.finish().map_err(io_error(src))?;
+    drop(builder);
+    let padding = (TAR_RECORD_SIZE - counter.count % TAR_RECORD_SIZE) % TAR_RECORD_SIZE;
+    io::copy(&mut io::Read::take(io::repeat(0), padding), &mut counter).map_err(io_error(src))?;
+    counter.flush().map_err(io_error(src))?;
+    Ok(progress.summary)
+}
+
+struct CountingWriter<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Extract an archive of `name` into `dest`, refusing to write outside of `dest`
+fn extract_archive<R: io::Read>(
+    reader: R,
+    name: &Path,
+    dest: &Path,
+    no_preserve: bool,
+    progress: Option<ProgressFn>,
+) -> Result<CopySummary, CopyError> {
+    let mut archive = tar::Archive::new(reader);
+    archive.set_preserve_permissions(!no_preserve);
+    archive.set_preserve_mtime(!no_preserve);
+    archive.set_unpack_xattrs(false);
+
+    let dest_parent = match dest.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dest_parent).map_err(io_error(dest_parent))?;
+    let Some(dest_name) = dest.file_name() else {
+        return Err(CopyError::Io {
+            path: dest.to_path_buf(),
+            source: io::Error::new(io::ErrorKind::InvalidInput, "destination has no file name"),
+        });
+    };
+    // Canonical form of `dest`, which may not exist yet
+    let root = dest_parent
+        .canonicalize()
+        .map_err(io_error(dest_parent))?
+        .join(dest_name);
+
+    let mut progress = Progress::new(progress);
+    let mut skipped = Vec::new();
+    for entry in archive.entries().map_err(io_error(dest))? {
+        let mut entry = entry.map_err(io_error(dest))?;
+        let entry_path = entry.path().map_err(io_error(dest))?.into_owned();
+        let Some(relative) = safe_relative_path(&entry_path, name) else {
+            skipped.push(entry_path);
+            continue;
+        };
+        let target = dest.join(&relative);
+
+        let entry_type = entry.header().entry_type();
+        let safe_type = entry_type.is_symlink()
+            || entry_type.is_file()
+            || entry_type.is_dir()
+            || entry_type == tar::EntryType::Continuous;
+        if !safe_type {
+            skipped.push(entry_path);
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(io_error(parent))?;
+            // A parent could be a symlink extracted earlier, or a pre-existing one to somewhere else
+            let parent = parent.canonicalize().map_err(io_error(parent))?;
+            let inside = if relative.as_os_str().is_empty() {
+                Some(parent.as_path()) == root.parent()
+            } else {
+                parent.starts_with(&root)
+            };
+            let link_inside = if entry_type.is_symlink() {
+                let link = entry.link_name().map_err(io_error(&target))?;
+                link.and_then(|link| resolve_link(&parent, &link))
+                    .is_some_and(|resolved| resolved.starts_with(&root))
+            } else {
+                true
+            };
+            if !inside || !link_inside {
+                skipped.push(entry_path);
+                continue;
+            }
+        }
+        // Never write through an existing symlink
+        if fs::symlink_metadata(&target).is_ok_and(|meta| meta.file_type().is_symlink()) {
+            fs::remove_file(&target).map_err(io_error(&target))?;
+        }
+        let file_size = (!entry_type.is_dir() && !entry_type.is_symlink()).then(|| entry.size());
+        entry.unpack(&target).map_err(io_error(&target))?;
+        progress.copied(&target, file_size);
+    }
+    let mut summary = progress.summary;
+    summary.skipped = skipped;
+    Ok(summary)
+}
+
+/// The path of an archive entry relative to the archived `name`, if it is safe to extract
+fn safe_relative_path(entry_path: &Path, name: &Path) -> Option<PathBuf> {
+    let relative = entry_path.strip_prefix(name).ok()?;
+    let mut safe = PathBuf::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => safe.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(safe)
+}
+
+/// Resolve the target `link` of a symlink in the canonical directory `parent`
+///
+/// Symlinks that already exist on disk are followed, so links built on other links are resolved to where they
+/// really point. Returns `None` for absolute links, and for links that go up from a path that does not exist yet,
+/// since where they end up depends on what is created there later.
+fn resolve_link(parent: &Path, link: &Path) -> Option<PathBuf> {
+    let mut resolved = parent.to_path_buf();
+    let mut exists = true;
+    for component in link.components() {
+        match component {
+            Component::Normal(part) => {
+                resolved.push(part);
+                match resolved.canonicalize() {
+                    Ok(canonical) => resolved = canonical,
+                    Err(_) => exists = false,
+                }
+            }
+            Component::CurDir => {}
+            Component::ParentDir if exists => {
+                resolved.pop();
+            }
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(resolved)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{extract_archive, resolve_link, safe_relative_path, split_remote_path, write_archive};
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let tmp = std::env::temp_dir().join(format!("kube-cp-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        tmp.canonicalize().unwrap()
+    }
+
+    /// An archive of symlinks, given as `(path, target)` pairs
+    #[cfg(unix)]
+    fn symlink_archive(links: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, target) in links {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_size(0);
+            header.set_mode(0o777);
+            builder.append_link(&mut header, path, target).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn remote_paths_are_split_for_tar() {
+        assert_eq!(split_remote_path("/tmp/foo").unwrap(), ("/tmp", "foo"));
+        assert_eq!(split_remote_path("/tmp/foo/").unwrap(), ("/tmp", "foo"));
+        assert_eq!(split_remote_path("/foo").unwrap(), ("/", "foo"));
+        assert_eq!(split_remote_path("foo").unwrap(), (".", "foo"));
+        assert!(split_remote_path("/").is_err());
+        assert!(split_remote_path("/tmp/..").is_err());
+    }
+
+    #[test]
+    fn unsafe_entries_are_rejected() {
+        let name = Path::new("data");
+        assert_eq!(
+            safe_relative_path(Path::new("data/a/b"), name),
+            Some(Path::new("a/b").into())
+        );
+        assert_eq!(
+            safe_relative_path(Path::new("data"), name),
+            Some(Path::new("").into())
+        );
+        assert_eq!(safe_relative_path(Path::new("data/../etc/passwd"), name), None);
+        assert_eq!(safe_relative_path(Path::new("other/file"), name), None);
+    }
+
+    #[test]
+    fn links_are_resolved_on_disk() {
+        let tmp = temp_dir("resolve");
+        fs::create_dir_all(tmp.join("a")).unwrap();
+        let a = tmp.join("a");
+        assert_eq!(resolve_link(&a, Path::new("../b")), Some(tmp.join("b")));
+        assert_eq!(resolve_link(&a, Path::new("c/d")), Some(a.join("c/d")));
+        assert_eq!(resolve_link(&a, Path::new("/etc/passwd")), None);
+        // going up from a path that does not exist yet cannot be checked
+        assert_eq!(resolve_link(&a, Path::new("c/..")), None);
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(".", a.join("s")).unwrap();
+            assert_eq!(resolve_link(&a, Path::new("s/..")), Some(tmp.clone()));
+        }
+        fs::remove_dir_all(&tmp).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn chained_symlinks_cannot_escape() {
+        let tmp = temp_dir("chained");
+        let archive = symlink_archive(&[("data/s", "."), ("data/s/l", ".."), ("data/ok", "s")]);
+        let dest = tmp.join("dest");
+        let extracted = extract_archive(archive.as_slice(), Path::new("data"), &dest, false, None).unwrap();
+        assert_eq!(extracted.skipped, vec![PathBuf::from("data/s/l")]);
+        assert!(fs::symlink_metadata(dest.join("l")).is_err());
+        assert_eq!(fs::read_link(dest.join("ok")).unwrap(), Path::new("s"));
+        fs::remove_dir_all(&tmp).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn root_symlinks_cannot_escape() {
+        let tmp = temp_dir("root-link");
+        fs::create_dir_all(tmp.join("bar")).unwrap();
+        let archive = symlink_archive(&[("data", "bar")]);
+        let dest = tmp.join("dest");
+        let extracted = extract_archive(archive.as_slice(), Path::new("data"), &dest, false, None).unwrap();
+        assert_eq!(extracted.skipped, vec![PathBuf::from("data")]);
+        assert!(fs::symlink_metadata(&dest).is_err());
+        fs::remove_dir_all(&tmp).unwrap();
+    }
+
+    #[test]
+    fn archives_roundtrip_without_escaping() {
+        let tmp = temp_dir("roundtrip");
+        let src = tmp.join("src");
+        fs::create_dir_all(src.join("nested")).unwrap();
+        fs::write(src.join("a.txt"), "hello").unwrap();
+        fs::write(src.join("nested/b.txt"), "world!").unwrap();
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink("nested/b.txt", src.join("inside")).unwrap();
+            std::os::unix::fs::symlink("../../outside", src.join("nested/escape")).unwrap();
+        }
+
+        let mut archive = Vec::new();
+        let written = write_archive(&mut archive, &src, Path::new("data"), false, None).unwrap();
+        assert_eq!((written.files, written.bytes), (2, 11));
+        assert_eq!(archive.len() % 10240, 0);
+
+        let dest = tmp.join("dest");
+        let extracted = extract_archive(archive.as_slice(), Path::new("data"), &dest, false, None).unwrap();
+        assert_eq!((extracted.files, extracted.bytes), (2, 11));
+        assert_eq!(fs::read_to_string(dest.join("a.txt")).unwrap(), "hello");
+        assert_eq!(fs::read_to_string(dest.join("nested/b.txt")).unwrap(), "world!");
+        #[cfg(unix)]
+        {
+            assert_eq!(fs::read_to_string(dest.join("inside")).unwrap(), "world!");
+            assert!(!dest.join("nested/escape").exists());
+            assert_eq!(extracted.skipped, vec![
+                Path::new("data/nested/escape").to_path_buf()
+            ]);
+        }
+        fs::remove_dir_all(&tmp).unwrap();
+    }
+}
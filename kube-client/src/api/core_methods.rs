@@ -3,10 +3,8 @@ use futures::Stream;
 use serde::{de::DeserializeOwned, Serialize};
 use std::fmt::Debug;
 
-use crate::{api::Api, Error, Result};
-use kube_core::{
-    metadata::PartialObjectMeta, object::ObjectList, params::*, response::Status, ErrorResponse, WatchEvent,
-};
+use crate::{api::Api, client::ListPart, Error, Result};
+use kube_core::{metadata::PartialObjectMeta, object::ObjectList, params::*, response::Status, WatchEvent};
 
 /// PUSH/PUT/POST/GET abstractions
 impl<K> Api<K>
@@ -138,7 +136,7 @@ where
     pub async fn get_opt(&self, name: &str) -> Result<Option<K>> {
         match self.get(name).await {
             Ok(obj) => Ok(Some(obj)),
-            Err(Error::Api(ErrorResponse { reason, .. })) if &reason == "NotFound" => Ok(None),
+            Err(err) if err.is_not_found() => Ok(None),
             Err(err) => Err(err),
         }
     }
@@ -164,7 +162,7 @@ where
     pub async fn get_metadata_opt(&self, name: &str) -> Result<Option<PartialObjectMeta<K>>> {
         match self.get_metadata(name).await {
             Ok(meta) => Ok(Some(meta)),
-            Err(Error::Api(ErrorResponse { reason, .. })) if &reason == "NotFound" => Ok(None),
+            Err(err) if err.is_not_found() => Ok(None),
             Err(err) => Err(err),
         }
     }
@@ -220,6 +218,49 @@ where
         self.client.request::<ObjectList<PartialObjectMeta<K>>>(req).await
     }
 
+    /// Get a list of resources, decoding each object as soon as it has been received
+    ///
+    /// This is a more memory efficient alternative to [list](`Api::list`) for large lists,
+    /// since the whole response never needs to be buffered.
+    /// The stream yields the [`ListPart::Metadata`] of the list, with its continue token and resourceVersion,
+    /// followed by a [`ListPart::Item`] for each object.
+    ///
+    /// ```no_run
+    /// use kube::api::{Api, ListParams, ListPart, ResourceExt};
+    /// use k8s_openapi::api::core::v1::Pod;
+    /// use futures::{StreamExt, TryStreamExt};
+    ///
+    /// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client: kube::Client = todo!();
+    /// let pods: Api<Pod> = Api::namespaced(client, "apps");
+    /// let mut parts = pods.list_parts(&ListParams::default()).await?.boxed();
+    /// while let Some(part) = parts.try_next().await? {
+    ///     match part {
+    ///         ListPart::Metadata(meta) => println!("Listed at {:?}", meta.resource_version),
+    ///         ListPart::Item(p) => println!("Found Pod: {}", p.name_any()),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn list_parts(&self, lp: &ListParams) -> Result<impl Stream<Item = Result<ListPart<K>>>> {
+        let mut req = self.request.list(lp).map_err(Error::BuildRequest)?;
+        req.extensions_mut().insert("list");
+        self.client.request_list::<K>(req).await
+    }
+
+    /// Get a list of resources that contains only their metadata, decoding each object as soon as it has been received
+    ///
+    /// This is the incremental variant of [list_metadata](`Api::list_metadata`), like [list_parts](`Api::list_parts`).
+    pub async fn list_metadata_parts(
+        &self,
+        lp: &ListParams,
+    ) -> Result<impl Stream<Item = Result<ListPart<PartialObjectMeta<K>>>>> {
+        let mut req = self.request.list_metadata(lp).map_err(Error::BuildRequest)?;
+        req.extensions_mut().insert("list_metadata");
+        self.client.request_list::<PartialObjectMeta<K>>(req).await
+    }
+
     /// Create a resource
     ///
     /// This function requires a type that Serializes to `K`, which can be:
@@ -1,10 +1,11 @@
 //! API helpers for get-or-create and get-and-modify patterns
 //!
 //! [`Api::entry`] is the primary entry point for this API.
+//! [`Api::update_with`] and [`Api::update_status_with`] retry get-and-modify updates that conflict with other writers.
 
 // Import used in docs
 #[allow(unused_imports)] use std::collections::HashMap;
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Duration};
 
 use crate::{Api, Error, Result};
 use kube_core::{params::PostParams, Resource};
@@ -58,6 +59,130 @@ impl<K: Resource + Clone + DeserializeOwned + Debug> Api<K> {
     }
 }
 
+/// Parameters for [`Api::update_with`] and [`Api::update_status_with`]
+#[derive(Clone, Debug)]
+pub struct UpdateParams {
+    /// Parameters for each replace request
+    pub post_params: PostParams,
+    /// How many times the object is fetched and replaced before a conflict is returned
+    pub max_attempts: u32,
+    /// How long to wait after the first conflict, doubling after every conflict after that
+    pub initial_backoff: Duration,
+    /// The longest time to wait after a conflict
+    pub max_backoff: Duration,
+}
+
+impl Default for UpdateParams {
+    fn default() -> Self {
+        Self {
+            post_params: PostParams::default(),
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+impl UpdateParams {
+    /// Use `post_params` for each replace request
+    #[must_use]
+    pub fn post_params(mut self, post_params: PostParams) -> Self {
+        self.post_params = post_params;
+        self
+    }
+
+    /// Give up after `max_attempts` conflicts
+    #[must_use]
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Wait `initial` after the first conflict, doubling up to `max` for every conflict after that
+    #[must_use]
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+}
+
+impl<K: Resource + Clone + DeserializeOwned + Serialize + Debug> Api<K> {
+    /// Modify an object with optimistic concurrency, retrying when it was changed by someone else
+    ///
+    /// The object is fetched, modified by `f`, and replaced with the resourceVersion it was fetched at.
+    /// If the replace conflicts with another change, the object is fetched and modified again,
+    /// with backoff, up to [`UpdateParams::max_attempts`] times before the `409 Conflict` is returned.
+    ///
+    /// `f` can be called several times, and should only depend on the object it is given.
+    ///
+    /// ```no_run
+    /// use kube::api::{entry::UpdateParams, Api};
+    /// use k8s_openapi::api::core::v1::ConfigMap;
+    /// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client: kube::Client = todo!();
+    /// let cms: Api<ConfigMap> = Api::default_namespaced(client);
+    /// let cm = cms
+    ///     .update_with("counter", &UpdateParams::default(), |cm| {
+    ///         let data = cm.data.get_or_insert_with(Default::default);
+    ///         let count: u32 = data.get("count").and_then(|c| c.parse().ok()).unwrap_or(0);
+    ///         data.insert("count".into(), (count + 1).to_string());
+    ///     })
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn update_with(&self, name: &str, up: &UpdateParams, f: impl FnMut(&mut K)) -> Result<K> {
+        self.update_with_retries(name, up, false, f).await
+    }
+
+    /// Modify the status of an object with optimistic concurrency, retrying when it was changed by someone else
+    ///
+    /// This is like [`Api::update_with`], but fetches and replaces the object through its status subresource.
+    ///
+    /// NB: Requires that the resource has a status subresource.
+    pub async fn update_status_with(
+        &self,
+        name: &str,
+        up: &UpdateParams,
+        f: impl FnMut(&mut K),
+    ) -> Result<K> {
+        self.update_with_retries(name, up, true, f).await
+    }
+
+    async fn update_with_retries(
+        &self,
+        name: &str,
+        up: &UpdateParams,
+        status: bool,
+        mut f: impl FnMut(&mut K),
+    ) -> Result<K> {
+        let mut backoff = up.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            let result = if status {
+                let mut object = self.get_status(name).await?;
+                f(&mut object);
+                let data = serde_json::to_vec(&object).map_err(Error::SerdeError)?;
+                self.replace_status(name, &up.post_params, data).await
+            } else {
+                let mut object = self.get(name).await?;
+                f(&mut object);
+                self.replace(name, &up.post_params, &object).await
+            };
+            match result {
+                Err(err) if err.is_conflict() && attempt < up.max_attempts => {
+                    tracing::debug!(name, attempt, "update conflicted, retrying");
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(up.max_backoff);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 /// A view into a single object, with enough context to create or update it
 ///
@@ -312,8 +437,9 @@ impl<'a, K> VacantEntry<'a, K> {
 
 #[cfg(test)]
 mod tests {
-    use std::collections::BTreeMap;
+    use std::{collections::BTreeMap, pin::pin};
 
+    use http::{Method, Request, Response, StatusCode};
     use k8s_openapi::api::core::v1::ConfigMap;
     use kube_core::{
         params::{DeleteParams, PostParams},
@@ -321,9 +447,11 @@ mod tests {
     };
 
     use crate::{
-        api::entry::{CommitError, Entry},
+        api::entry::{CommitError, Entry, UpdateParams},
+        client::Body,
         Api, Client, Error,
     };
+    use tower_test::mock;
 
     #[tokio::test]
     #[ignore = "needs cluster (gets and writes cms)"]
@@ -586,4 +714,67 @@ mod tests {
         api.delete(object_name, &DeleteParams::default()).await?;
         Ok(())
     }
+
+    #[tokio::test]
+    async fn update_with_retries_conflicts() {
+        fn config_map(resource_version: &str, count: &str) -> Response<Body> {
+            let cm = serde_json::json!({
+                "apiVersion": "v1",
+                "kind": "ConfigMap",
+                "metadata": { "name": "counter", "namespace": "default", "resourceVersion": resource_version },
+                "data": { "count": count },
+            });
+            Response::builder()
+                .body(Body::from(serde_json::to_vec(&cm).unwrap()))
+                .unwrap()
+        }
+
+        let (mock_service, handle) = mock::pair::<Request<Body>, Response<Body>>();
+        let spawned = tokio::spawn(async move {
+            let mut handle = pin!(handle);
+            for (resource_version, count, conflict) in [("1", "1", true), ("2", "5", false)] {
+                let (request, send) = handle.next_request().await.expect("service not called");
+                assert_eq!(request.method(), Method::GET);
+                send.send_response(config_map(resource_version, count));
+
+                let (request, send) = handle.next_request().await.expect("service not called");
+                assert_eq!(request.method(), Method::PUT);
+                let body = request.into_body().collect_bytes().await.unwrap();
+                let cm: ConfigMap = serde_json::from_slice(&body).unwrap();
+                assert_eq!(cm.metadata.resource_version.as_deref(), Some(resource_version));
+                if conflict {
+                    let status = serde_json::json!({
+                        "status": "Failure",
+                        "message": "the object has been modified",
+                        "reason": "Conflict",
+                        "code": 409,
+                    });
+                    send.send_response(
+                        Response::builder()
+                            .status(StatusCode::CONFLICT)
+                            .body(Body::from(serde_json::to_vec(&status).unwrap()))
+                            .unwrap(),
+                    );
+                } else {
+                    let count = &cm.data.unwrap()["count"];
+                    send.send_response(config_map("3", count));
+                }
+            }
+        });
+
+        let api = Api::<ConfigMap>::default_namespaced(Client::new(mock_service, "default"));
+        let mut calls = 0;
+        let cm = api
+            .update_with("counter", &UpdateParams::default(), |cm| {
+                calls += 1;
+                let data = cm.data.get_or_insert_with(BTreeMap::default);
+                let count: u32 = data["count"].parse().unwrap();
+                data.insert("count".into(), (count + 1).to_string());
+            })
+            .await
+            .unwrap();
+        assert_eq!(calls, 2);
+        assert_eq!(cm.data.unwrap()["count"], "6");
+        spawned.await.unwrap();
+    }
 }
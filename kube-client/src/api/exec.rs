@@ -0,0 +1,308 @@
+use std::{fmt, io, time::Duration};
+
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::Status;
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+
+use crate::api::{Api, AttachParams, Execute};
+
+/// Exit codes above this are guessed to mean a signal by [`ExitStatus::inferred_signal`]
+const SIGNAL_EXIT_CODE_OFFSET: i32 = 128;
+/// Highest signal number on Linux (`SIGRTMAX`)
+const MAX_SIGNAL: i32 = 64;
+
+/// Errors from [`Api::exec_output`]
+#[derive(Debug, Error)]
+pub enum ExecError {
+    /// Failed to start the command in the container
+    #[error("failed to exec in the container: {0}")]
+    Exec(#[source] Box<crate::Error>),
+
+    /// Failed to communicate with the process in the container
+    #[error("failed to communicate with the remote process: {0}")]
+    Attach(#[source] Box<super::remote_command::Error>),
+
+    /// Failed to read the output of the process
+    #[error("failed to read output: {0}")]
+    ReadOutput(#[source] io::Error),
+
+    /// The connection was closed without reporting how the process exited
+    #[error("the connection was closed without an exit status")]
+    MissingStatus,
+
+    /// The process did not exit within [`ExecParams::timeout`], and was disconnected
+    #[error("the command did not exit within {0:?}")]
+    TimedOut(Duration),
+}
+
+/// How a process run by [`Api::exec_output`] exited
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// The process exited with an exit code, `0` on success
+    ///
+    /// Kubernetes only reports exit codes, also for processes killed by a signal.
+    /// See [`ExitStatus::inferred_signal`].
+    Exited(i32),
+    /// The command could not be run, e.g. because the executable was not found
+    Failed {
+        /// The machine readable reason from the API server, if any
+        reason: Option<String>,
+        /// The human readable description of the failure
+        message: Option<String>,
+    },
+}
+
+impl ExitStatus {
+    /// Whether the process exited with code `0`
+    pub fn success(&self) -> bool {
+        *self == Self::Exited(0)
+    }
+
+    /// The exit code of the process, if it ran
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            Self::Exited(code) => Some(*code),
+            Self::Failed { .. } => None,
+        }
+    }
+
+    /// The signal that probably killed the process, guessed from its exit code
+    ///
+    /// Shells and container runtimes report a process killed by signal `N` with exit code `128 + N`,
+    /// so this is `code - 128` for exit codes between 129 and 192. It is only a guess: a process may
+    /// also exit with such a code on its own.
+    pub fn inferred_signal(&self) -> Option<i32> {
+        self.code()
+            .filter(|&code| code > SIGNAL_EXIT_CODE_OFFSET && code <= SIGNAL_EXIT_CODE_OFFSET + MAX_SIGNAL)
+            .map(|code| code - SIGNAL_EXIT_CODE_OFFSET)
+    }
+
+    fn from_status(status: Status) -> Self {
+        if status.status.as_deref() == Some("Success") {
+            return Self::Exited(0);
+        }
+        let exit_code = status
+            .details
+            .as_ref()
+            .and_then(|details| details.causes.as_ref())
+            .and_then(|causes| causes.iter().find(|c| c.reason.as_deref() == Some("ExitCode")))
+            .and_then(|cause| cause.message.as_deref()?.parse::<i32>().ok());
+        match exit_code {
+            Some(code) => Self::Exited(code),
+            None => Self::Failed {
+                reason: status.reason,
+                message: status.message,
+            },
+        }
+    }
+}
+
+impl fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exited(code) => write!(f, "exit code {code}"),
+            Self::Failed { reason, message } => {
+                write!(f, "failed")?;
+                if let Some(reason) = reason {
+                    write!(f, " ({reason})")?;
+                }
+                if let Some(message) = message {
+                    write!(f, ": {message}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The captured result of [`Api::exec_output`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecOutput {
+    /// How the process exited
+    pub status: ExitStatus,
+    /// Everything the process wrote to stdout
+    pub stdout: Vec<u8>,
+    /// Everything the process wrote to stderr
+    pub stderr: Vec<u8>,
+}
+
+/// Parameters for [`Api::exec_output`]
+#[derive(Clone, Debug, Default)]
+pub struct ExecParams {
+    /// The container to run the command in, required for pods with multiple containers
+    pub container: Option<String>,
+    /// Input to write to the stdin of the process
+    pub stdin: Option<Vec<u8>>,
+    /// How long to wait for the process to exit
+    pub timeout: Option<Duration>,
+}
+
+impl ExecParams {
+    /// Run the command in a specific container
+    #[must_use]
+    pub fn container<T: Into<String>>(mut self, container: T) -> Self {
+        self.container = Some(container.into());
+        self
+    }
+
+    /// Write `input` to the stdin of the process
+    #[must_use]
+    pub fn stdin<T: Into<Vec<u8>>>(mut self, input: T) -> Self {
+        self.stdin = Some(input.into());
+        self
+    }
+
+    /// Disconnect and fail with [`ExecError::TimedOut`] if the process has not exited after `timeout`
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+impl<K> Api<K>
+where
+    K: Clone + DeserializeOwned + Execute,
+{
+    /// Run a command in a pod to completion, capturing its output and exit status
+    ///
+    /// Unlike [`Api::exec`], this drains stdout and stderr while the command runs, and reports a non-zero exit
+    /// code as an [`ExitStatus`] rather than an error.
+    ///
+    /// [`ExecParams::stdin`] is closed once it has been written if the connection can close stdin on its own
+    /// (see [`AttachedProcess::can_close_stdin`](super::AttachedProcess::can_close_stdin)), which is the case for
+    /// SPDY. WebSocket connections cannot, so stdin is then kept open and commands that read stdin until it ends
+    /// will not exit on their own; set [`ExecParams::timeout`] when that is possible.
+    ///
+    /// ```no_run
+    /// use k8s_openapi::api::core::v1::Pod;
+    /// use kube::{api::ExecParams, Api, Client};
+    /// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client: Client = todo!();
+    /// let pods: Api<Pod> = Api::default_namespaced(client);
+    /// let ep = ExecParams::default().timeout(std::time::Duration::from_secs(10));
+    /// let output = pods.exec_output("example", ["sh", "-c", "ls /data"], &ep).await?;
+    /// if output.status.success() {
+    ///     println!("{}", String::from_utf8_lossy(&output.stdout));
+    /// } else {
+    ///     eprintln!("ls {}: {}", output.status, String::from_utf8_lossy(&output.stderr));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn exec_output<I, T>(
+        &self,
+        name: &str,
+        command: I,
+        ep: &ExecParams,
+    ) -> Result<ExecOutput, ExecError>
+    where
+        I: IntoIterator<Item = T> + fmt::Debug,
+        T: Into<String>,
+    {
+        let ap = AttachParams {
+            container: ep.container.clone(),
+            stdin: ep.stdin.is_some(),
+            ..AttachParams::default()
+        };
+        let mut process = self
+            .exec(name, command, &ap)
+            .await
+            .map_err(|err| ExecError::Exec(Box::new(err)))?;
+        let close_stdin = process.can_close_stdin();
+        let stdin = process.stdin();
+        let stdout = process.stdout().expect("stdout was requested");
+        let stderr = process.stderr().expect("stderr was requested");
+        let status = process.take_status().expect("status was not taken");
+
+        let write_stdin = async move {
+            let mut stdin = stdin?;
+            // The process may exit without reading all of its input, which is not an error in itself
+            let _ = stdin.write_all(ep.stdin.as_deref().unwrap_or_default()).await;
+            // Otherwise, keep stdin open until the process exits, because closing it closes the whole connection
+            (!close_stdin).then_some(stdin)
+        };
+        let run = async { tokio::join!(write_stdin, read_all(stdout), read_all(stderr), status) };
+        let (stdin, stdout, stderr, status) = match ep.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, run).await {
+                Ok(output) => output,
+                Err(_) => {
+                    process.abort();
+                    return Err(ExecError::TimedOut(timeout));
+                }
+            },
+            None => run.await,
+        };
+        drop(stdin);
+        process
+            .join()
+            .await
+            .map_err(|err| ExecError::Attach(Box::new(err)))?;
+        Ok(ExecOutput {
+            status: ExitStatus::from_status(status.ok_or(ExecError::MissingStatus)?),
+            stdout: stdout.map_err(ExecError::ReadOutput)?,
+            stderr: stderr.map_err(ExecError::ReadOutput)?,
+        })
+    }
+}
+
+async fn read_all(mut reader: impl AsyncRead + Unpin) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf).await?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod test {
+    use super::ExitStatus;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::Status;
+
+    fn status(value: serde_json::Value) -> ExitStatus {
+        ExitStatus::from_status(serde_json::from_value::<Status>(value).unwrap())
+    }
+
+    #[test]
+    fn exit_status_from_status() {
+        assert_eq!(
+            status(serde_json::json!({ "status": "Success" })),
+            ExitStatus::Exited(0)
+        );
+
+        let exited = status(serde_json::json!({
+            "status": "Failure",
+            "reason": "NonZeroExitCode",
+            "message": "command terminated with non-zero exit code: error executing command [sh -c exit 3], exit code 3",
+            "details": { "causes": [{ "reason": "ExitCode", "message": "3" }] }
+        }));
+        assert_eq!(exited, ExitStatus::Exited(3));
+        assert_eq!(
+            (exited.code(), exited.inferred_signal(), exited.success()),
+            (Some(3), None, false)
+        );
+
+        let killed = status(serde_json::json!({
+            "status": "Failure",
+            "reason": "NonZeroExitCode",
+            "details": { "causes": [{ "reason": "ExitCode", "message": "137" }] }
+        }));
+        assert_eq!(killed, ExitStatus::Exited(137));
+        assert_eq!((killed.code(), killed.inferred_signal()), (Some(137), Some(9)));
+        assert_eq!(killed.to_string(), "exit code 137");
+
+        let failed = status(serde_json::json!({
+            "status": "Failure",
+            "reason": "InternalError",
+            "message": "executable file not found in $PATH",
+        }));
+        assert_eq!(failed, ExitStatus::Failed {
+            reason: Some("InternalError".into()),
+            message: Some("executable file not found in $PATH".into()),
+        });
+        assert_eq!(failed.code(), None);
+        assert_eq!(
+            failed.to_string(),
+            "failed (InternalError): executable file not found in $PATH"
+        );
+    }
+}
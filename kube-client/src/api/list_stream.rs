@@ -0,0 +1,416 @@
+use std::{
+    collections::HashSet,
+    fmt::Debug,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use futures::{future::BoxFuture, stream::BoxStream, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+
+use crate::{api::Api, client::ListPart, Error, Result};
+use kube_core::{params::ListParams, Resource};
+
+/// What [`ListStream`] does when the server expires its continue token
+///
+/// Continue tokens expire after a few minutes, so consumers that are slow to read a long list can lose their place.
+/// The server then responds with `410 Gone`, and the list can only be started again from the first page.
+///
+/// Lists started from a [`ListParams::continue_token`] of the caller are never started again,
+/// as that would list the objects before the caller's token. They always yield the `410 Gone` error and end.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnExpired {
+    /// Start again from the first page, skipping objects that were already yielded
+    ///
+    /// Every object is yielded at most once, as it was when it was first listed.
+    /// Objects yielded before the restart are not listed again, so changes to them (including deletions)
+    /// between the two lists are missed, and not reported by a watch from [`ListStream::resource_version`].
+    /// Use [`OnExpired::Relist`] when the list is followed by a watch.
+    ///
+    /// The uids of yielded objects are kept until the list ends, to recognize them after a restart.
+    #[default]
+    Restart,
+    /// Yield the `410 Gone` error, and then start again from the first page, yielding every object again
+    ///
+    /// The stream continues after the error, with [`ListStream::restarted`] set.
+    /// Objects yielded before the restart may have been deleted since,
+    /// so consumers should replace what they listed before with the objects of the new list.
+    Relist,
+    /// Yield the `410 Gone` error and end the stream
+    Fail,
+}
+
+type Parts<K> = BoxStream<'static, Result<ListPart<K>>>;
+
+enum Page<K> {
+    /// A page to fetch, starting from a continue token
+    Next(Option<String>),
+    /// A page being requested, with the continue token it was requested with
+    Fetching(Option<String>, BoxFuture<'static, Result<Parts<K>>>),
+    /// A page being read, with the continue token of the next page once the metadata has been read
+    Reading(Parts<K>, Option<String>),
+    /// The last page was read
+    Done,
+}
+
+/// A stream of objects listed across pages, created by [`Api::list_stream`]
+#[must_use = "streams do nothing unless polled"]
+pub struct ListStream<K> {
+    api: Api<K>,
+    lp: ListParams,
+    on_expired: OnExpired,
+    page: Page<K>,
+    resource_version: Option<String>,
+    restarted: bool,
+    seen: HashSet<String>,
+}
+
+/// Whether a `410 Gone` from fetching a page should start the list again
+fn can_restart(lp: &ListParams, continue_token: Option<&String>, on_expired: OnExpired) -> bool {
+    // NB: a list that started from the token of the caller cannot be restarted from its start
+    continue_token.is_some() && lp.continue_token.is_none() && on_expired != OnExpired::Fail
+}
+
+impl<K> ListStream<K> {
+    /// Set what happens when the continue token expires, instead of [`OnExpired::Restart`]
+    pub fn on_expired(mut self, on_expired: OnExpired) -> Self {
+        self.on_expired = on_expired;
+        self
+    }
+
+    /// The resourceVersion of the listed collection
+    ///
+    /// This is set once a page has been fetched, and is the version to start a follow-up watch from
+    /// when the stream has ended. If the list was restarted, it is the version of the restarted list,
+    /// and a watch from it misses changes to objects skipped by [`OnExpired::Restart`].
+    pub fn resource_version(&self) -> Option<&str> {
+        self.resource_version.as_deref()
+    }
+
+    /// Whether the list was started again after its continue token expired
+    ///
+    /// With [`OnExpired::Relist`], this is set when the `410 Gone` error of the restart is yielded.
+    pub fn restarted(&self) -> bool {
+        self.restarted
+    }
+
+    /// Whether yielded objects must be remembered, to skip them if the list is restarted
+    fn tracks_seen(&self) -> bool {
+        // NB: only paginated lists have a continue token that can expire, and lists from the caller's token are never restarted
+        self.on_expired == OnExpired::Restart && self.lp.limit.is_some() && self.lp.continue_token.is_none()
+    }
+}
+
+impl<K> Api<K>
+where
+    K: Resource + Clone + DeserializeOwned + Debug + Send + 'static,
+{
+    /// List objects lazily, fetching a page at a time
+    ///
+    /// Pages are [`ListParams::limit`] objects long, or contain every object if it is unset.
+    /// Objects are decoded as they are received, like [`Api::list_parts`], and a page is only fetched
+    /// once the objects of the previous page were consumed, starting from [`ListParams::continue_token`] if set.
+    ///
+    /// If the continue token expires before the list is finished, the list is started again
+    /// as configured by [`ListStream::on_expired`]. Lists that are followed by a watch should use
+    /// [`OnExpired::Relist`], so the watch does not miss changes to objects listed before the restart.
+    ///
+    /// ```no_run
+    /// use kube::{api::{Api, ListParams, OnExpired, ResourceExt, WatchParams}, Error};
+    /// use k8s_openapi::api::core::v1::Pod;
+    /// use futures::StreamExt;
+    ///
+    /// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client: kube::Client = todo!();
+    /// let pods: Api<Pod> = Api::namespaced(client, "apps");
+    /// let mut stream = pods
+    ///     .list_stream(&ListParams::default().limit(100))
+    ///     .on_expired(OnExpired::Relist);
+    /// let mut names = Vec::new();
+    /// while let Some(res) = stream.next().await {
+    ///     match res {
+    ///         Ok(p) => names.push(p.name_any()),
+    ///         // the list starts again, and lists every pod again
+    ///         Err(Error::Api(err)) if err.code == 410 && stream.restarted() => names.clear(),
+    ///         Err(err) => return Err(err.into()),
+    ///     }
+    /// }
+    /// let version = stream.resource_version().unwrap_or("0");
+    /// let events = pods.watch(&WatchParams::default(), version).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn list_stream(&self, lp: &ListParams) -> ListStream<K> {
+        ListStream {
+            api: self.clone(),
+            lp: lp.clone(),
+            on_expired: OnExpired::default(),
+            page: Page::Next(lp.continue_token.clone()),
+            resource_version: None,
+            restarted: false,
+            seen: HashSet::new(),
+        }
+    }
+}
+
+// NB: the pending request and response are boxed, so nothing in a `ListStream` relies on being pinned
+impl<K> Unpin for ListStream<K> {}
+
+impl<K> Stream for ListStream<K>
+where
+    K: Resource + Clone + DeserializeOwned + Debug + Send + 'static,
+{
+    type Item = Result<K>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match &mut this.page {
+                Page::Done => return Poll::Ready(None),
+                Page::Next(continue_token) => {
+                    let continue_token = continue_token.take();
+                    let api = this.api.clone();
+                    let mut lp = this.lp.clone();
+                    lp.continue_token.clone_from(&continue_token);
+                    let request = async move { api.list_parts(&lp).await.map(StreamExt::boxed) };
+                    this.page = Page::Fetching(continue_token, Box::pin(request));
+                }
+                Page::Fetching(continue_token, request) => match ready!(request.as_mut().poll(cx)) {
+                    Ok(parts) => this.page = Page::Reading(parts, None),
+                    Err(Error::Api(err))
+                        if err.code == 410
+                            && can_restart(&this.lp, continue_token.as_ref(), this.on_expired) =>
+                    {
+                        tracing::debug!("continue token expired, restarting list");
+                        this.restarted = true;
+                        this.page = Page::Next(None);
+                        if this.on_expired == OnExpired::Relist {
+                            return Poll::Ready(Some(Err(Error::Api(err))));
+                        }
+                    }
+                    Err(err) => {
+                        this.page = Page::Done;
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                },
+                Page::Reading(parts, next_token) => match ready!(parts.poll_next_unpin(cx)) {
+                    Some(Ok(ListPart::Item(obj))) => {
+                        if this.tracks_seen() && !this.seen.insert(key_of(&obj)) {
+                            continue;
+                        }
+                        return Poll::Ready(Some(Ok(obj)));
+                    }
+                    Some(Ok(ListPart::Metadata(meta))) => {
+                        if meta.resource_version.is_some() {
+                            this.resource_version = meta.resource_version;
+                        }
+                        *next_token = meta.continue_.filter(|token| !token.is_empty());
+                    }
+                    Some(Err(err)) => {
+                        this.page = Page::Done;
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                    None => {
+                        this.page = match next_token.take() {
+                            Some(token) => Page::Next(Some(token)),
+                            None => {
+                                // the list can no longer be restarted
+                                this.seen = HashSet::new();
+                                Page::Done
+                            }
+                        };
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// Identify an object across lists by its uid, or its name if it has none
+fn key_of<K: Resource>(obj: &K) -> String {
+    let meta = obj.meta();
+    match &meta.uid {
+        Some(uid) => uid.clone(),
+        None => format!(
+            "{}/{}",
+            meta.namespace.as_deref().unwrap_or_default(),
+            meta.name.as_deref().unwrap_or_default()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::pin;
+
+    use crate::{
+        api::{ListParams, OnExpired},
+        client::Body,
+        Api, Client, Error,
+    };
+    use futures::{StreamExt, TryStreamExt};
+    use http::{Request, Response, StatusCode};
+    use k8s_openapi::api::core::v1::Pod;
+    use kube_core::ResourceExt;
+    use serde_json::json;
+    use tower_test::mock;
+
+    fn page(names: &[&str], continue_token: Option<&str>, resource_version: &str) -> Response<Body> {
+        let items: Vec<_> = names
+            .iter()
+            .map(|name| json!({ "metadata": { "name": name, "uid": name } }))
+            .collect();
+        let list = json!({
+            "apiVersion": "v1",
+            "kind": "PodList",
+            "metadata": { "continue": continue_token, "resourceVersion": resource_version },
+            "items": items,
+        });
+        Response::builder()
+            .body(Body::from(serde_json::to_vec(&list).unwrap()))
+            .unwrap()
+    }
+
+    fn expired() -> Response<Body> {
+        let status = json!({
+            "kind": "Status",
+            "apiVersion": "v1",
+            "status": "Failure",
+            "message": "The provided continue parameter is too old",
+            "reason": "Expired",
+            "code": 410,
+        });
+        Response::builder()
+            .status(StatusCode::GONE)
+            .body(Body::from(serde_json::to_vec(&status).unwrap()))
+            .unwrap()
+    }
+
+    /// Serve `responses` in order, checking the continue token of each request
+    fn serve(
+        responses: Vec<(Option<&'static str>, Response<Body>)>,
+    ) -> (Client, tokio::task::JoinHandle<()>) {
+        let (mock_service, handle) = mock::pair::<Request<Body>, Response<Body>>();
+        let spawned = tokio::spawn(async move {
+            let mut handle = pin!(handle);
+            for (continue_token, response) in responses {
+                let (request, send) = handle.next_request().await.expect("service not called");
+                let query = request.uri().query().unwrap_or_default().to_string();
+                assert!(query.contains("limit=2"), "{query}");
+                match continue_token {
+                    Some(token) => assert!(query.contains(&format!("continue={token}")), "{query}"),
+                    None => assert!(!query.contains("continue="), "{query}"),
+                }
+                send.send_response(response);
+            }
+        });
+        (Client::new(mock_service, "default"), spawned)
+    }
+
+    #[tokio::test]
+    async fn lists_across_pages() {
+        let (client, spawned) = serve(vec![
+            (None, page(&["a", "b"], Some("p2"), "10")),
+            (Some("p2"), page(&["c"], None, "10")),
+        ]);
+        let pods: Api<Pod> = Api::default_namespaced(client);
+        let mut stream = pods.list_stream(&ListParams::default().limit(2));
+        let mut names = vec![];
+        while let Some(pod) = stream.try_next().await.unwrap() {
+            names.push(pod.name_any());
+        }
+        assert_eq!(names, ["a", "b", "c"]);
+        assert_eq!(stream.resource_version(), Some("10"));
+        assert!(!stream.restarted());
+        spawned.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn restarts_on_expired_continue_token() {
+        let (client, spawned) = serve(vec![
+            (None, page(&["a", "b"], Some("p2"), "10")),
+            (Some("p2"), expired()),
+            (None, page(&["a", "c"], Some("p2b"), "20")),
+            (Some("p2b"), page(&["d"], None, "20")),
+        ]);
+        let pods: Api<Pod> = Api::default_namespaced(client);
+        let mut stream = pods.list_stream(&ListParams::default().limit(2));
+        let mut names = vec![];
+        while let Some(pod) = stream.try_next().await.unwrap() {
+            names.push(pod.name_any());
+        }
+        assert_eq!(names, ["a", "b", "c", "d"]);
+        assert_eq!(stream.resource_version(), Some("20"));
+        assert!(stream.restarted());
+        assert!(stream.seen.is_empty());
+        spawned.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn relists_or_fails_on_expired_continue_token() {
+        let (client, spawned) = serve(vec![
+            (None, page(&["a", "b"], Some("p2"), "10")),
+            (Some("p2"), expired()),
+            (None, page(&["a", "c"], None, "20")),
+        ]);
+        let pods: Api<Pod> = Api::default_namespaced(client);
+        let mut stream = pods
+            .list_stream(&ListParams::default().limit(2))
+            .on_expired(OnExpired::Relist);
+        let mut names = vec![];
+        while let Some(res) = stream.next().await {
+            match res {
+                Ok(pod) => names.push(pod.name_any()),
+                Err(err) => {
+                    // the restart is announced before the objects are listed again
+                    assert!(matches!(err, Error::Api(err) if err.code == 410));
+                    assert!(stream.restarted());
+                    names.push("-".into());
+                }
+            }
+        }
+        assert_eq!(names, ["a", "b", "-", "a", "c"]);
+        spawned.await.unwrap();
+
+        let (client, spawned) = serve(vec![
+            (None, page(&["a", "b"], Some("p2"), "10")),
+            (Some("p2"), expired()),
+        ]);
+        let pods: Api<Pod> = Api::default_namespaced(client);
+        let stream = pods
+            .list_stream(&ListParams::default().limit(2))
+            .on_expired(OnExpired::Fail);
+        let err = stream.try_collect::<Vec<_>>().await.unwrap_err();
+        assert!(matches!(err, Error::Api(err) if err.code == 410));
+        spawned.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn never_restarts_from_the_callers_continue_token() {
+        for on_expired in [OnExpired::Restart, OnExpired::Relist] {
+            let (client, spawned) = serve(vec![
+                (Some("mine"), page(&["c", "d"], Some("p2"), "10")),
+                (Some("p2"), expired()),
+            ]);
+            let pods: Api<Pod> = Api::default_namespaced(client);
+            let mut stream = pods
+                .list_stream(&ListParams::default().limit(2).continue_token("mine"))
+                .on_expired(on_expired);
+            assert_eq!(stream.try_next().await.unwrap().unwrap().name_any(), "c");
+            assert_eq!(stream.try_next().await.unwrap().unwrap().name_any(), "d");
+            let err = stream.try_next().await.unwrap_err();
+            assert!(matches!(err, Error::Api(err) if err.code == 410));
+            assert!(stream.try_next().await.unwrap().is_none());
+            assert!(!stream.restarted());
+            spawned.await.unwrap();
+        }
+
+        // nor when the caller's own token has expired
+        let (client, spawned) = serve(vec![(Some("mine"), expired())]);
+        let pods: Api<Pod> = Api::default_namespaced(client);
+        let mut stream = pods.list_stream(&ListParams::default().limit(2).continue_token("mine"));
+        assert!(stream.try_next().await.is_err());
+        assert!(stream.try_next().await.unwrap().is_none());
+        spawned.await.unwrap();
+    }
+}
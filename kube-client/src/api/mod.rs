@@ -1,12 +1,28 @@
 //! API helpers for structured interaction with the Kubernetes API
 
 mod core_methods;
+mod list_stream;
+pub use crate::client::ListPart;
+pub use list_stream::{ListStream, OnExpired};
 #[cfg(feature = "ws")] mod remote_command;
 use std::fmt::Debug;
 
 #[cfg(feature = "ws")] pub use remote_command::{AttachedProcess, TerminalSize};
+#[cfg(feature = "ws")] mod exec;
+#[cfg(feature = "ws")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
+pub use exec::{ExecError, ExecOutput, ExecParams, ExitStatus};
 #[cfg(feature = "ws")] mod portforward;
 #[cfg(feature = "ws")] pub use portforward::Portforwarder;
+#[cfg(feature = "ws")] mod portforward_listener;
+#[cfg(feature = "ws")] pub use portforward_listener::PortforwardListener;
+#[cfg(feature = "ws")] mod portforward_target;
+#[cfg(feature = "ws")]
+pub use portforward_target::{PortforwardTarget, PortforwardTargetError};
+#[cfg(feature = "cp")] mod copy;
+#[cfg(feature = "cp")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cp")))]
+pub use copy::{CopyError, CopyParams, CopyProgress, CopySummary};
 
 mod subresource;
 #[cfg(feature = "ws")]
@@ -38,7 +54,7 @@ pub use params::{
     ValidationDirective, VersionMatch, WatchParams,
 };
 
-use crate::Client;
+use crate::{client::WarningHandler, Client};
 /// The generic Api abstraction
 ///
 /// This abstracts over a [`Request`] and a type `K` so that
@@ -121,6 +137,25 @@ impl<K: Resource> Api<K> {
     pub fn resource_url(&self) -> &str {
         &self.request.url_path
     }
+
+    /// Return the namespace this `Api` is scoped to, if any
+    ///
+    /// Returns `None` for cluster level resources and for `Api`s viewing all namespaces.
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    /// Set the handler for the warnings the apiserver sends with responses to this `Api`
+    ///
+    /// The warnings are passed to `handler` in addition to the handler of the client (which logs them by default),
+    /// so warnings about requests made through this `Api` can also be handled separately,
+    /// for instance collected with [`CollectWarnings`](crate::client::CollectWarnings).
+    /// Use [`Client::with_warning_handler`] to replace the handler of the client instead.
+    #[must_use]
+    pub fn with_warning_handler(mut self, handler: impl WarningHandler) -> Self {
+        self.client = self.client.with_chained_warning_handler(handler);
+        self
+    }
 }
 
 /// Api constructors for Resource implementors with Default DynamicTypes
@@ -10,6 +10,9 @@ use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, DuplexStream};
 use tokio_tungstenite::{tungstenite as ws, WebSocketStream};
 use tokio_util::io::ReaderStream;
 
+#[cfg(feature = "spdy")] use crate::client::SpdyConnection;
+use crate::client::StreamConnection;
+
 /// Errors from Portforwarder.
 #[derive(Debug, Error)]
 pub enum Error {
@@ -64,6 +67,12 @@ pub enum Error {
     /// Failed to shutdown a pod writer channel.
     #[error("failed to shutdown write to Pod channel: {0}")]
     Shutdown(#[source] std::io::Error),
+
+    /// Failed to communicate over the SPDY connection
+    #[cfg(feature = "spdy")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "spdy")))]
+    #[error("SPDY connection failed: {0}")]
+    Spdy(#[source] crate::client::SpdyError),
 }
 
 type ErrorReceiver = oneshot::Receiver<String>;
@@ -89,10 +98,7 @@ pub struct Portforwarder {
 }
 
 impl Portforwarder {
-    pub(crate) fn new<S>(stream: WebSocketStream<S>, port_nums: &[u16]) -> Self
-    where
-        S: AsyncRead + AsyncWrite + Unpin + Sized + Send + 'static,
-    {
+    pub(crate) fn new(connection: StreamConnection, port_nums: &[u16]) -> Self {
         let mut ports = HashMap::with_capacity(port_nums.len());
         let mut error_rxs = HashMap::with_capacity(port_nums.len());
         let mut error_txs = Vec::with_capacity(port_nums.len());
@@ -106,12 +112,21 @@ impl Portforwarder {
             error_rxs.insert(*port, rx);
             error_txs.push(Some(tx));
         }
-        let task = tokio::spawn(start_message_loop(
-            stream,
-            port_nums.to_vec(),
-            task_ios,
-            error_txs,
-        ));
+        let task = match connection {
+            StreamConnection::WebSocket(stream) => tokio::spawn(start_message_loop(
+                *stream,
+                port_nums.to_vec(),
+                task_ios,
+                error_txs,
+            )),
+            #[cfg(feature = "spdy")]
+            StreamConnection::Spdy(connection) => tokio::spawn(start_spdy_message_loop(
+                connection,
+                port_nums.to_vec(),
+                task_ios,
+                error_txs,
+            )),
+        };
 
         Portforwarder {
             ports,
@@ -120,6 +135,20 @@ impl Portforwarder {
         }
     }
 
+    /// Key streams and errors by `(from, to)` port pairs, e.g. to use Service ports rather than pod ports.
+    ///
+    /// Each stream can only be moved to one port, so every `from` port must occur once in `mapping`.
+    pub(crate) fn rekey(&mut self, mapping: impl Iterator<Item = (u16, u16)>) {
+        let moved = mapping
+            .filter(|(from, to)| from != to)
+            .filter_map(|(from, to)| Some((to, self.ports.remove(&from)?, self.errors.remove(&from)?)))
+            .collect::<Vec<_>>();
+        for (to, stream, error) in moved {
+            self.ports.insert(to, stream);
+            self.errors.insert(to, error);
+        }
+    }
+
     /// Take a port stream by the port on the target resource.
     ///
     /// A value is returned at most once per port.
@@ -188,6 +217,81 @@ where
     future::try_join_all(loops).await.map(|_| ())
 }
 
+// Over SPDY, each port uses a data and an error stream, identified by headers rather than a channel prefix.
+#[cfg(feature = "spdy")]
+async fn start_spdy_message_loop(
+    connection: SpdyConnection,
+    ports: Vec<u16>,
+    duplexes: Vec<DuplexStream>,
+    error_senders: Vec<Option<ErrorSender>>,
+) -> Result<(), Error> {
+    let mut forwards = Vec::with_capacity(ports.len());
+    for (request_id, ((port, duplex), error_sender)) in
+        ports.iter().zip(duplexes).zip(error_senders).enumerate()
+    {
+        let (port, request_id) = (port.to_string(), request_id.to_string());
+        let headers = |stream_type: &'static str| {
+            [
+                ("streamtype", stream_type),
+                ("port", port.as_str()),
+                ("requestid", request_id.as_str()),
+            ]
+        };
+        let mut error_stream = connection
+            .open_stream(&headers("error"))
+            .await
+            .map_err(Error::Spdy)?;
+        // Only the server writes to the error stream
+        error_stream.shutdown().await.map_err(Error::Shutdown)?;
+        let data_stream = connection
+            .open_stream(&headers("data"))
+            .await
+            .map_err(Error::Spdy)?;
+        forwards.push(forward_spdy_port(duplex, data_stream, error_stream, error_sender));
+    }
+    let result = future::try_join_all(forwards).await.map(|_| ());
+    let closed = connection.close().await.map_err(Error::Spdy);
+    result.and(closed)
+}
+
+#[cfg(feature = "spdy")]
+async fn forward_spdy_port(
+    port_stream: DuplexStream,
+    data_stream: DuplexStream,
+    mut error_stream: DuplexStream,
+    error_sender: Option<ErrorSender>,
+) -> Result<(), Error> {
+    use tokio::io::AsyncReadExt;
+
+    let (mut port_reader, mut port_writer) = tokio::io::split(port_stream);
+    let (mut data_reader, mut data_writer) = tokio::io::split(data_stream);
+    let to_pod = async {
+        tokio::io::copy(&mut port_reader, &mut data_writer)
+            .await
+            .map_err(Error::ReadBytesToSend)?;
+        data_writer.shutdown().await.map_err(Error::Shutdown)
+    };
+    let from_pod = async {
+        tokio::io::copy(&mut data_reader, &mut port_writer)
+            .await
+            .map_err(Error::WriteBytesFromPod)?;
+        port_writer.shutdown().await.map_err(Error::Shutdown)
+    };
+    let read_error = async {
+        let mut message = Vec::new();
+        // A broken error stream means the connection is gone, which is reported when closing it
+        let _ = error_stream.read_to_end(&mut message).await;
+        if !message.is_empty() {
+            if let Some(sender) = error_sender {
+                let message = String::from_utf8(message).map_err(Error::InvalidErrorMessage)?;
+                sender.send(message).map_err(Error::ForwardErrorMessage)?;
+            }
+        }
+        Ok(())
+    };
+    tokio::try_join!(to_pod, from_pod, read_error).map(|_| ())
+}
+
 async fn to_pod_loop(
     ch: u8,
     reader: tokio::io::ReadHalf<DuplexStream>,
@@ -0,0 +1,296 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use futures::{stream, StreamExt};
+use serde::de::DeserializeOwned;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, watch},
+    task::JoinHandle,
+    time::Instant,
+};
+
+use crate::{
+    api::{portforward_target, Api, Portforward, PortforwardTarget, Portforwarder},
+    Error, Result,
+};
+
+/// How long to keep trying to open a port-forward for an accepted connection, e.g. while the pod restarts.
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const INITIAL_RECONNECT_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Forwards connections accepted on local TCP ports to ports on a pod.
+///
+/// Created by [`Api::portforward_listener`] or [`Api::portforward_listener_to`]. Where a [`Portforwarder`] provides
+/// a single stream per port, this opens a new port-forward for every accepted connection, similar to
+/// `kubectl port-forward`.
+///
+/// If the pod is restarting (or is being replaced by a pod of the same name), opening the port-forward for a new
+/// connection is retried for up to 30 seconds. When forwarding to a Service or workload, each connection is sent to
+/// a ready pod, and another ready pod is picked if forwarding to the previous one fails.
+/// Connections that were open when their pod went away are closed.
+///
+/// Dropping the listener stops accepting new connections, while letting open connections finish.
+pub struct PortforwardListener {
+    local_addrs: Vec<(u16, SocketAddr)>,
+    shutdown: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl PortforwardListener {
+    /// The local address forwarding to `port` on the target.
+    ///
+    /// Useful to find the port chosen by the OS when binding to local port `0`.
+    pub fn local_addr(&self, port: u16) -> Option<SocketAddr> {
+        self.local_addrs
+            .iter()
+            .find(|(remote, _)| *remote == port)
+            .map(|(_, addr)| *addr)
+    }
+
+    /// Stop accepting connections, and wait for open connections to close.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        self.join().await;
+    }
+
+    /// Abort the listener, closing all open connections.
+    #[inline]
+    pub fn abort(&self) {
+        self.task.abort();
+    }
+
+    /// Waits for the listener to complete, after [`PortforwardListener::abort`] or [`PortforwardListener::shutdown`].
+    pub async fn join(self) {
+        // A cancelled task is what we expect after an abort, and the task is not expected to panic
+        let _ = self.task.await;
+    }
+}
+
+impl<K> Api<K>
+where
+    K: Clone + DeserializeOwned + Portforward + 'static,
+{
+    /// Bind local ports and forward each accepted connection to ports of a pod
+    ///
+    /// `ports` are pairs of `(local_port, pod_port)`, like `kubectl port-forward pod/example 8080:80`.
+    /// Listeners are bound to localhost. Use local port `0` to let the OS pick a free port, and
+    /// [`PortforwardListener::local_addr`] to find it.
+    ///
+    /// ```no_run
+    /// use k8s_openapi::api::core::v1::Pod;
+    /// use kube::{Api, Client};
+    /// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client: Client = todo!();
+    /// let pods: Api<Pod> = Api::default_namespaced(client);
+    /// let listener = pods.portforward_listener("example", &[(8080, 80)]).await?;
+    /// tokio::signal::ctrl_c().await?;
+    /// listener.shutdown().await;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn portforward_listener(
+        &self,
+        name: &str,
+        ports: &[(u16, u16)],
+    ) -> Result<PortforwardListener> {
+        self.portforward_listener_to(&PortforwardTarget::Pod(name.to_string()), ports)
+            .await
+    }
+
+    /// Bind local ports and forward each accepted connection to ports of a Service or workload
+    ///
+    /// Like [`Api::portforward_listener`], but every connection is forwarded to a ready pod backing `target`.
+    /// For a [`PortforwardTarget::Service`], the remote ports are Service ports.
+    ///
+    /// ```no_run
+    /// use k8s_openapi::api::core::v1::Pod;
+    /// use kube::{Api, Client};
+    /// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client: Client = todo!();
+    /// let pods: Api<Pod> = Api::default_namespaced(client);
+    /// let listener = pods.portforward_listener_to(&"svc/web".parse()?, &[(8080, 80)]).await?;
+    /// tokio::signal::ctrl_c().await?;
+    /// listener.shutdown().await;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn portforward_listener_to(
+        &self,
+        target: &PortforwardTarget,
+        ports: &[(u16, u16)],
+    ) -> Result<PortforwardListener> {
+        let mut listeners = Vec::with_capacity(ports.len());
+        let mut local_addrs = Vec::with_capacity(ports.len());
+        for &(local_port, pod_port) in ports {
+            let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, local_port))
+                .await
+                .map_err(|source| Error::BindLocalPort {
+                    port: local_port,
+                    source,
+                })?;
+            let local_addr = listener.local_addr().map_err(|source| Error::BindLocalPort {
+                port: local_port,
+                source,
+            })?;
+            local_addrs.push((pod_port, local_addr));
+            listeners.push((listener, pod_port));
+        }
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let task = tokio::spawn(serve(self.clone(), target.clone(), listeners, shutdown_rx));
+        Ok(PortforwardListener {
+            local_addrs,
+            shutdown: shutdown_tx,
+            task,
+        })
+    }
+}
+
+async fn serve<K>(
+    api: Api<K>,
+    target: PortforwardTarget,
+    listeners: Vec<(TcpListener, u16)>,
+    mut shutdown: watch::Receiver<bool>,
+) where
+    K: Clone + DeserializeOwned + Portforward + 'static,
+{
+    // Connections observe this being dropped when the listener is aborted
+    let (_alive_tx, alive_rx) = watch::channel(());
+    // Every connection holds a sender, so receiving `None` means that all of them have finished
+    let (open_tx, mut open_rx) = mpsc::channel::<()>(1);
+    let mut accepted = stream::select_all(listeners.into_iter().map(|(listener, port)| {
+        Box::pin(stream::unfold(listener, move |listener| async move {
+            let conn = listener.accept().await.map(|(conn, _)| (conn, port));
+            Some((conn, listener))
+        }))
+    }));
+    loop {
+        let (conn, port) = tokio::select! {
+            // Also resolves when the `PortforwardListener` is dropped
+            _ = shutdown.changed() => break,
+            conn = accepted.next() => match conn {
+                Some(Ok(conn)) => conn,
+                Some(Err(err)) => {
+                    tracing::warn!(error = &err as &dyn std::error::Error, "failed to accept connection");
+                    tokio::time::sleep(INITIAL_RECONNECT_BACKOFF).await;
+                    continue;
+                }
+                None => break,
+            },
+        };
+        let api = api.clone();
+        let target = target.clone();
+        let mut alive = alive_rx.clone();
+        let open = open_tx.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                () = forward_connection(&api, &target, port, conn) => {}
+                // Never changed, so this only resolves when the listener is aborted
+                _ = alive.changed() => {}
+            }
+            drop(open);
+        });
+    }
+    drop(accepted);
+    drop(open_tx);
+    let _ = open_rx.recv().await;
+}
+
+async fn forward_connection<K>(api: &Api<K>, target: &PortforwardTarget, port: u16, mut conn: TcpStream)
+where
+    K: Clone + DeserializeOwned + Portforward,
+{
+    let (pod, mut forwarder) = match connect(api, target, port).await {
+        Ok(connected) => connected,
+        Err(err) => {
+            tracing::warn!(
+                error = &err as &dyn std::error::Error,
+                %target,
+                port,
+                "failed to open port-forward"
+            );
+            return;
+        }
+    };
+    let name = pod.as_str();
+    let (Some(mut upstream), Some(error)) = (forwarder.take_stream(port), forwarder.take_error(port)) else {
+        return;
+    };
+    if let Err(err) = tokio::io::copy_bidirectional(&mut conn, &mut upstream).await {
+        tracing::debug!(
+            error = &err as &dyn std::error::Error,
+            pod = name,
+            port,
+            "connection closed"
+        );
+    }
+    drop(upstream);
+    if let Some(Some(msg)) = futures::FutureExt::now_or_never(error) {
+        tracing::warn!(pod = name, port, "port-forward error: {msg}");
+    }
+    if let Err(err) = forwarder.join().await {
+        tracing::debug!(
+            error = &err as &dyn std::error::Error,
+            pod = name,
+            port,
+            "port-forward failed"
+        );
+    }
+}
+
+/// Open a port-forward keyed by `port`, retrying while the pod is (re)starting
+///
+/// Returns the name of the pod that was picked for `target`, avoiding the last pod that failed.
+async fn connect<K>(api: &Api<K>, target: &PortforwardTarget, port: u16) -> Result<(String, Portforwarder)>
+where
+    K: Clone + DeserializeOwned + Portforward,
+{
+    let deadline = Instant::now() + RECONNECT_TIMEOUT;
+    let mut backoff = INITIAL_RECONNECT_BACKOFF;
+    let mut failed_pod = None;
+    loop {
+        let resolved = portforward_target::resolve(
+            &api.client,
+            api.target_namespace(),
+            target,
+            &[port],
+            failed_pod.as_deref(),
+        )
+        .await;
+        let err = match resolved {
+            Ok(resolved) => match api.portforward(&resolved.pod, &resolved.ports).await {
+                Ok(mut forwarder) => {
+                    forwarder.rekey(resolved.ports.into_iter().zip([port]));
+                    return Ok((resolved.pod, forwarder));
+                }
+                Err(err) => {
+                    failed_pod = Some(resolved.pod);
+                    err
+                }
+            },
+            Err(err) => {
+                // The failed pod may be the only one that can come back
+                failed_pod = None;
+                err
+            }
+        };
+        match err {
+            // Retrying will not help if we are not allowed to forward in the first place
+            Error::Api(resp) if resp.code == 401 || resp.code == 403 => return Err(Error::Api(resp)),
+            err if Instant::now() + backoff < deadline => {
+                tracing::debug!(
+                    error = &err as &dyn std::error::Error,
+                    %target,
+                    port,
+                    "retrying port-forward"
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+            }
+            err => return Err(err),
+        }
+    }
+}
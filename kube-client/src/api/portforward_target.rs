@@ -0,0 +1,365 @@
+use std::{collections::HashSet, fmt, str::FromStr};
+
+use k8s_openapi::{
+    api::{
+        apps::v1::{Deployment, ReplicaSet, StatefulSet},
+        core::v1::{Pod, Service},
+    },
+    apimachinery::pkg::{apis::meta::v1::LabelSelector, util::intstr::IntOrString},
+};
+use kube_core::{params::ListParams, ParseExpressionError, Selector};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+use crate::{
+    api::{Api, Portforward, Portforwarder},
+    Client, Error, Result,
+};
+
+/// A resource to port-forward to, like the `TYPE/NAME` argument of `kubectl port-forward`
+///
+/// Anything but a [`PortforwardTarget::Pod`] is resolved to one of its ready pods.
+/// For a [`PortforwardTarget::Service`], the requested ports are Service ports, which are mapped to the
+/// (possibly named) `targetPort` on the pod.
+///
+/// Can be parsed from the kubectl syntax:
+///
+/// ```
+/// use kube::api::PortforwardTarget;
+/// let target: PortforwardTarget = "svc/web".parse().unwrap();
+/// assert_eq!(target, PortforwardTarget::Service("web".into()));
+/// let target: PortforwardTarget = "web-0".parse().unwrap();
+/// assert_eq!(target, PortforwardTarget::Pod("web-0".into()));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PortforwardTarget {
+    /// A pod by name
+    Pod(String),
+    /// A ready pod selected by a Service
+    Service(String),
+    /// A ready pod of a Deployment
+    Deployment(String),
+    /// A ready pod of a StatefulSet
+    StatefulSet(String),
+    /// A ready pod of a ReplicaSet
+    ReplicaSet(String),
+}
+
+impl fmt::Display for PortforwardTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pod(name) => write!(f, "pod/{name}"),
+            Self::Service(name) => write!(f, "service/{name}"),
+            Self::Deployment(name) => write!(f, "deployment/{name}"),
+            Self::StatefulSet(name) => write!(f, "statefulset/{name}"),
+            Self::ReplicaSet(name) => write!(f, "replicaset/{name}"),
+        }
+    }
+}
+
+impl FromStr for PortforwardTarget {
+    type Err = PortforwardTargetError;
+
+    fn from_str(s: &str) -> Result<Self, PortforwardTargetError> {
+        let Some((kind, name)) = s.split_once('/') else {
+            return Ok(Self::Pod(s.to_string()));
+        };
+        let name = name.to_string();
+        match kind.to_ascii_lowercase().as_str() {
+            "po" | "pod" | "pods" => Ok(Self::Pod(name)),
+            "svc" | "service" | "services" => Ok(Self::Service(name)),
+            "deploy" | "deployment" | "deployments" => Ok(Self::Deployment(name)),
+            "sts" | "statefulset" | "statefulsets" => Ok(Self::StatefulSet(name)),
+            "rs" | "replicaset" | "replicasets" => Ok(Self::ReplicaSet(name)),
+            _ => Err(PortforwardTargetError::UnknownKind(kind.to_string())),
+        }
+    }
+}
+
+/// Errors from resolving a [`PortforwardTarget`] to a pod
+#[derive(Debug, Error)]
+pub enum PortforwardTargetError {
+    /// The target could not be parsed, because its resource type cannot be port-forwarded to
+    #[error("cannot port-forward to a resource of type {0:?}")]
+    UnknownKind(String),
+
+    /// The target does not select any pods
+    #[error("{0} has no pod selector")]
+    NoSelector(PortforwardTarget),
+
+    /// The pod selector of the target could not be parsed
+    #[error("{target} has an invalid selector: {source}")]
+    InvalidSelector {
+        /// The target with the invalid selector.
+        target: PortforwardTarget,
+        /// The underlying parse error.
+        #[source]
+        source: ParseExpressionError,
+    },
+
+    /// The target has no ready pods to forward to
+    #[error("{0} has no ready pods")]
+    NoReadyPods(PortforwardTarget),
+
+    /// A requested port is not exposed by the Service
+    #[error("{target} does not expose port {port}")]
+    UnknownPort {
+        /// The Service that was requested.
+        target: PortforwardTarget,
+        /// The requested Service port.
+        port: u16,
+    },
+
+    /// A `targetPort` of the Service is not exposed by the pod picked for it
+    #[error("pod {pod} does not expose the target ports of {target}")]
+    UnknownTargetPort {
+        /// The Service that was requested.
+        target: PortforwardTarget,
+        /// The pod that was picked for the Service.
+        pod: String,
+    },
+
+    /// Several requested ports map to the same port on the pod, which can only be forwarded once
+    #[error("several requested ports of {target} map to port {port} on the pod")]
+    SharedPodPort {
+        /// The target that was requested.
+        target: PortforwardTarget,
+        /// The port on the pod.
+        port: u16,
+    },
+}
+
+/// A pod picked to serve a [`PortforwardTarget`]
+pub(crate) struct ResolvedTarget {
+    pub(crate) pod: String,
+    /// The requested ports mapped to ports on the pod, in the requested order
+    pub(crate) ports: Vec<u16>,
+}
+
+/// Pick a ready pod for `target`, skipping the pod named `exclude`
+pub(crate) async fn resolve(
+    client: &Client,
+    namespace: &str,
+    target: &PortforwardTarget,
+    ports: &[u16],
+    exclude: Option<&str>,
+) -> Result<ResolvedTarget> {
+    let (selector, target_ports) = match target {
+        PortforwardTarget::Pod(name) => {
+            return Ok(ResolvedTarget {
+                pod: name.clone(),
+                ports: ports.to_vec(),
+            })
+        }
+        PortforwardTarget::Service(name) => {
+            let svc: Service = Api::namespaced(client.clone(), namespace).get(name).await?;
+            let spec = svc.spec.unwrap_or_default();
+            let selector: Selector = spec.selector.unwrap_or_default().into_iter().collect();
+            if selector.selects_all() {
+                return Err(Error::PortforwardTarget(PortforwardTargetError::NoSelector(
+                    target.clone(),
+                )));
+            }
+            let svc_ports = spec.ports.unwrap_or_default();
+            let target_ports = ports
+                .iter()
+                .map(|&port| {
+                    let svc_port = svc_ports
+                        .iter()
+                        .find(|p| p.port == i32::from(port))
+                        .ok_or_else(|| {
+                            Error::PortforwardTarget(PortforwardTargetError::UnknownPort {
+                                target: target.clone(),
+                                port,
+                            })
+                        })?;
+                    Ok(svc_port
+                        .target_port
+                        .clone()
+                        .unwrap_or(IntOrString::Int(svc_port.port)))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            (selector, Some(target_ports))
+        }
+        PortforwardTarget::Deployment(name) => {
+            let deploy: Deployment = Api::namespaced(client.clone(), namespace).get(name).await?;
+            (workload_selector(target, deploy.spec.map(|s| s.selector))?, None)
+        }
+        PortforwardTarget::StatefulSet(name) => {
+            let sts: StatefulSet = Api::namespaced(client.clone(), namespace).get(name).await?;
+            (workload_selector(target, sts.spec.map(|s| s.selector))?, None)
+        }
+        PortforwardTarget::ReplicaSet(name) => {
+            let rs: ReplicaSet = Api::namespaced(client.clone(), namespace).get(name).await?;
+            (workload_selector(target, rs.spec.map(|s| s.selector))?, None)
+        }
+    };
+
+    let pods: Api<Pod> = Api::namespaced(client.clone(), namespace);
+    let pod = pods
+        .list(&ListParams::default().labels_from(&selector))
+        .await?
+        .items
+        .into_iter()
+        .find(|pod| is_ready(pod) && pod.metadata.name.as_deref() != exclude)
+        .ok_or_else(|| Error::PortforwardTarget(PortforwardTargetError::NoReadyPods(target.clone())))?;
+    let pod_name = pod.metadata.name.clone().unwrap_or_default();
+
+    let ports = match target_ports {
+        None => ports.to_vec(),
+        Some(target_ports) => target_ports
+            .iter()
+            .map(|target_port| pod_port(&pod, target_port))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| {
+                Error::PortforwardTarget(PortforwardTargetError::UnknownTargetPort {
+                    target: target.clone(),
+                    pod: pod_name.clone(),
+                })
+            })?,
+    };
+    Ok(ResolvedTarget { pod: pod_name, ports })
+}
+
+fn workload_selector(target: &PortforwardTarget, selector: Option<LabelSelector>) -> Result<Selector> {
+    let selector = Selector::try_from(selector.unwrap_or_default()).map_err(|source| {
+        Error::PortforwardTarget(PortforwardTargetError::InvalidSelector {
+            target: target.clone(),
+            source,
+        })
+    })?;
+    if selector.selects_all() {
+        return Err(Error::PortforwardTarget(PortforwardTargetError::NoSelector(
+            target.clone(),
+        )));
+    }
+    Ok(selector)
+}
+
+/// The first port that occurs more than once in `ports`
+///
+/// The stream to a pod port can only be taken once, so it cannot serve several requested ports.
+fn shared_port(ports: &[u16]) -> Option<u16> {
+    let mut seen = HashSet::new();
+    ports.iter().copied().find(|&port| !seen.insert(port))
+}
+
+fn is_ready(pod: &Pod) -> bool {
+    pod.metadata.deletion_timestamp.is_none()
+        && pod
+            .status
+            .as_ref()
+            .and_then(|status| status.conditions.as_ref())
+            .is_some_and(|conds| conds.iter().any(|c| c.type_ == "Ready" && c.status == "True"))
+}
+
+/// Map a Service `targetPort` to a port on the pod, looking up named ports in the pod's containers
+fn pod_port(pod: &Pod, target_port: &IntOrString) -> Option<u16> {
+    match target_port {
+        IntOrString::Int(port) => u16::try_from(*port).ok(),
+        IntOrString::String(name) => pod
+            .spec
+            .as_ref()?
+            .containers
+            .iter()
+            .flat_map(|c| c.ports.iter().flatten())
+            .find(|p| p.name.as_deref() == Some(name.as_str()))
+            .and_then(|p| u16::try_from(p.container_port).ok()),
+    }
+}
+
+impl<K> Api<K>
+where
+    K: Clone + DeserializeOwned + Portforward,
+{
+    /// Forward ports of a pod backing a Service or workload
+    ///
+    /// Picks a ready pod for `target`, like `kubectl port-forward svc/web 80`. Streams of the returned
+    /// [`Portforwarder`] are keyed by the requested `ports`, even when they map to different ports on the pod.
+    /// Requesting several Service ports that map to the same port on the pod fails with
+    /// [`PortforwardTargetError::SharedPodPort`].
+    ///
+    /// The pod is only picked once. Use [`Api::portforward_listener_to`] to fail over to another ready pod
+    /// when the chosen pod goes away.
+    ///
+    /// ```no_run
+    /// use k8s_openapi::api::core::v1::Pod;
+    /// use kube::{api::PortforwardTarget, Api, Client};
+    /// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client: Client = todo!();
+    /// let pods: Api<Pod> = Api::default_namespaced(client);
+    /// let mut pf = pods.portforward_to(&PortforwardTarget::Service("web".into()), &[80]).await?;
+    /// let stream = pf.take_stream(80).unwrap();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn portforward_to(&self, target: &PortforwardTarget, ports: &[u16]) -> Result<Portforwarder> {
+        let resolved = resolve(&self.client, self.target_namespace(), target, ports, None).await?;
+        if let Some(port) = shared_port(&resolved.ports) {
+            return Err(Error::PortforwardTarget(PortforwardTargetError::SharedPodPort {
+                target: target.clone(),
+                port,
+            }));
+        }
+        let mut forwarder = self.portforward(&resolved.pod, &resolved.ports).await?;
+        forwarder.rekey(resolved.ports.iter().copied().zip(ports.iter().copied()));
+        Ok(forwarder)
+    }
+
+    /// The namespace that port-forward targets are looked up in
+    pub(crate) fn target_namespace(&self) -> &str {
+        self.namespace
+            .as_deref()
+            .unwrap_or_else(|| self.client.default_namespace())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{pod_port, shared_port, PortforwardTarget, PortforwardTargetError};
+    use k8s_openapi::{api::core::v1::Pod, apimachinery::pkg::util::intstr::IntOrString};
+
+    #[test]
+    fn targets_parse_like_kubectl() {
+        let parse = |s: &str| s.parse::<PortforwardTarget>().unwrap();
+        assert_eq!(parse("web-0"), PortforwardTarget::Pod("web-0".into()));
+        assert_eq!(parse("pod/web-0"), PortforwardTarget::Pod("web-0".into()));
+        assert_eq!(parse("svc/web"), PortforwardTarget::Service("web".into()));
+        assert_eq!(parse("deploy/web"), PortforwardTarget::Deployment("web".into()));
+        assert_eq!(
+            parse("statefulset/web"),
+            PortforwardTarget::StatefulSet("web".into())
+        );
+        assert_eq!(parse("rs/web"), PortforwardTarget::ReplicaSet("web".into()));
+        assert!(matches!(
+            "cm/web".parse::<PortforwardTarget>(),
+            Err(PortforwardTargetError::UnknownKind(kind)) if kind == "cm"
+        ));
+        assert_eq!(
+            parse(&PortforwardTarget::Service("web".into()).to_string()),
+            parse("svc/web")
+        );
+    }
+
+    #[test]
+    fn named_target_ports_resolve_to_container_ports() {
+        let pod: Pod = serde_json::from_value(serde_json::json!({
+            "metadata": { "name": "web-0" },
+            "spec": { "containers": [
+                { "name": "app", "ports": [{ "name": "http", "containerPort": 8080 }] },
+                { "name": "sidecar", "ports": [{ "name": "metrics", "containerPort": 9090 }] },
+            ]}
+        }))
+        .unwrap();
+        assert_eq!(pod_port(&pod, &IntOrString::String("http".into())), Some(8080));
+        assert_eq!(pod_port(&pod, &IntOrString::String("metrics".into())), Some(9090));
+        assert_eq!(pod_port(&pod, &IntOrString::String("grpc".into())), None);
+        assert_eq!(pod_port(&pod, &IntOrString::Int(3000)), Some(3000));
+    }
+
+    #[test]
+    fn shared_pod_ports_are_found() {
+        assert_eq!(shared_port(&[8080, 9090]), None);
+        assert_eq!(shared_port(&[8080, 9090, 8080]), Some(8080));
+    }
+}
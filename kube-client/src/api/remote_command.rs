@@ -18,6 +18,8 @@ use tokio_tungstenite::{
 };
 
 use super::AttachParams;
+#[cfg(feature = "spdy")] use crate::client::SpdyConnection;
+use crate::client::StreamConnection;
 
 type StatusReceiver = oneshot::Receiver<Status>;
 type StatusSender = oneshot::Sender<Status>;
@@ -86,6 +88,12 @@ pub enum Error {
     /// Failed to set terminal size, tty need to be true to resize the terminal
     #[error("failed to set terminal size, tty need to be true to resize the terminal")]
     TtyNeedToBeTrue,
+
+    /// Failed to communicate over the SPDY connection
+    #[cfg(feature = "spdy")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "spdy")))]
+    #[error("SPDY connection failed: {0}")]
+    Spdy(#[source] crate::client::SpdyError),
 }
 
 const MAX_BUF_SIZE: usize = 1024;
@@ -103,6 +111,7 @@ pub struct AttachedProcess {
     has_stdin: bool,
     has_stdout: bool,
     has_stderr: bool,
+    can_close_stdin: bool,
     stdin_writer: Option<DuplexStream>,
     stdout_reader: Option<DuplexStream>,
     stderr_reader: Option<DuplexStream>,
@@ -112,10 +121,7 @@ pub struct AttachedProcess {
 }
 
 impl AttachedProcess {
-    pub(crate) fn new<S>(stream: WebSocketStream<S>, ap: &AttachParams) -> Self
-    where
-        S: AsyncRead + AsyncWrite + Unpin + Sized + Send + 'static,
-    {
+    pub(crate) fn new(connection: StreamConnection, ap: &AttachParams) -> Self {
         // To simplify the implementation, always create a pipe for stdin.
         // The caller does not have access to it unless they had requested.
         let (stdin_writer, stdin_reader) = tokio::io::duplex(ap.max_stdin_buf_size.unwrap_or(MAX_BUF_SIZE));
@@ -139,19 +145,32 @@ impl AttachedProcess {
             (None, None)
         };
 
-        let task = tokio::spawn(start_message_loop(
-            stream,
-            stdin_reader,
-            stdout_writer,
-            stderr_writer,
-            status_tx,
-            terminal_resize_rx,
-        ));
+        let can_close_stdin = !matches!(connection, StreamConnection::WebSocket(_));
+        let task = match connection {
+            StreamConnection::WebSocket(stream) => tokio::spawn(start_message_loop(
+                *stream,
+                stdin_reader,
+                stdout_writer,
+                stderr_writer,
+                status_tx,
+                terminal_resize_rx,
+            )),
+            #[cfg(feature = "spdy")]
+            StreamConnection::Spdy(connection) => tokio::spawn(start_spdy_message_loop(
+                connection,
+                ap.stdin.then_some(stdin_reader),
+                stdout_writer,
+                stderr_writer,
+                status_tx,
+                terminal_resize_rx,
+            )),
+        };
 
         AttachedProcess {
             has_stdin: ap.stdin,
             has_stdout: ap.stdout,
             has_stderr: ap.stderr,
+            can_close_stdin,
             task,
             stdin_writer: Some(stdin_writer),
             stdout_reader,
@@ -173,6 +192,8 @@ impl AttachedProcess {
     /// # }
     /// ```
     /// Only available if [`AttachParams`](super::AttachParams) had `stdin`.
+    ///
+    /// Dropping the writer closes stdin if [`AttachedProcess::can_close_stdin`], and the whole connection otherwise.
     pub fn stdin(&mut self) -> Option<impl AsyncWrite + Unpin> {
         if !self.has_stdin {
             return None;
@@ -180,6 +201,13 @@ impl AttachedProcess {
         self.stdin_writer.take()
     }
 
+    /// Whether stdin can be closed on its own, so the process sees the end of its input
+    ///
+    /// This is the case for SPDY connections. WebSocket connections are closed entirely when stdin is closed.
+    pub fn can_close_stdin(&self) -> bool {
+        self.can_close_stdin
+    }
+
     /// Async reader for stdout outputs.
     /// ```no_run
     /// # use kube_client::api::AttachedProcess;
@@ -363,6 +391,111 @@ where
     Ok(())
 }
 
+// Over SPDY, each channel is a separate stream, named by its `streamtype` header.
+// Stdin can be closed on its own, and the connection is closed by the server when the process exits.
+#[cfg(feature = "spdy")]
+async fn start_spdy_message_loop(
+    connection: SpdyConnection,
+    stdin: Option<impl AsyncRead + Unpin>,
+    stdout: Option<impl AsyncWrite + Unpin>,
+    stderr: Option<impl AsyncWrite + Unpin>,
+    status_tx: StatusSender,
+    terminal_size_rx: Option<TerminalSizeReceiver>,
+) -> Result<(), Error> {
+    use tokio::io::AsyncReadExt;
+
+    let open = |stream_type: &'static str| {
+        let connection = &connection;
+        async move {
+            connection
+                .open_stream(&[("streamtype", stream_type)])
+                .await
+                .map_err(Error::Spdy)
+        }
+    };
+    let open_if = |requested: bool, stream_type| async move {
+        if requested {
+            open(stream_type).await.map(Some)
+        } else {
+            Ok(None)
+        }
+    };
+    let mut error_stream = open("error").await?;
+    let stdin_stream = open_if(stdin.is_some(), "stdin").await?;
+    let stdout_stream = open_if(stdout.is_some(), "stdout").await?;
+    let stderr_stream = open_if(stderr.is_some(), "stderr").await?;
+    let resize_stream = open_if(terminal_size_rx.is_some(), "resize").await?;
+
+    let read_status = async move {
+        let mut status = Vec::new();
+        error_stream
+            .read_to_end(&mut status)
+            .await
+            .map_err(|err| Error::Spdy(err.into()))?;
+        if !status.is_empty() {
+            let status = serde_json::from_slice::<Status>(&status).map_err(Error::DeserializeStatus)?;
+            status_tx.send(status).map_err(|_| Error::SendStatus)?;
+        }
+        Ok(())
+    };
+    let read_stdout = async {
+        if let (Some(mut from), Some(mut to)) = (stdout_stream, stdout) {
+            tokio::io::copy(&mut from, &mut to)
+                .await
+                .map_err(Error::WriteStdout)?;
+        }
+        Ok(())
+    };
+    let read_stderr = async {
+        if let (Some(mut from), Some(mut to)) = (stderr_stream, stderr) {
+            tokio::io::copy(&mut from, &mut to)
+                .await
+                .map_err(Error::WriteStderr)?;
+        }
+        Ok(())
+    };
+    let write_stdin = async {
+        let (Some(stdin), Some(mut to)) = (stdin, stdin_stream) else {
+            return Ok(());
+        };
+        let mut stdin = tokio_util::io::ReaderStream::new(stdin);
+        while let Some(bytes) = stdin.next().await.transpose().map_err(Error::ReadStdin)? {
+            // The stream only fails when the connection is closed, which is reported when closing it
+            if to.write_all(&bytes).await.is_err() {
+                return Ok(());
+            }
+        }
+        // Closing stdin is passed on to the process
+        let _ = to.shutdown().await;
+        Ok(())
+    };
+    let write_terminal_size = async {
+        let (Some(mut sizes), Some(mut to)) = (terminal_size_rx, resize_stream) else {
+            return Ok(());
+        };
+        while let Some(size) = sizes.next().await {
+            let size = serde_json::to_vec(&size).map_err(Error::SerializeTerminalSize)?;
+            if to.write_all(&size).await.is_err() {
+                break;
+            }
+        }
+        Ok(())
+    };
+
+    let read = async { tokio::try_join!(read_status, read_stdout, read_stderr).map(|_| ()) };
+    // Input only ends with the process, when everything has been read
+    let write = async {
+        tokio::try_join!(write_stdin, write_terminal_size)?;
+        futures::future::pending().await
+    };
+    let result = select! {
+        res = read => res,
+        res = write => res,
+    };
+    let closed = connection.close().await.map_err(Error::Spdy);
+    result.and(closed)
+}
+
 /// Channeled messages from the server.
 enum Message {
     /// To Stdout channel (1)
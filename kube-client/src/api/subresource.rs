@@ -510,8 +510,8 @@ where
     pub async fn attach(&self, name: &str, ap: &AttachParams) -> Result<AttachedProcess> {
         let mut req = self.request.attach(name, ap).map_err(Error::BuildRequest)?;
         req.extensions_mut().insert("attach");
-        let stream = self.client.connect(req).await?;
-        Ok(AttachedProcess::new(stream, ap))
+        let connection = self.client.connect_remote_command(req).await?;
+        Ok(AttachedProcess::new(connection, ap))
     }
 }
 
@@ -565,8 +565,8 @@ where
             .exec(name, command, ap)
             .map_err(Error::BuildRequest)?;
         req.extensions_mut().insert("exec");
-        let stream = self.client.connect(req).await?;
-        Ok(AttachedProcess::new(stream, ap))
+        let connection = self.client.connect_remote_command(req).await?;
+        Ok(AttachedProcess::new(connection, ap))
     }
 }
 
@@ -606,7 +606,7 @@ where
             .request
             .portforward(name, ports)
             .map_err(Error::BuildRequest)?;
-        let stream = self.client.connect(req).await?;
-        Ok(Portforwarder::new(stream, ports))
+        let connection = self.client.connect_portforward(req).await?;
+        Ok(Portforwarder::new(connection, ports))
     }
 }
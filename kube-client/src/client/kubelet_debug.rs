@@ -31,8 +31,8 @@ impl Client {
         let mut req =
             Request::kubelet_node_attach(kubelet_params, container, ap).map_err(Error::BuildRequest)?;
         req.extensions_mut().insert("kubelet_node_attach");
-        let stream = self.connect(req).await?;
-        Ok(AttachedProcess::new(stream, ap))
+        let connection = self.connect_remote_command(req).await?;
+        Ok(AttachedProcess::new(connection, ap))
     }
 
     /// Execute a command in a pod directly from the node
@@ -53,8 +53,8 @@ impl Client {
         let mut req = Request::kubelet_node_exec(kubelet_params, container, command, ap)
             .map_err(Error::BuildRequest)?;
         req.extensions_mut().insert("kubelet_node_exec");
-        let stream = self.connect(req).await?;
-        Ok(AttachedProcess::new(stream, ap))
+        let connection = self.connect_remote_command(req).await?;
+        Ok(AttachedProcess::new(connection, ap))
     }
 
     /// Forward ports of a pod directly from the node
@@ -69,8 +69,8 @@ impl Client {
         let mut req =
             Request::kubelet_node_portforward(kubelet_params, ports).map_err(Error::BuildRequest)?;
         req.extensions_mut().insert("kubelet_node_portforward");
-        let stream = self.connect(req).await?;
-        Ok(Portforwarder::new(stream, ports))
+        let connection = self.connect_portforward(req).await?;
+        Ok(Portforwarder::new(connection, ports))
     }
 
     /// Stream logs directly from node
@@ -0,0 +1,302 @@
+//! Incremental decoding of list responses
+use std::marker::PhantomData;
+
+use bytes::{Buf, BytesMut};
+use kube_core::metadata::ListMeta;
+use serde::de::{DeserializeOwned, Error as _};
+use tokio_util::codec::Decoder;
+
+/// A part of a list response, decoded as soon as it has been received
+#[derive(Clone, Debug)]
+pub enum ListPart<K> {
+    /// The metadata of the list, with its resourceVersion and continue token
+    ///
+    /// The apiserver sends this before the items.
+    Metadata(ListMeta),
+    /// An object in the list
+    Item(K),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    /// Before the opening brace of the list
+    Start,
+    /// Before the next field of the list, or its closing brace
+    BeforeKey,
+    /// In the name of a field
+    Key,
+    /// Between the name of a field and its value
+    BeforeColon,
+    /// Before the value of a field
+    BeforeValue,
+    /// In the value of a field other than `items`
+    Value,
+    /// After the value of a field
+    AfterValue,
+    /// Before the next item, or the end of the items
+    BeforeItem,
+    /// In an item
+    Item,
+    /// After an item
+    AfterItem,
+    /// After the closing brace of the list
+    End,
+    /// The response is not a valid list, and the rest of it is ignored
+    Failed,
+}
+
+/// Finds the end of a JSON value, possibly across several chunks
+#[derive(Default)]
+struct Scanner {
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+}
+
+impl Scanner {
+    /// The length of the value at the start of `buf`, continuing from the bytes before `from` that were scanned
+    fn scan(&mut self, buf: &[u8], from: usize) -> Option<usize> {
+        for (i, &b) in buf.iter().enumerate().skip(from) {
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if b == b'\\' {
+                    self.escaped = true;
+                } else if b == b'"' {
+                    self.in_string = false;
+                    if self.depth == 0 {
+                        return Some(i + 1);
+                    }
+                }
+                continue;
+            }
+            match b {
+                b'"' => self.in_string = true,
+                b'{' | b'[' => self.depth += 1,
+                // NB: a closing bracket at depth 0 ends a number or literal, and belongs to the enclosing value
+                b'}' | b']' if self.depth == 0 => return Some(i),
+                b'}' | b']' => {
+                    self.depth -= 1;
+                    if self.depth == 0 {
+                        return Some(i + 1);
+                    }
+                }
+                b',' if self.depth == 0 => return Some(i),
+                b if b.is_ascii_whitespace() && self.depth == 0 => return Some(i),
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+/// Decodes the items of a list response as they arrive, rather than buffering the whole list
+///
+/// Only the fields of the list itself are tracked, so the memory needed is bounded by the size of
+/// the largest item. Items that fail to deserialize are yielded as errors without ending the list.
+pub(crate) struct ListDecoder<K> {
+    state: State,
+    /// How far the value at the start of the buffer has been scanned
+    scanned: usize,
+    scanner: Scanner,
+    key: String,
+    _item: PhantomData<fn() -> K>,
+}
+
+impl<K> Default for ListDecoder<K> {
+    fn default() -> Self {
+        Self {
+            state: State::Start,
+            scanned: 0,
+            scanner: Scanner::default(),
+            key: String::new(),
+            _item: PhantomData,
+        }
+    }
+}
+
+impl<K> ListDecoder<K> {
+    fn fail(&mut self, src: &mut BytesMut, msg: &str) -> Option<Result<ListPart<K>, serde_json::Error>> {
+        self.state = State::Failed;
+        src.clear();
+        Some(Err(serde_json::Error::custom(msg)))
+    }
+
+    /// Start scanning the value at the start of the buffer
+    fn start_value(&mut self, state: State) {
+        self.state = state;
+        self.scanned = 0;
+        self.scanner = Scanner::default();
+    }
+}
+
+impl<K: DeserializeOwned> Decoder for ListDecoder<K> {
+    type Error = std::io::Error;
+    type Item = Result<ListPart<K>, serde_json::Error>;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            match self.state {
+                State::End | State::Failed => {
+                    src.clear();
+                    return Ok(None);
+                }
+                State::Key | State::Value | State::Item => {
+                    let Some(len) = self.scanner.scan(src, self.scanned) else {
+                        self.scanned = src.len();
+                        return Ok(None);
+                    };
+                    let value = src.split_to(len);
+                    match self.state {
+                        State::Key => match serde_json::from_slice(&value) {
+                            Ok(key) => {
+                                self.key = key;
+                                self.state = State::BeforeColon;
+                            }
+                            Err(err) => {
+                                self.state = State::Failed;
+                                return Ok(Some(Err(err)));
+                            }
+                        },
+                        State::Item => {
+                            self.state = State::AfterItem;
+                            return Ok(Some(serde_json::from_slice(&value).map(ListPart::Item)));
+                        }
+                        _ => {
+                            self.state = State::AfterValue;
+                            if self.key == "metadata" {
+                                return Ok(Some(serde_json::from_slice(&value).map(ListPart::Metadata)));
+                            }
+                        }
+                    }
+                }
+                state => {
+                    let Some(start) = src.iter().position(|b| !b.is_ascii_whitespace()) else {
+                        src.clear();
+                        return Ok(None);
+                    };
+                    src.advance(start);
+                    match (state, src[0]) {
+                        (State::Start, b'{') => self.state = State::BeforeKey,
+                        (State::BeforeKey, b'"') => {
+                            self.start_value(State::Key);
+                            continue;
+                        }
+                        (State::BeforeKey | State::AfterValue, b'}') => self.state = State::End,
+                        (State::BeforeColon, b':') => self.state = State::BeforeValue,
+                        (State::BeforeValue, b'[') if self.key == "items" => self.state = State::BeforeItem,
+                        (State::BeforeValue, _) => {
+                            self.start_value(State::Value);
+                            continue;
+                        }
+                        (State::AfterValue, b',') => self.state = State::BeforeKey,
+                        (State::BeforeItem | State::AfterItem, b']') => self.state = State::AfterValue,
+                        (State::BeforeItem, _) => {
+                            self.start_value(State::Item);
+                            continue;
+                        }
+                        (State::AfterItem, b',') => self.state = State::BeforeItem,
+                        _ => return Ok(self.fail(src, "expected a list object")),
+                    }
+                    src.advance(1);
+                }
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if let Some(part) = self.decode(src)? {
+            return Ok(Some(part));
+        }
+        match self.state {
+            State::End | State::Failed => Ok(None),
+            _ => Ok(self.fail(src, "unexpected end of list")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ListDecoder, ListPart};
+    use bytes::BytesMut;
+    use k8s_openapi::api::core::v1::Pod;
+    use tokio_util::codec::Decoder;
+
+    /// Decode `body` split into chunks of `chunk_size` bytes
+    fn decode(body: &str, chunk_size: usize) -> Vec<Result<ListPart<Pod>, String>> {
+        let mut decoder = ListDecoder::<Pod>::default();
+        let mut buf = BytesMut::new();
+        let mut parts = vec![];
+        for chunk in body.as_bytes().chunks(chunk_size) {
+            buf.extend_from_slice(chunk);
+            while let Some(part) = decoder.decode(&mut buf).unwrap() {
+                parts.push(part.map_err(|err| err.to_string()));
+            }
+        }
+        while let Some(part) = decoder.decode_eof(&mut buf).unwrap() {
+            parts.push(part.map_err(|err| err.to_string()));
+        }
+        parts
+    }
+
+    fn summarize(parts: &[Result<ListPart<Pod>, String>]) -> Vec<String> {
+        parts
+            .iter()
+            .map(|part| match part {
+                Ok(ListPart::Metadata(meta)) => format!("meta:{}", meta.resource_version.clone().unwrap()),
+                Ok(ListPart::Item(pod)) => format!("pod:{}", pod.metadata.name.clone().unwrap()),
+                Err(_) => "error".into(),
+            })
+            .collect()
+    }
+
+    const LIST: &str = r#"{
+        "kind": "PodList",
+        "apiVersion": "v1",
+        "metadata": { "resourceVersion": "42", "continue": "" },
+        "items": [
+            { "metadata": { "name": "a", "annotations": { "x": "}]\"[{" } } },
+            {"metadata":{"name":"b"},"spec":{"containers":[{"name":"c","args":["1","2"]}]}},
+            { "metadata": { "name": "c" }, "status": { "phase": "Running" } }
+        ],
+        "extra": [1, true, null, -2.5e3]
+    }"#;
+
+    #[test]
+    fn decodes_items_across_chunks() {
+        for chunk_size in [1, 2, 7, 64, LIST.len()] {
+            let parts = decode(LIST, chunk_size);
+            assert_eq!(
+                summarize(&parts),
+                ["meta:42", "pod:a", "pod:b", "pod:c"],
+                "{chunk_size}"
+            );
+        }
+        let parts = decode(LIST, 5);
+        let Ok(ListPart::Item(pod)) = &parts[1] else {
+            unreachable!()
+        };
+        assert_eq!(pod.metadata.annotations.as_ref().unwrap()["x"], "}]\"[{");
+    }
+
+    #[test]
+    fn decodes_empty_and_null_items() {
+        let parts = decode(r#"{"metadata":{"resourceVersion":"1"},"items":[]}"#, 3);
+        assert_eq!(summarize(&parts), ["meta:1"]);
+        let parts = decode(r#"{"items":null,"metadata":{"resourceVersion":"1"}}"#, 3);
+        assert_eq!(summarize(&parts), ["meta:1"]);
+    }
+
+    #[test]
+    fn reports_invalid_items_and_lists() {
+        let parts = decode(
+            r#"{"items":[{"metadata":{"name":1}},{"metadata":{"name":"b"}}]}"#,
+            4,
+        );
+        assert_eq!(summarize(&parts), ["error", "pod:b"]);
+        let parts = decode(r#"{"items":[{"metadata":{"name":"a"}}"#, 4);
+        assert_eq!(summarize(&parts), ["pod:a", "error"]);
+        let parts = decode("[]", 4);
+        assert_eq!(summarize(&parts), ["error"]);
+    }
+}
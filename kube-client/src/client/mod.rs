@@ -7,8 +7,10 @@
 //!
 //! The [`Client`] can also be used with [`Discovery`](crate::Discovery) to dynamically
 //! retrieve the resources served by the kubernetes API.
+use std::sync::Arc;
+
 use either::{Either, Left, Right};
-use futures::{future::BoxFuture, AsyncBufRead, StreamExt, TryStream, TryStreamExt};
+use futures::{future::BoxFuture, AsyncBufRead, Stream, StreamExt, TryStream, TryStreamExt};
 use http::{self, Request, Response};
 use http_body_util::BodyExt;
 #[cfg(feature = "ws")] use hyper_util::rt::TokioIo;
@@ -38,15 +40,22 @@ mod client_ext;
 #[cfg(feature = "unstable-client")]
 pub use client_ext::scope;
 mod config_ext;
+mod list_decoder;
 pub use auth::Error as AuthError;
 pub use config_ext::ConfigExt;
+use list_decoder::ListDecoder;
+pub use list_decoder::ListPart;
 pub mod middleware;
+mod warning;
+use warning::{ChainWarnings, RequestWarningHandler, WarningLayer};
+pub use warning::{CollectWarnings, DedupWarnings, LogWarnings, Warning, WarningHandler};
 
 #[cfg(any(feature = "rustls-tls", feature = "openssl-tls"))] mod tls;
 
 #[cfg(feature = "openssl-tls")]
 pub use tls::openssl_tls::Error as OpensslTlsError;
 #[cfg(feature = "rustls-tls")] pub use tls::rustls_tls::Error as RustlsTlsError;
+#[cfg(feature = "spdy")] mod spdy;
 #[cfg(feature = "ws")] mod upgrade;
 
 #[cfg(feature = "oauth")]
@@ -59,6 +68,11 @@ pub use auth::oidc_errors;
 
 #[cfg(feature = "ws")] pub use upgrade::UpgradeConnectionError;
 
+#[cfg(feature = "spdy")] pub(crate) use spdy::SpdyConnection;
+#[cfg(feature = "spdy")]
+#[cfg_attr(docsrs, doc(cfg(feature = "spdy")))]
+pub use spdy::{SpdyError, StreamingProtocol};
+
 #[cfg(feature = "kubelet-debug")]
 #[cfg_attr(docsrs, doc(cfg(feature = "kubelet-debug")))]
 mod kubelet_debug;
@@ -78,6 +92,31 @@ pub struct Client {
     // - `BoxFuture` for dynamic response future type
     inner: Buffer<Request<Body>, BoxFuture<'static, Result<Response<Body>, BoxError>>>,
     default_ns: String,
+    warning_handler: Arc<dyn WarningHandler>,
+    #[cfg(feature = "spdy")]
+    streaming_protocol: StreamingProtocol,
+}
+
+/// An upgraded connection for the streaming subresources
+#[cfg(feature = "ws")]
+pub(crate) enum StreamConnection {
+    WebSocket(Box<WebSocketStream<TokioIo<hyper::upgrade::Upgraded>>>),
+    #[cfg(feature = "spdy")]
+    Spdy(SpdyConnection),
+}
+
+/// Copy a request to retry it, keeping the name used for tracing
+#[cfg(feature = "spdy")]
+fn clone_request(request: &Request<Vec<u8>>) -> Request<Vec<u8>> {
+    let mut clone = Request::new(request.body().clone());
+    *clone.method_mut() = request.method().clone();
+    *clone.uri_mut() = request.uri().clone();
+    *clone.version_mut() = request.version();
+    *clone.headers_mut() = request.headers().clone();
+    if let Some(name) = request.extensions().get::<&'static str>() {
+        clone.extensions_mut().insert(*name);
+    }
+    clone
 }
 
 /// Constructors and low-level api interfaces.
@@ -128,12 +167,46 @@ impl Client {
         let service = MapResponseBodyLayer::new(Body::wrap_body)
             .layer(service)
             .map_err(|e| e.into());
+        let service = WarningLayer.layer(service);
         Self {
             inner: Buffer::new(BoxService::new(service), 1024),
             default_ns: default_namespace.into(),
+            warning_handler: Arc::new(LogWarnings),
+            #[cfg(feature = "spdy")]
+            streaming_protocol: StreamingProtocol::default(),
         }
     }
 
+    /// Set the protocol used for `exec`, `attach` and `portforward` connections
+    ///
+    /// By default, WebSockets are used, falling back to SPDY/3.1 for servers that do not accept them.
+    #[cfg(feature = "spdy")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "spdy")))]
+    #[must_use]
+    pub fn with_streaming_protocol(mut self, protocol: StreamingProtocol) -> Self {
+        self.streaming_protocol = protocol;
+        self
+    }
+
+    /// Set the handler for the warnings the apiserver sends with responses
+    ///
+    /// By default, warnings are logged with [`LogWarnings`]. The handler only applies to requests
+    /// made through the returned client, and clients or [`Api`](crate::Api)s created from it.
+    #[must_use]
+    pub fn with_warning_handler(mut self, handler: impl WarningHandler) -> Self {
+        self.warning_handler = Arc::new(handler);
+        self
+    }
+
+    /// Pass warnings to `handler` after the current handler
+    pub(crate) fn with_chained_warning_handler(mut self, handler: impl WarningHandler) -> Self {
+        self.warning_handler = Arc::new(ChainWarnings {
+            first: self.warning_handler,
+            then: handler,
+        });
+        self
+    }
+
     /// Create and initialize a [`Client`] using the inferred configuration.
     ///
     /// Will use [`Config::infer`] which attempts to load the local kubeconfig first,
@@ -167,7 +240,12 @@ impl Client {
     /// Perform a raw HTTP request against the API and return the raw response back.
     /// This method can be used to get raw access to the API which may be used to, for example,
     /// create a proxy server or application-level gateway between localhost and the API server.
-    pub async fn send(&self, request: Request<Body>) -> Result<Response<Body>> {
+    ///
+    /// Warnings sent with the response are passed to the [`WarningHandler`] of the client.
+    pub async fn send(&self, mut request: Request<Body>) -> Result<Response<Body>> {
+        request
+            .extensions_mut()
+            .insert(RequestWarningHandler(self.warning_handler.clone()));
         let mut svc = self.inner.clone();
         let res = svc
             .ready()
@@ -237,6 +315,91 @@ impl Client {
         }
     }
 
+    /// Upgrade a connection for `exec` or `attach`
+    #[cfg(feature = "ws")]
+    pub(crate) async fn connect_remote_command(&self, request: Request<Vec<u8>>) -> Result<StreamConnection> {
+        self.connect_stream(request, upgrade::WS_PROTOCOL).await
+    }
+
+    /// Upgrade a connection for `portforward`
+    #[cfg(feature = "ws")]
+    pub(crate) async fn connect_portforward(&self, request: Request<Vec<u8>>) -> Result<StreamConnection> {
+        self.connect_stream(request, upgrade::PORT_FORWARD_PROTOCOL).await
+    }
+
+    /// Upgrade a connection for the streaming subresources, using the configured `StreamingProtocol`
+    ///
+    /// `spdy_protocol` is the protocol to negotiate for the streams when using SPDY.
+    #[cfg(feature = "ws")]
+    #[cfg_attr(not(feature = "spdy"), allow(unused_variables))]
+    async fn connect_stream(
+        &self,
+        request: Request<Vec<u8>>,
+        spdy_protocol: &'static str,
+    ) -> Result<StreamConnection> {
+        #[cfg(feature = "spdy")]
+        match self.streaming_protocol {
+            StreamingProtocol::WebSocket => {}
+            StreamingProtocol::Spdy => {
+                return self
+                    .connect_spdy(request, spdy_protocol)
+                    .await
+                    .map(StreamConnection::Spdy)
+            }
+            StreamingProtocol::Auto => {
+                let fallback = clone_request(&request);
+                return match self.connect(request).await {
+                    Ok(stream) => Ok(StreamConnection::WebSocket(Box::new(stream))),
+                    Err(Error::UpgradeConnection(err)) if websocket_refused(&err) => {
+                        tracing::debug!(
+                            error = &err as &dyn std::error::Error,
+                            "WebSocket upgrade failed, falling back to SPDY"
+                        );
+                        self.connect_spdy(fallback, spdy_protocol)
+                            .await
+                            .map(StreamConnection::Spdy)
+                    }
+                    Err(err) => Err(err),
+                };
+            }
+        }
+        self.connect(request)
+            .await
+            .map(|stream| StreamConnection::WebSocket(Box::new(stream)))
+    }
+
+    /// Make SPDY/3.1 connection, speaking `protocol` over the streams.
+    #[cfg(feature = "spdy")]
+    async fn connect_spdy(
+        &self,
+        request: Request<Vec<u8>>,
+        protocol: &'static str,
+    ) -> Result<SpdyConnection> {
+        use http::header::HeaderValue;
+        let (mut parts, body) = request.into_parts();
+        // WebSockets require GET, but `kubectl` upgrades to SPDY with POST
+        parts.method = http::Method::POST;
+        parts
+            .headers
+            .insert(http::header::CONNECTION, HeaderValue::from_static("Upgrade"));
+        parts.headers.insert(
+            http::header::UPGRADE,
+            HeaderValue::from_static(spdy::SPDY_PROTOCOL),
+        );
+        parts
+            .headers
+            .insert(spdy::STREAM_PROTOCOL_VERSION, HeaderValue::from_static(protocol));
+
+        let res = self.send(Request::from_parts(parts, Body::from(body))).await?;
+        upgrade::verify_spdy_response(&res, protocol).map_err(Error::UpgradeConnection)?;
+        match hyper::upgrade::on(res).await {
+            Ok(upgraded) => Ok(SpdyConnection::new(TokioIo::new(upgraded))),
+            Err(e) => Err(Error::UpgradeConnection(
+                UpgradeConnectionError::GetPendingUpgrade(e),
+            )),
+        }
+    }
+
     /// Perform a raw HTTP request against the API and deserialize the response
     /// as JSON to some known type.
     pub async fn request<T>(&self, request: Request<Vec<u8>>) -> Result<T>
@@ -274,6 +437,29 @@ impl Client {
         Ok(body.into_async_read())
     }
 
+    /// Perform a raw HTTP request for a list, and stream its metadata and items as they are received
+    ///
+    /// Unlike deserializing an [`ObjectList`](kube_core::ObjectList) with [`Client::request`],
+    /// this does not buffer the whole response, so the memory needed is bounded by the size of the largest item
+    /// rather than the size of the list.
+    pub async fn request_list<T>(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<impl Stream<Item = Result<ListPart<T>>>>
+    where
+        T: DeserializeOwned,
+    {
+        let res = self.send(request.map(Body::from)).await?;
+        let res = handle_api_errors(res).await?;
+        let body = res.into_body().into_data_stream().map_err(std::io::Error::other);
+        let parts = FramedRead::new(StreamReader::new(body), ListDecoder::default());
+        Ok(parts.map(|part| match part {
+            Ok(Ok(part)) => Ok(part),
+            Ok(Err(err)) => Err(Error::SerdeError(err)),
+            Err(err) => Err(Error::ReadList(err)),
+        }))
+    }
+
     /// Perform a raw HTTP request against the API and get back either an object
     /// deserialized as JSON or a [`Status`] Object.
     pub async fn request_status<T>(&self, request: Request<Vec<u8>>) -> Result<Either<T, Status>>
@@ -466,12 +652,12 @@ async fn handle_api_errors(res: Response<Body>) -> Result<Response<Body>> {
             Err(Error::Api(errdata))
         } else {
             tracing::warn!("Unsuccessful data error parse: {}", text);
-            let error_response = ErrorResponse {
-                status: status.to_string(),
-                code: status.as_u16(),
-                message: format!("{text:?}"),
-                reason: "Failed to parse error data".into(),
-            };
+            let error_response = ErrorResponse::new(
+                &status.to_string(),
+                &format!("{text:?}"),
+                "Failed to parse error data",
+                status.as_u16(),
+            );
             tracing::debug!("Unsuccessful: {error_response:?} (reconstruct)");
             Err(Error::Api(error_response))
         }
@@ -480,6 +666,22 @@ async fn handle_api_errors(res: Response<Body>) -> Result<Response<Body>> {
     }
 }
 
+/// Whether the server refused the WebSocket upgrade, so the connection can be retried with SPDY
+///
+/// Servers without WebSocket support for the streaming subresources reject the upgrade with
+/// `400 Bad Request`, or answer without honouring the `Upgrade` or the requested subprotocol.
+/// Any other failure (e.g. `403 Forbidden` or `404 Not Found`) would fail the same way over SPDY.
+#[cfg(feature = "spdy")]
+fn websocket_refused(err: &UpgradeConnectionError) -> bool {
+    match err {
+        UpgradeConnectionError::ProtocolSwitch(status) => *status == http::StatusCode::BAD_REQUEST,
+        UpgradeConnectionError::MissingUpgradeWebSocketHeader
+        | UpgradeConnectionError::MissingConnectionUpgradeHeader
+        | UpgradeConnectionError::SecWebSocketProtocolMismatch => true,
+        _ => false,
+    }
+}
+
 impl TryFrom<Config> for Client {
     type Error = Error;
 
@@ -496,6 +698,7 @@ mod tests {
     use std::pin::pin;
 
     use crate::{client::Body, Api, Client};
+    #[cfg(feature = "spdy")] use crate::{client::UpgradeConnectionError, Error};
 
     use http::{Request, Response};
     use k8s_openapi::api::core::v1::Pod;
@@ -541,4 +744,67 @@ mod tests {
         assert_eq!(pod.metadata.annotations.unwrap().get("kube-rs").unwrap(), "test");
         spawned.await.unwrap();
     }
+
+    #[cfg(feature = "spdy")]
+    fn upgrade_request() -> Request<Vec<u8>> {
+        Request::get("/api/v1/namespaces/default/pods/test/exec?command=ls&stdout=true")
+            .body(vec![])
+            .unwrap()
+    }
+
+    #[cfg(feature = "spdy")]
+    #[tokio::test]
+    async fn auto_falls_back_to_spdy_when_the_websocket_upgrade_is_refused() {
+        let (mock_service, handle) = mock::pair::<Request<Body>, Response<Body>>();
+        let spawned = tokio::spawn(async move {
+            let mut handle = pin!(handle);
+            let (request, send) = handle.next_request().await.expect("service not called");
+            assert_eq!(request.headers()[http::header::UPGRADE], "websocket");
+            send.send_response(Response::builder().status(400).body(Body::empty()).unwrap());
+            let (request, send) = handle.next_request().await.expect("no SPDY fallback");
+            assert_eq!(request.method(), http::Method::POST);
+            assert_eq!(request.headers()[http::header::UPGRADE], "SPDY/3.1");
+            send.send_response(Response::builder().status(403).body(Body::empty()).unwrap());
+        });
+
+        let client = Client::new(mock_service, "default");
+        let err = client
+            .connect_remote_command(upgrade_request())
+            .await
+            .err()
+            .expect("upgrade succeeded");
+        assert!(matches!(
+            err,
+            Error::UpgradeConnection(UpgradeConnectionError::ProtocolSwitch(status)) if status == 403
+        ));
+        spawned.await.unwrap();
+    }
+
+    #[cfg(feature = "spdy")]
+    #[tokio::test]
+    async fn auto_returns_other_upgrade_errors_unchanged() {
+        for status in [403, 404, 500] {
+            let (mock_service, handle) = mock::pair::<Request<Body>, Response<Body>>();
+            let spawned = tokio::spawn(async move {
+                let mut handle = pin!(handle);
+                let (_, send) = handle.next_request().await.expect("service not called");
+                send.send_response(Response::builder().status(status).body(Body::empty()).unwrap());
+                // the mock would hand out a second request if the client fell back to SPDY
+                assert!(handle.next_request().await.is_none());
+            });
+
+            let client = Client::new(mock_service, "default");
+            let err = client
+                .connect_remote_command(upgrade_request())
+                .await
+                .err()
+                .expect("upgrade succeeded");
+            assert!(matches!(
+                err,
+                Error::UpgradeConnection(UpgradeConnectionError::ProtocolSwitch(got)) if got == status
+            ));
+            drop(client);
+            spawned.await.unwrap();
+        }
+    }
 }
@@ -0,0 +1,846 @@
+//! A SPDY/3.1 client for the streaming subresources, for servers that do not accept WebSockets
+//!
+//! Only the parts of the protocol used by Kubernetes are implemented: the client opens streams, sends and receives
+//! data on them, and half-closes them. Like the Kubernetes implementation, flow control is not enforced,
+//! so `WINDOW_UPDATE` frames are neither sent nor honored. Instead, data received on a stream is queued
+//! until it is read, so that a stream that is not being read does not hold up the others.
+use std::{collections::HashMap, io};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress};
+use futures::{
+    stream::{self, BoxStream, SelectAll},
+    SinkExt, StreamExt,
+};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt, DuplexStream, WriteHalf},
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
+use tokio_util::{
+    codec::{Decoder, Encoder, Framed},
+    io::ReaderStream,
+};
+
+/// The protocol in the `Upgrade` header.
+pub const SPDY_PROTOCOL: &str = "SPDY/3.1";
+/// The header used to negotiate the protocol spoken over the streams.
+pub const STREAM_PROTOCOL_VERSION: &str = "X-Stream-Protocol-Version";
+
+/// The protocol used for `exec`, `attach` and `portforward` connections
+///
+/// Set with [`Client::with_streaming_protocol`](crate::Client::with_streaming_protocol).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StreamingProtocol {
+    /// Use WebSockets, falling back to SPDY/3.1 when the server does not accept the WebSocket upgrade
+    #[default]
+    Auto,
+    /// Only use WebSockets
+    WebSocket,
+    /// Only use SPDY/3.1
+    Spdy,
+}
+
+const VERSION: u16 = 3;
+const CONTROL_BIT: u32 = 0x8000_0000;
+const STREAM_ID_MASK: u32 = 0x7fff_ffff;
+const FLAG_FIN: u8 = 0x01;
+const FRAME_HEADER_LENGTH: usize = 8;
+
+const SYN_STREAM: u16 = 1;
+const SYN_REPLY: u16 = 2;
+const RST_STREAM: u16 = 3;
+const PING: u16 = 6;
+const GOAWAY: u16 = 7;
+const HEADERS: u16 = 8;
+
+/// `RST_STREAM` status for streams opened by the server, which are not supported.
+const REFUSED_STREAM: u32 = 3;
+
+/// Size of the buffer between the connection and each stream, the same as for WebSocket port-forwarding.
+///
+/// Received data that does not fit is queued, see [`forward`].
+const STREAM_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Header blocks are compressed with a zlib stream primed with this dictionary, from the SPDY/3 specification.
+const HEADER_DICTIONARY: &[u8] = b"\
+    \0\0\0\x07options\
+    \0\0\0\x04head\
+    \0\0\0\x04post\
+    \0\0\0\x03put\
+    \0\0\0\x06delete\
+    \0\0\0\x05trace\
+    \0\0\0\x06accept\
+    \0\0\0\x0eaccept-charset\
+    \0\0\0\x0faccept-encoding\
+    \0\0\0\x0faccept-language\
+    \0\0\0\x0daccept-ranges\
+    \0\0\0\x03age\
+    \0\0\0\x05allow\
+    \0\0\0\x0dauthorization\
+    \0\0\0\x0dcache-control\
+    \0\0\0\x0aconnection\
+    \0\0\0\x0ccontent-base\
+    \0\0\0\x10content-encoding\
+    \0\0\0\x10content-language\
+    \0\0\0\x0econtent-length\
+    \0\0\0\x10content-location\
+    \0\0\0\x0bcontent-md5\
+    \0\0\0\x0dcontent-range\
+    \0\0\0\x0ccontent-type\
+    \0\0\0\x04date\
+    \0\0\0\x04etag\
+    \0\0\0\x06expect\
+    \0\0\0\x07expires\
+    \0\0\0\x04from\
+    \0\0\0\x04host\
+    \0\0\0\x08if-match\
+    \0\0\0\x11if-modified-since\
+    \0\0\0\x0dif-none-match\
+    \0\0\0\x08if-range\
+    \0\0\0\x13if-unmodified-since\
+    \0\0\0\x0dlast-modified\
+    \0\0\0\x08location\
+    \0\0\0\x0cmax-forwards\
+    \0\0\0\x06pragma\
+    \0\0\0\x12proxy-authenticate\
+    \0\0\0\x13proxy-authorization\
+    \0\0\0\x05range\
+    \0\0\0\x07referer\
+    \0\0\0\x0bretry-after\
+    \0\0\0\x06server\
+    \0\0\0\x02te\
+    \0\0\0\x07trailer\
+    \0\0\0\x11transfer-encoding\
+    \0\0\0\x07upgrade\
+    \0\0\0\x0auser-agent\
+    \0\0\0\x04vary\
+    \0\0\0\x03via\
+    \0\0\0\x07warning\
+    \0\0\0\x10www-authenticate\
+    \0\0\0\x06method\
+    \0\0\0\x03get\
+    \0\0\0\x06status\
+    \0\0\0\x06200 OK\
+    \0\0\0\x07version\
+    \0\0\0\x08HTTP/1.1\
+    \0\0\0\x03url\
+    \0\0\0\x06public\
+    \0\0\0\x0aset-cookie\
+    \0\0\0\x0akeep-alive\
+    \0\0\0\x06origin\
+    100101201202205206300302303304305306307402405406407408409410411412413414415416417502504505\
+    203 Non-Authoritative Information\
+    204 No Content\
+    301 Moved Permanently\
+    400 Bad Request\
+    401 Unauthorized\
+    403 Forbidden\
+    404 Not Found\
+    500 Internal Server Error\
+    501 Not Implemented\
+    503 Service Unavailable\
+    Jan Feb Mar Apr May Jun Jul Aug Sept Oct Nov Dec \
+    00:00:00 Mon, Tue, Wed, Thu, Fri, Sat, Sun, GMT\
+    chunked,text/html,\
+    image/png,image/jpg,image/gif,application/xml,application/xhtml+xml,text/plain,\
+    text/javascript,\
+    publicprivatemax-age=gzip,deflate,sdchcharset=utf-8charset=iso-8859-1,utf-,*,enq=0.";
+
+/// Errors from a SPDY connection
+#[derive(Debug, Error)]
+pub enum SpdyError {
+    /// Failed to read from or write to the connection
+    #[error("failed to read or write the connection: {0}")]
+    Io(#[from] io::Error),
+
+    /// Failed to compress a header block
+    #[error("failed to compress headers: {0}")]
+    CompressHeaders(#[source] flate2::CompressError),
+
+    /// Failed to decompress a header block
+    #[error("failed to decompress headers: {0}")]
+    DecompressHeaders(#[source] flate2::DecompressError),
+
+    /// Received a frame that could not be decoded
+    #[error("received an invalid frame: {0}")]
+    InvalidFrame(&'static str),
+
+    /// The server does not accept new streams after sending `GOAWAY`
+    #[error("the server is not accepting new streams")]
+    GoingAway,
+
+    /// The connection was closed
+    #[error("the connection is closed")]
+    Closed,
+
+    /// Failed to complete the background task
+    #[error("failed to complete the background task: {0}")]
+    Spawn(#[source] tokio::task::JoinError),
+}
+
+type Headers = Vec<(String, String)>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Frame {
+    Data {
+        stream_id: u32,
+        fin: bool,
+        data: Bytes,
+    },
+    SynStream {
+        stream_id: u32,
+        headers: Headers,
+    },
+    SynReply {
+        stream_id: u32,
+        fin: bool,
+        headers: Headers,
+    },
+    Headers {
+        stream_id: u32,
+        fin: bool,
+        headers: Headers,
+    },
+    RstStream {
+        stream_id: u32,
+        status: u32,
+    },
+    Ping(u32),
+    GoAway {
+        last_stream_id: u32,
+        status: u32,
+    },
+    /// `SETTINGS`, `WINDOW_UPDATE` and unknown control frames
+    Ignored,
+}
+
+/// Frame codec, holding the header compression state of both directions
+struct Codec {
+    compress: Compress,
+    decompress: Decompress,
+}
+
+impl Codec {
+    fn new() -> Self {
+        let mut compress = Compress::new(Compression::default(), true);
+        compress
+            .set_dictionary(HEADER_DICTIONARY)
+            .expect("dictionary can be set before compressing");
+        Self {
+            compress,
+            decompress: Decompress::new(true),
+        }
+    }
+
+    fn encode_headers(&mut self, headers: &[(String, String)], dst: &mut BytesMut) -> Result<(), SpdyError> {
+        let mut block = Vec::new();
+        block.put_u32(headers.len() as u32);
+        for (name, value) in headers {
+            block.put_u32(name.len() as u32);
+            block.put_slice(name.as_bytes());
+            block.put_u32(value.len() as u32);
+            block.put_slice(value.as_bytes());
+        }
+        let start = self.compress.total_in();
+        let mut compressed = Vec::with_capacity(block.len() + 64);
+        loop {
+            let consumed = (self.compress.total_in() - start) as usize;
+            self.compress
+                .compress_vec(&block[consumed..], &mut compressed, FlushCompress::Sync)
+                .map_err(SpdyError::CompressHeaders)?;
+            let consumed = (self.compress.total_in() - start) as usize;
+            if consumed == block.len() && compressed.len() < compressed.capacity() {
+                break;
+            }
+            compressed.reserve(block.len().max(64));
+        }
+        dst.put_slice(&compressed);
+        Ok(())
+    }
+
+    fn decode_headers(&mut self, compressed: &[u8]) -> Result<Headers, SpdyError> {
+        let start = self.decompress.total_in();
+        let mut block = Vec::with_capacity(compressed.len() * 4 + 64);
+        loop {
+            if block.len() == block.capacity() {
+                block.reserve(compressed.len().max(64));
+            }
+            let consumed = (self.decompress.total_in() - start) as usize;
+            let produced = block.len();
+            match self
+                .decompress
+                .decompress_vec(&compressed[consumed..], &mut block, FlushDecompress::Sync)
+            {
+                Ok(_) => {}
+                // The first header block asks for the dictionary
+                Err(err) if err.needs_dictionary().is_some() => {
+                    self.decompress
+                        .set_dictionary(HEADER_DICTIONARY)
+                        .map_err(SpdyError::DecompressHeaders)?;
+                    continue;
+                }
+                Err(err) => return Err(SpdyError::DecompressHeaders(err)),
+            }
+            let now_consumed = (self.decompress.total_in() - start) as usize;
+            if now_consumed == compressed.len() && block.len() < block.capacity() {
+                break;
+            }
+            if now_consumed == consumed && block.len() == produced {
+                return Err(SpdyError::InvalidFrame("truncated header block"));
+            }
+        }
+        parse_headers(&block)
+    }
+}
+
+fn parse_headers(mut block: &[u8]) -> Result<Headers, SpdyError> {
+    fn read_string(block: &mut &[u8]) -> Result<String, SpdyError> {
+        if block.remaining() < 4 {
+            return Err(SpdyError::InvalidFrame("truncated header block"));
+        }
+        let len = block.get_u32() as usize;
+        if block.remaining() < len {
+            return Err(SpdyError::InvalidFrame("truncated header block"));
+        }
+        let (value, rest) = block.split_at(len);
+        *block = rest;
+        String::from_utf8(value.to_vec()).map_err(|_| SpdyError::InvalidFrame("header is not valid UTF-8"))
+    }
+
+    if block.remaining() < 4 {
+        return Err(SpdyError::InvalidFrame("truncated header block"));
+    }
+    let count = block.get_u32();
+    let mut headers = Vec::new();
+    for _ in 0..count {
+        let name = read_string(&mut block)?;
+        let value = read_string(&mut block)?;
+        headers.push((name, value));
+    }
+    Ok(headers)
+}
+
+fn put_control_frame(dst: &mut BytesMut, frame_type: u16, flags: u8, payload: &[u8]) {
+    dst.put_u16(0x8000 | VERSION);
+    dst.put_u16(frame_type);
+    dst.put_u32(u32::from(flags) << 24 | payload.len() as u32);
+    dst.put_slice(payload);
+}
+
+impl Encoder<Frame> for Codec {
+    type Error = SpdyError;
+
+    fn encode(&mut self, frame: Frame, dst: &mut BytesMut) -> Result<(), SpdyError> {
+        let fin_flag = |fin: bool| if fin { FLAG_FIN } else { 0 };
+        let mut payload = BytesMut::new();
+        match frame {
+            Frame::Data { stream_id, fin, data } => {
+                dst.put_u32(stream_id & STREAM_ID_MASK);
+                dst.put_u32(u32::from(fin_flag(fin)) << 24 | data.len() as u32);
+                dst.put_slice(&data);
+            }
+            Frame::SynStream { stream_id, headers } => {
+                payload.put_u32(stream_id);
+                // No associated stream, the lowest priority, and no credential slot
+                payload.put_u32(0);
+                payload.put_u16(0);
+                self.encode_headers(&headers, &mut payload)?;
+                put_control_frame(dst, SYN_STREAM, 0, &payload);
+            }
+            Frame::SynReply {
+                stream_id,
+                fin,
+                headers,
+            } => {
+                payload.put_u32(stream_id);
+                self.encode_headers(&headers, &mut payload)?;
+                put_control_frame(dst, SYN_REPLY, fin_flag(fin), &payload);
+            }
+            Frame::Headers {
+                stream_id,
+                fin,
+                headers,
+            } => {
+                payload.put_u32(stream_id);
+                self.encode_headers(&headers, &mut payload)?;
+                put_control_frame(dst, HEADERS, fin_flag(fin), &payload);
+            }
+            Frame::RstStream { stream_id, status } => {
+                payload.put_u32(stream_id);
+                payload.put_u32(status);
+                put_control_frame(dst, RST_STREAM, 0, &payload);
+            }
+            Frame::Ping(id) => put_control_frame(dst, PING, 0, &id.to_be_bytes()),
+            Frame::GoAway {
+                last_stream_id,
+                status,
+            } => {
+                payload.put_u32(last_stream_id);
+                payload.put_u32(status);
+                put_control_frame(dst, GOAWAY, 0, &payload);
+            }
+            Frame::Ignored => {}
+        }
+        Ok(())
+    }
+}
+
+impl Decoder for Codec {
+    type Error = SpdyError;
+    type Item = Frame;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Frame>, SpdyError> {
+        if src.len() < FRAME_HEADER_LENGTH {
+            return Ok(None);
+        }
+        let length = u32::from_be_bytes([0, src[5], src[6], src[7]]) as usize;
+        if src.len() < FRAME_HEADER_LENGTH + length {
+            src.reserve(FRAME_HEADER_LENGTH + length - src.len());
+            return Ok(None);
+        }
+        let mut payload = src.split_to(FRAME_HEADER_LENGTH + length);
+        let first = payload.get_u32();
+        let fin = payload.get_u8() & FLAG_FIN != 0;
+        payload.advance(3);
+
+        if first & CONTROL_BIT == 0 {
+            return Ok(Some(Frame::Data {
+                stream_id: first,
+                fin,
+                data: payload.freeze(),
+            }));
+        }
+        if (first >> 16) as u16 & 0x7fff != VERSION {
+            return Err(SpdyError::InvalidFrame("unsupported SPDY version"));
+        }
+        let min_length = match first as u16 {
+            SYN_STREAM => 10,
+            SYN_REPLY | HEADERS | PING => 4,
+            RST_STREAM | GOAWAY => 8,
+            _ => 0,
+        };
+        if payload.len() < min_length {
+            return Err(SpdyError::InvalidFrame("control frame is too short"));
+        }
+        // Header blocks are decompressed even when the frame is not used, to keep the compression state in sync
+        let frame = match first as u16 {
+            SYN_STREAM => {
+                let stream_id = payload.get_u32() & STREAM_ID_MASK;
+                payload.advance(6);
+                Frame::SynStream {
+                    stream_id,
+                    headers: self.decode_headers(&payload)?,
+                }
+            }
+            SYN_REPLY => Frame::SynReply {
+                stream_id: payload.get_u32() & STREAM_ID_MASK,
+                fin,
+                headers: self.decode_headers(&payload)?,
+            },
+            HEADERS => Frame::Headers {
+                stream_id: payload.get_u32() & STREAM_ID_MASK,
+                fin,
+                headers: self.decode_headers(&payload)?,
+            },
+            RST_STREAM => Frame::RstStream {
+                stream_id: payload.get_u32() & STREAM_ID_MASK,
+                status: payload.get_u32(),
+            },
+            PING => Frame::Ping(payload.get_u32()),
+            GOAWAY => Frame::GoAway {
+                last_stream_id: payload.get_u32() & STREAM_ID_MASK,
+                status: payload.get_u32(),
+            },
+            _ => Frame::Ignored,
+        };
+        Ok(Some(frame))
+    }
+}
+
+enum Command {
+    Open {
+        headers: Headers,
+        reply: oneshot::Sender<Result<DuplexStream, SpdyError>>,
+    },
+}
+
+/// A SPDY connection, driven by a background task
+///
+/// Dropping the connection aborts the task. Use [`SpdyConnection::close`] to close it gracefully.
+pub(crate) struct SpdyConnection {
+    commands: mpsc::Sender<Command>,
+    task: Option<JoinHandle<Result<(), SpdyError>>>,
+}
+
+impl SpdyConnection {
+    pub(crate) fn new<S>(io: S) -> Self
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let (commands, receiver) = mpsc::channel(8);
+        let task = tokio::spawn(drive(Framed::new(io, Codec::new()), receiver));
+        Self {
+            commands,
+            task: Some(task),
+        }
+    }
+
+    /// Open a stream with `headers`
+    ///
+    /// Data is exchanged through the returned stream. Shutting down or dropping it half-closes the SPDY stream,
+    /// and reads reach the end when the server half-closes or resets the SPDY stream.
+    pub(crate) async fn open_stream(&self, headers: &[(&str, &str)]) -> Result<DuplexStream, SpdyError> {
+        let (reply, receiver) = oneshot::channel();
+        let headers = headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        self.commands
+            .send(Command::Open { headers, reply })
+            .await
+            .map_err(|_| SpdyError::Closed)?;
+        receiver.await.map_err(|_| SpdyError::Closed)?
+    }
+
+    /// Close the connection after the opened streams have been half-closed and their data has been sent
+    pub(crate) async fn close(mut self) -> Result<(), SpdyError> {
+        let task = self.task.take().expect("task is only taken when closing");
+        // Dropping the command sender lets the task finish
+        drop(self);
+        task.await.unwrap_or_else(|err| Err(SpdyError::Spawn(err)))
+    }
+}
+
+impl Drop for SpdyConnection {
+    fn drop(&mut self) {
+        if let Some(task) = &self.task {
+            task.abort();
+        }
+    }
+}
+
+struct Stream {
+    /// Queues data from the server, until the server half-closes the stream or the reader is dropped
+    received: Option<mpsc::UnboundedSender<Bytes>>,
+    reset: bool,
+}
+
+async fn drive<S>(
+    mut framed: Framed<S, Codec>,
+    mut commands: mpsc::Receiver<Command>,
+) -> Result<(), SpdyError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // Streams are few and short-lived along with the connection, so they are never removed
+    let mut streams = HashMap::<u32, Stream>::new();
+    // Data to send per stream, ending with `None` when the stream is half-closed locally
+    let mut outgoing = SelectAll::<BoxStream<'static, (u32, Option<Bytes>)>>::new();
+    let mut next_stream_id = 1;
+    let mut going_away = false;
+    let mut closing = false;
+    while !(closing && outgoing.is_empty()) {
+        tokio::select! {
+            frame = framed.next() => match frame {
+                Some(frame) => match frame? {
+                    Frame::Data { stream_id, fin, data } => receive(&mut streams, stream_id, fin, data),
+                    Frame::SynReply { stream_id, fin, .. } | Frame::Headers { stream_id, fin, .. } => {
+                        receive(&mut streams, stream_id, fin, Bytes::new());
+                    }
+                    Frame::RstStream { stream_id, status } => {
+                        tracing::debug!(stream_id, status, "stream was reset");
+                        if let Some(stream) = streams.get_mut(&stream_id) {
+                            stream.reset = true;
+                            receive(&mut streams, stream_id, true, Bytes::new());
+                        }
+                    }
+                    Frame::SynStream { stream_id, .. } => {
+                        framed.send(Frame::RstStream { stream_id, status: REFUSED_STREAM }).await?;
+                    }
+                    // Only pings from the server have even ids, and need a reply
+                    Frame::Ping(id) if id % 2 == 0 => framed.send(Frame::Ping(id)).await?,
+                    Frame::GoAway { status, .. } => {
+                        tracing::debug!(status, "server is going away");
+                        going_away = true;
+                    }
+                    Frame::Ping(_) | Frame::Ignored => {}
+                },
+                // The server closed the connection
+                None => return Ok(()),
+            },
+
+            Some((stream_id, data)) = outgoing.next(), if !outgoing.is_empty() => {
+                if !streams.get(&stream_id).is_some_and(|stream| stream.reset) {
+                    let fin = data.is_none();
+                    let data = data.unwrap_or_default();
+                    framed.send(Frame::Data { stream_id, fin, data }).await?;
+                }
+            },
+
+            command = commands.recv(), if !closing => match command {
+                Some(Command::Open { headers, reply }) => {
+                    if going_away {
+                        let _ = reply.send(Err(SpdyError::GoingAway));
+                        continue;
+                    }
+                    let stream_id = next_stream_id;
+                    next_stream_id += 2;
+                    framed.send(Frame::SynStream { stream_id, headers }).await?;
+                    let (local, remote) = tokio::io::duplex(STREAM_BUFFER_SIZE);
+                    let (reader, writer) = tokio::io::split(remote);
+                    let (received, queue) = mpsc::unbounded_channel();
+                    tokio::spawn(forward(queue, writer));
+                    streams.insert(stream_id, Stream { received: Some(received), reset: false });
+                    let data = ReaderStream::new(reader)
+                        .take_while(|chunk| std::future::ready(chunk.is_ok()))
+                        .map(move |chunk| (stream_id, chunk.ok()))
+                        .chain(stream::once(std::future::ready((stream_id, None))));
+                    outgoing.push(data.boxed());
+                    // The caller may have given up on the stream, which is then closed like any dropped stream
+                    let _ = reply.send(Ok(local));
+                }
+                None => closing = true,
+            },
+        }
+    }
+    framed
+        .send(Frame::GoAway {
+            last_stream_id: 0,
+            status: 0,
+        })
+        .await?;
+    framed.close().await
+}
+
+/// Queue data received on a stream for its reader, discarding it if the reader is gone
+///
+/// This never waits for the reader, so that the connection keeps serving the other streams.
+fn receive(streams: &mut HashMap<u32, Stream>, stream_id: u32, fin: bool, data: Bytes) {
+    let Some(stream) = streams.get_mut(&stream_id) else {
+        return;
+    };
+    let Some(received) = &stream.received else {
+        return;
+    };
+    let reader_gone = !data.is_empty() && received.send(data).is_err();
+    if reader_gone || fin {
+        // NB: data that is already queued is still forwarded before the stream is half-closed
+        stream.received = None;
+    }
+}
+
+/// Write the data queued for a stream to its reader, half-closing it once the server has half-closed the stream
+async fn forward(mut queue: mpsc::UnboundedReceiver<Bytes>, mut writer: WriteHalf<DuplexStream>) {
+    while let Some(data) = queue.recv().await {
+        if writer.write_all(&data).await.is_err() {
+            // The reader is gone
+            return;
+        }
+    }
+    let _ = writer.shutdown().await;
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Codec, Frame, SpdyConnection};
+    use bytes::{Bytes, BytesMut};
+    use futures::{SinkExt, StreamExt};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_util::codec::{Decoder, Encoder, Framed};
+
+    fn headers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn frames_roundtrip() {
+        let (mut client, mut server) = (Codec::new(), Codec::new());
+        let frames = vec![
+            Frame::SynStream {
+                stream_id: 1,
+                headers: headers(&[("streamtype", "error")]),
+            },
+            Frame::SynStream {
+                stream_id: 3,
+                headers: headers(&[("streamtype", "stdout")]),
+            },
+            Frame::SynReply {
+                stream_id: 3,
+                fin: false,
+                headers: headers(&[]),
+            },
+            Frame::Data {
+                stream_id: 3,
+                fin: true,
+                data: Bytes::from_static(b"hello"),
+            },
+            Frame::RstStream {
+                stream_id: 1,
+                status: 5,
+            },
+            Frame::Ping(2),
+            Frame::GoAway {
+                last_stream_id: 3,
+                status: 0,
+            },
+        ];
+        let mut buf = BytesMut::new();
+        for frame in frames.clone() {
+            client.encode(frame, &mut buf).unwrap();
+        }
+        let mut decoded = Vec::new();
+        while let Some(frame) = server.decode(&mut buf).unwrap() {
+            decoded.push(frame);
+        }
+        assert_eq!(decoded, frames);
+    }
+
+    #[test]
+    fn headers_are_decompressed_with_the_spdy_dictionary() {
+        // Two SYN_REPLY frames, with headers compressed as a single zlib stream primed with the SPDY/3 dictionary
+        let mut buf = BytesMut::from(
+            &b"\
+            \x80\x03\x00\x02\x00\x00\x00\x24\x00\x00\x00\x03\x78\xbb\xe3\xc6\xa7\xc2\x02\xa6\x23\x46\x70\x3a\x2b\x29\
+            \x4a\x4d\xcc\x85\x16\x25\xc0\x14\x9b\x92\x5f\x5a\x02\x00\x00\x00\xff\xff\
+            \x80\x03\x00\x02\x00\x00\x00\x0f\x00\x00\x00\x05\xc2\x29\x97\x5a\x54\x04\x00\x00\x00\xff\xff"[..],
+        );
+        let mut codec = Codec::new();
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(Frame::SynReply {
+                stream_id: 3,
+                fin: false,
+                headers: headers(&[("streamtype", "stdout")]),
+            })
+        );
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(Frame::SynReply {
+                stream_id: 5,
+                fin: false,
+                headers: headers(&[("streamtype", "stderr")]),
+            })
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[tokio::test]
+    async fn streams_exchange_data_and_half_close() {
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        let connection = SpdyConnection::new(client_io);
+        let mut server = Framed::new(server_io, Codec::new());
+
+        let mut stream = connection.open_stream(&[("streamtype", "data")]).await.unwrap();
+        assert_eq!(server.next().await.unwrap().unwrap(), Frame::SynStream {
+            stream_id: 1,
+            headers: headers(&[("streamtype", "data")]),
+        });
+
+        stream.write_all(b"ping").await.unwrap();
+        stream.shutdown().await.unwrap();
+        let mut received = Vec::new();
+        loop {
+            match server.next().await.unwrap().unwrap() {
+                Frame::Data {
+                    stream_id: 1,
+                    fin,
+                    data,
+                } => {
+                    received.extend_from_slice(&data);
+                    if fin {
+                        break;
+                    }
+                }
+                frame => panic!("unexpected frame {frame:?}"),
+            }
+        }
+        assert_eq!(received, b"ping");
+
+        server
+            .send(Frame::Data {
+                stream_id: 1,
+                fin: true,
+                data: Bytes::from_static(b"pong"),
+            })
+            .await
+            .unwrap();
+        let mut reply = Vec::new();
+        stream.read_to_end(&mut reply).await.unwrap();
+        assert_eq!(reply, b"pong");
+
+        drop(stream);
+        let closed = tokio::spawn(connection.close());
+        assert!(matches!(
+            server.next().await.unwrap().unwrap(),
+            Frame::GoAway { .. }
+        ));
+        assert!(server.next().await.is_none());
+        closed.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn unread_streams_do_not_block_the_connection() {
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        let connection = SpdyConnection::new(client_io);
+        let mut server = Framed::new(server_io, Codec::new());
+        let mut unread = connection.open_stream(&[("streamtype", "stdout")]).await.unwrap();
+        let mut read = connection.open_stream(&[("streamtype", "stderr")]).await.unwrap();
+        for _ in 0..2 {
+            assert!(matches!(
+                server.next().await.unwrap().unwrap(),
+                Frame::SynStream { .. }
+            ));
+        }
+
+        // more than the buffer of the stream, which is not read until the end
+        let chunk = Bytes::from(vec![b'x'; 64 * 1024]);
+        for _ in 0..48 {
+            server
+                .send(Frame::Data {
+                    stream_id: 1,
+                    fin: false,
+                    data: chunk.clone(),
+                })
+                .await
+                .unwrap();
+        }
+        server
+            .send(Frame::Data {
+                stream_id: 1,
+                fin: true,
+                data: Bytes::new(),
+            })
+            .await
+            .unwrap();
+        server
+            .send(Frame::Data {
+                stream_id: 3,
+                fin: true,
+                data: Bytes::from_static(b"still served"),
+            })
+            .await
+            .unwrap();
+        server.send(Frame::Ping(2)).await.unwrap();
+
+        let timeout = std::time::Duration::from_secs(5);
+        let mut reply = Vec::new();
+        tokio::time::timeout(timeout, read.read_to_end(&mut reply))
+            .await
+            .expect("stream was blocked by the unread stream")
+            .unwrap();
+        assert_eq!(reply, b"still served");
+        let pong = tokio::time::timeout(timeout, server.next())
+            .await
+            .expect("ping was blocked by the unread stream");
+        assert_eq!(pong.unwrap().unwrap(), Frame::Ping(2));
+
+        let mut received = Vec::new();
+        unread.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received.len(), 48 * 64 * 1024);
+    }
+}
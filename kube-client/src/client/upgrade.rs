@@ -6,8 +6,10 @@ use crate::client::Body;
 
 // Binary subprotocol v4. See `Client::connect`.
 pub const WS_PROTOCOL: &str = "v4.channel.k8s.io";
+// Stream protocol for port-forwarding over SPDY. Remote commands use `WS_PROTOCOL` over SPDY as well.
+pub const PORT_FORWARD_PROTOCOL: &str = "portforward.k8s.io";
 
-/// Possible errors from upgrading to a WebSocket connection
+/// Possible errors from upgrading to a WebSocket or SPDY connection
 #[cfg(feature = "ws")]
 #[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
 #[derive(Debug, Error)]
@@ -35,6 +37,18 @@ pub enum UpgradeConnectionError {
     #[error("Sec-WebSocket-Protocol mismatched")]
     SecWebSocketProtocolMismatch,
 
+    /// `Upgrade` header was not set to `SPDY/3.1` (case insensitive)
+    #[cfg(feature = "spdy")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "spdy")))]
+    #[error("upgrade header was not set to SPDY/3.1")]
+    MissingUpgradeSpdyHeader,
+
+    /// `X-Stream-Protocol-Version` mismatched.
+    #[cfg(feature = "spdy")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "spdy")))]
+    #[error("X-Stream-Protocol-Version mismatched")]
+    StreamProtocolMismatch,
+
     /// Failed to get pending HTTP upgrade.
     #[error("failed to get pending HTTP upgrade: {0}")]
     GetPendingUpgrade(#[source] hyper::Error),
@@ -86,3 +100,42 @@ pub fn verify_response(res: &Response<Body>, key: &str) -> Result<(), UpgradeCon
 
     Ok(())
 }
+
+// Verify a SPDY upgrade response, and that the server agreed to speak `protocol` over the streams.
+#[cfg(feature = "spdy")]
+pub fn verify_spdy_response(res: &Response<Body>, protocol: &str) -> Result<(), UpgradeConnectionError> {
+    use super::spdy::{SPDY_PROTOCOL, STREAM_PROTOCOL_VERSION};
+
+    if res.status() != StatusCode::SWITCHING_PROTOCOLS {
+        return Err(UpgradeConnectionError::ProtocolSwitch(res.status()));
+    }
+
+    let headers = res.headers();
+    if !headers
+        .get(http::header::UPGRADE)
+        .and_then(|h| h.to_str().ok())
+        .map(|h| h.eq_ignore_ascii_case(SPDY_PROTOCOL))
+        .unwrap_or(false)
+    {
+        return Err(UpgradeConnectionError::MissingUpgradeSpdyHeader);
+    }
+
+    if !headers
+        .get(http::header::CONNECTION)
+        .and_then(|h| h.to_str().ok())
+        .map(|h| h.eq_ignore_ascii_case("Upgrade"))
+        .unwrap_or(false)
+    {
+        return Err(UpgradeConnectionError::MissingConnectionUpgradeHeader);
+    }
+
+    if !headers
+        .get(STREAM_PROTOCOL_VERSION)
+        .map(|h| h == protocol)
+        .unwrap_or(false)
+    {
+        return Err(UpgradeConnectionError::StreamProtocolMismatch);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,415 @@
+//! Handling of the warnings sent by the apiserver
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use futures::future::BoxFuture;
+use http::{header::WARNING, HeaderMap, Request, Response};
+use tower::{Layer, Service};
+
+/// Number of distinct warnings remembered by [`DedupWarnings::new`]
+const DEDUP_CAPACITY: usize = 1024;
+
+/// A warning sent by the apiserver with a response
+///
+/// The apiserver warns about deprecated APIs, and about unknown or duplicate fields when validating with
+/// [`ValidationDirective::Warn`](crate::api::ValidationDirective::Warn).
+/// Admission webhooks may add their own.
+/// Warnings are sent in `Warning` headers, as described in
+/// [RFC 7234](https://www.rfc-editor.org/rfc/rfc7234#section-5.5).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Warning {
+    /// The warning code, which is `299` for warnings from the apiserver
+    pub code: u16,
+    /// The name of the server that added the warning, or `-` if it is unknown
+    pub agent: String,
+    /// The warning message
+    pub text: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl Warning {
+    /// Parse the warnings in the `Warning` headers of a response
+    ///
+    /// Malformed warnings are skipped. Admission webhooks may send UTF-8 text,
+    /// and invalid UTF-8 is replaced rather than dropping the warning.
+    pub fn from_headers(headers: &HeaderMap) -> Vec<Self> {
+        let mut warnings = Vec::new();
+        for value in headers.get_all(WARNING) {
+            parse_warnings(&String::from_utf8_lossy(value.as_bytes()), &mut warnings);
+        }
+        warnings
+    }
+}
+
+/// Parse a `Warning` header value, which may hold several comma separated warnings
+fn parse_warnings(value: &str, warnings: &mut Vec<Warning>) {
+    let mut rest = value;
+    loop {
+        rest = rest.trim_start_matches([' ', '\t', ',']);
+        if rest.is_empty() {
+            return;
+        }
+        let Some(warning) = parse_warning(&mut rest) else {
+            tracing::debug!(value, "ignoring malformed warning header");
+            return;
+        };
+        warnings.push(warning);
+    }
+}
+
+/// Parse a `code agent "text" ["date"]` warning from the start of `rest`, and advance past it
+fn parse_warning(rest: &mut &str) -> Option<Warning> {
+    let (code, after_code) = rest.split_once(' ')?;
+    let code = code.parse().ok().filter(|_| code.len() == 3)?;
+    let (agent, after_agent) = after_code.split_once(' ')?;
+    let (text, after_text) = parse_quoted(after_agent)?;
+    *rest = after_text;
+    // NB: the date is optional, and only used by caches
+    if let Some(after_date) = rest
+        .strip_prefix(' ')
+        .filter(|date| date.starts_with('"'))
+        .and_then(|date| parse_quoted(date))
+        .map(|(_, after_date)| after_date)
+    {
+        *rest = after_date;
+    }
+    Some(Warning {
+        code,
+        agent: agent.to_string(),
+        text,
+    })
+}
+
+/// Parse a quoted string from the start of `value`, returning it unescaped and the rest of `value`
+fn parse_quoted(value: &str) -> Option<(String, &str)> {
+    let mut chars = value.strip_prefix('"')?.char_indices();
+    let mut text = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((text, &value[i + 2..])),
+            '\\' => text.push(chars.next()?.1),
+            c => text.push(c),
+        }
+    }
+    None
+}
+
+/// Something that handles the [`Warning`]s sent by the apiserver
+///
+/// Set the handler of a client with [`Client::with_warning_handler`](crate::Client::with_warning_handler).
+/// Any `Fn(&Warning)` closure can be used as a handler.
+pub trait WarningHandler: Send + Sync + 'static {
+    /// Handle a warning sent with a response
+    fn handle(&self, warning: &Warning);
+}
+
+impl<F> WarningHandler for F
+where
+    F: Fn(&Warning) + Send + Sync + 'static,
+{
+    fn handle(&self, warning: &Warning) {
+        self(warning);
+    }
+}
+
+/// Log warnings with `tracing`, at the warn level
+///
+/// This is the default [`WarningHandler`] of a [`Client`](crate::Client).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LogWarnings;
+
+impl WarningHandler for LogWarnings {
+    fn handle(&self, warning: &Warning) {
+        tracing::warn!(code = warning.code, agent = %warning.agent, "{}", warning.text);
+    }
+}
+
+/// Collect warnings, so they can be inspected after making requests
+///
+/// Clones share the collected warnings, so a clone can be given to a client
+/// and the original kept to read them.
+///
+/// ```no_run
+/// use kube::{api::{Api, Patch, PatchParams}, client::CollectWarnings, Client};
+/// use k8s_openapi::api::apps::v1::Deployment;
+/// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+/// # let client: Client = todo!();
+/// # let deployment: Deployment = todo!();
+/// let warnings = CollectWarnings::default();
+/// let deployments: Api<Deployment> = Api::default_namespaced(client).with_warning_handler(warnings.clone());
+/// let pp = PatchParams::apply("my-manager").validation_warn();
+/// deployments.patch("my-app", &pp, &Patch::Apply(&deployment)).await?;
+/// for warning in warnings.take() {
+///     eprintln!("warning: {warning}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CollectWarnings {
+    warnings: Arc<Mutex<Vec<Warning>>>,
+}
+
+impl CollectWarnings {
+    /// Take the warnings collected so far, leaving none
+    pub fn take(&self) -> Vec<Warning> {
+        std::mem::take(&mut *self.warnings.lock().unwrap_or_else(|err| err.into_inner()))
+    }
+}
+
+impl WarningHandler for CollectWarnings {
+    fn handle(&self, warning: &Warning) {
+        self.warnings
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .push(warning.clone());
+    }
+}
+
+/// Pass each distinct warning on to another handler only once
+///
+/// Long-running clients, like controllers, otherwise repeat the same warning
+/// about a deprecated API on every request.
+///
+/// Only a bounded number of distinct warnings is remembered. Once that is exceeded,
+/// the warning that was first seen the longest ago is forgotten, and passed on again if it recurs.
+#[derive(Debug)]
+pub struct DedupWarnings<H> {
+    inner: H,
+    capacity: usize,
+    seen: Mutex<Seen>,
+}
+
+#[derive(Debug, Default)]
+struct Seen {
+    warnings: HashSet<Warning>,
+    order: VecDeque<Warning>,
+}
+
+impl<H: WarningHandler> DedupWarnings<H> {
+    /// Deduplicate the warnings passed on to `inner`, remembering up to 1024 distinct warnings
+    pub fn new(inner: H) -> Self {
+        Self::with_capacity(inner, DEDUP_CAPACITY)
+    }
+
+    /// Deduplicate the warnings passed on to `inner`, remembering up to `capacity` distinct warnings
+    pub fn with_capacity(inner: H, capacity: usize) -> Self {
+        Self {
+            inner,
+            capacity,
+            seen: Mutex::default(),
+        }
+    }
+}
+
+impl<H: WarningHandler + Default> Default for DedupWarnings<H> {
+    fn default() -> Self {
+        Self::new(H::default())
+    }
+}
+
+impl<H: WarningHandler> WarningHandler for DedupWarnings<H> {
+    fn handle(&self, warning: &Warning) {
+        let first = {
+            let mut seen = self.seen.lock().unwrap_or_else(|err| err.into_inner());
+            let first = seen.warnings.insert(warning.clone());
+            if first {
+                seen.order.push_back(warning.clone());
+                while seen.order.len() > self.capacity {
+                    if let Some(oldest) = seen.order.pop_front() {
+                        seen.warnings.remove(&oldest);
+                    }
+                }
+            }
+            first
+        };
+        if first {
+            self.inner.handle(warning);
+        }
+    }
+}
+
+/// Pass warnings to the handler a client already had, and then to another one
+pub(crate) struct ChainWarnings<H> {
+    pub(crate) first: Arc<dyn WarningHandler>,
+    pub(crate) then: H,
+}
+
+impl<H: WarningHandler> WarningHandler for ChainWarnings<H> {
+    fn handle(&self, warning: &Warning) {
+        self.first.handle(warning);
+        self.then.handle(warning);
+    }
+}
+
+/// The handler for the warnings of a request, set by [`Client::send`](crate::Client::send)
+#[derive(Clone)]
+pub(crate) struct RequestWarningHandler(pub(crate) Arc<dyn WarningHandler>);
+
+/// Layer that passes the warnings of each response to the [`WarningHandler`] of its request
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct WarningLayer;
+
+impl<S> Layer<S> for WarningLayer {
+    type Service = WarningService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        WarningService { inner }
+    }
+}
+
+/// Service that passes the warnings of each response to the [`WarningHandler`] of its request
+#[derive(Clone)]
+pub(crate) struct WarningService<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for WarningService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response<ResBody>, S::Error>>;
+    type Response = S::Response;
+
+    fn poll_ready(&mut self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let handler = req.extensions_mut().remove::<RequestWarningHandler>();
+        let res = self.inner.call(req);
+        Box::pin(async move {
+            let res = res.await?;
+            if let Some(RequestWarningHandler(handler)) = handler {
+                for warning in Warning::from_headers(res.headers()) {
+                    handler.handle(&warning);
+                }
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::pin;
+
+    use super::{CollectWarnings, DedupWarnings, Warning, WarningHandler};
+    use crate::{client::Body, Api, Client};
+    use http::{header::WARNING, HeaderMap, HeaderValue, Request, Response};
+    use k8s_openapi::api::core::v1::ConfigMap;
+    use tower_test::mock;
+
+    fn warning(text: &str) -> Warning {
+        Warning {
+            code: 299,
+            agent: "-".into(),
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn parses_warning_headers() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            WARNING,
+            HeaderValue::from_static(r#"299 - "extensions/v1beta1 Ingress is deprecated""#),
+        );
+        headers.append(
+            WARNING,
+            HeaderValue::from_static(concat!(
+                r#"299 - "unknown field \"spec.replica\"", "#,
+                r#"199 proxy "cached" "Wed, 21 Oct 2015 07:28:00 GMT""#,
+            )),
+        );
+        headers.append(WARNING, HeaderValue::from_static("299 - unquoted"));
+        headers.append(
+            WARNING,
+            HeaderValue::from_bytes(r#"299 - "réplicas ≠ 3""#.as_bytes()).unwrap(),
+        );
+        headers.append(
+            WARNING,
+            HeaderValue::from_bytes(b"299 - \"invalid \xff utf-8\"").unwrap(),
+        );
+        assert_eq!(Warning::from_headers(&headers), [
+            warning("extensions/v1beta1 Ingress is deprecated"),
+            warning(r#"unknown field "spec.replica""#),
+            Warning {
+                code: 199,
+                agent: "proxy".into(),
+                text: "cached".into(),
+            },
+            warning("réplicas ≠ 3"),
+            warning("invalid \u{fffd} utf-8"),
+        ]);
+    }
+
+    #[test]
+    fn collects_and_deduplicates() {
+        let collected = CollectWarnings::default();
+        let handler = DedupWarnings::new(collected.clone());
+        handler.handle(&warning("a"));
+        handler.handle(&warning("b"));
+        handler.handle(&warning("a"));
+        assert_eq!(collected.take(), [warning("a"), warning("b")]);
+        assert!(collected.take().is_empty());
+
+        // the warning seen first is forgotten when the capacity is exceeded
+        let handler = DedupWarnings::with_capacity(collected.clone(), 2);
+        for text in ["a", "b", "a", "c", "b", "a"] {
+            handler.handle(&warning(text));
+        }
+        assert_eq!(collected.take(), [
+            warning("a"),
+            warning("b"),
+            warning("c"),
+            warning("a")
+        ]);
+    }
+
+    #[tokio::test]
+    async fn passes_response_warnings_to_the_handler() {
+        let (mock_service, handle) = mock::pair::<Request<Body>, Response<Body>>();
+        let spawned = tokio::spawn(async move {
+            let mut handle = pin!(handle);
+            for _ in 0..2 {
+                let (_, send) = handle.next_request().await.expect("service not called");
+                let cm = serde_json::json!({ "metadata": { "name": "cm" } });
+                send.send_response(
+                    Response::builder()
+                        .header(WARNING, r#"299 - "deprecated""#)
+                        .body(Body::from(serde_json::to_vec(&cm).unwrap()))
+                        .unwrap(),
+                );
+            }
+        });
+        let from_client = CollectWarnings::default();
+        let client = Client::new(mock_service, "default").with_warning_handler(from_client.clone());
+        let collected = CollectWarnings::default();
+        let cms: Api<ConfigMap> =
+            Api::default_namespaced(client.clone()).with_warning_handler(collected.clone());
+        cms.get("cm").await.unwrap();
+        assert_eq!(collected.take(), [warning("deprecated")]);
+        // the handler of the client still sees the warnings of the api
+        assert_eq!(from_client.take(), [warning("deprecated")]);
+
+        // the handler of the original client is unchanged
+        Api::<ConfigMap>::default_namespaced(client)
+            .get("cm")
+            .await
+            .unwrap();
+        assert!(collected.take().is_empty());
+        assert_eq!(from_client.take(), [warning("deprecated")]);
+        spawned.await.unwrap();
+    }
+}
@@ -0,0 +1,277 @@
+//! Support for aggregated discovery (`apidiscovery.k8s.io/v2`)
+//!
+//! Aggregated discovery returns every group, version and resource in a single response from `/api` or `/apis`,
+//! instead of one response per group version. Servers that do not support it answer with the legacy
+//! `APIVersions` and `APIGroupList` documents, so callers fall back to querying each group version.
+use super::parse::{GroupVersionData, ResourceNames};
+use crate::{Client, Error, Result};
+use http::{header, Request};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kube_core::discovery::{ApiCapabilities, ApiResource, Scope};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::collections::HashMap;
+
+/// Prefer the aggregated discovery document, falling back to the legacy one on older servers
+const ACCEPT_AGGREGATED: &str =
+    "application/json;g=apidiscovery.k8s.io;v=v2;as=APIGroupDiscoveryList,application/json";
+
+const AGGREGATED_KIND: &str = "APIGroupDiscoveryList";
+
+/// A discovery response from `/api` or `/apis`
+pub(crate) enum Discovered<T> {
+    /// The server supports aggregated discovery
+    Aggregated(APIGroupDiscoveryList),
+    /// The server answered with the legacy document
+    Legacy(T),
+}
+
+/// Query `/api` or `/apis`, asking for the aggregated document if the server supports it
+pub(crate) async fn discover<T: DeserializeOwned>(client: &Client, uri: &str) -> Result<Discovered<T>> {
+    #[derive(Deserialize)]
+    struct Kind {
+        #[serde(default)]
+        kind: String,
+    }
+
+    let request = Request::builder()
+        .uri(uri)
+        .header(header::ACCEPT, ACCEPT_AGGREGATED)
+        .body(vec![])
+        .map_err(Error::HttpError)?;
+    let text = client.request_text(request).await?;
+    let kind = serde_json::from_str::<Kind>(&text)
+        .map_err(Error::SerdeError)?
+        .kind;
+    if kind == AGGREGATED_KIND {
+        tracing::debug!(uri, "Using aggregated discovery");
+        let list = serde_json::from_str(&text).map_err(Error::SerdeError)?;
+        Ok(Discovered::Aggregated(list))
+    } else {
+        tracing::debug!(uri, "Aggregated discovery not supported, falling back");
+        let legacy = serde_json::from_str(&text).map_err(Error::SerdeError)?;
+        Ok(Discovered::Legacy(legacy))
+    }
+}
+
+/// All groups served under `/api` or `/apis`
+#[derive(Deserialize)]
+pub(crate) struct APIGroupDiscoveryList {
+    #[serde(default)]
+    pub(crate) items: Vec<APIGroupDiscovery>,
+}
+
+/// A group and its versions, ordered by preference
+#[derive(Deserialize)]
+pub(crate) struct APIGroupDiscovery {
+    #[serde(default)]
+    pub(crate) metadata: ObjectMeta,
+    #[serde(default)]
+    pub(crate) versions: Vec<APIVersionDiscovery>,
+}
+
+impl APIGroupDiscovery {
+    /// The name of the group, empty for the core group
+    pub(crate) fn name(&self) -> &str {
+        self.metadata.name.as_deref().unwrap_or_default()
+    }
+}
+
+#[derive(Deserialize)]
+pub(crate) struct APIVersionDiscovery {
+    pub(crate) version: String,
+    #[serde(default)]
+    resources: Vec<APIResourceDiscovery>,
+    #[serde(default)]
+    freshness: Option<String>,
+}
+
+impl APIVersionDiscovery {
+    /// Whether the resources of this version could not be refreshed from an aggregated apiserver
+    pub(crate) fn is_stale(&self) -> bool {
+        self.freshness.as_deref() == Some("Stale")
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct APIResourceDiscovery {
+    resource: String,
+    #[serde(default)]
+    response_kind: Option<GroupVersionKind>,
+    scope: String,
+    #[serde(default)]
+    singular_resource: String,
+    #[serde(default)]
+    short_names: Vec<String>,
+    #[serde(default)]
+    verbs: Vec<String>,
+    #[serde(default)]
+    subresources: Vec<APISubresourceDiscovery>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct APISubresourceDiscovery {
+    subresource: String,
+    #[serde(default)]
+    response_kind: Option<GroupVersionKind>,
+    #[serde(default)]
+    verbs: Vec<String>,
+}
+
+/// The kind of object returned for a resource, with the group and version only set when they differ
+#[derive(Default, Deserialize)]
+struct GroupVersionKind {
+    #[serde(default)]
+    group: String,
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    kind: String,
+}
+
+impl GroupVersionData {
+    /// Extract all information for a version of `group` from aggregated discovery
+    pub(crate) fn from_aggregated(group: &str, data: APIVersionDiscovery) -> Self {
+        let mut names = HashMap::new();
+        let resources = data
+            .resources
+            .into_iter()
+            .map(|res| {
+                names.insert(res.resource.clone(), ResourceNames {
+                    singular: res.singular_resource,
+                    short_names: res.short_names,
+                });
+                let scope = if res.scope == "Namespaced" {
+                    Scope::Namespaced
+                } else {
+                    Scope::Cluster
+                };
+                let subresources = res
+                    .subresources
+                    .into_iter()
+                    .map(|sub| {
+                        let ar = api_resource(group, &data.version, sub.response_kind, sub.subresource);
+                        let caps = ApiCapabilities {
+                            scope: scope.clone(),
+                            subresources: vec![],
+                            operations: sub.verbs,
+                        };
+                        (ar, caps)
+                    })
+                    .collect();
+                let ar = api_resource(group, &data.version, res.response_kind, res.resource);
+                let caps = ApiCapabilities {
+                    scope,
+                    subresources,
+                    operations: res.verbs,
+                };
+                (ar, caps)
+            })
+            .collect();
+        GroupVersionData {
+            version: data.version,
+            resources,
+            names,
+        }
+    }
+}
+
+fn api_resource(group: &str, version: &str, kind: Option<GroupVersionKind>, plural: String) -> ApiResource {
+    let gvk = kind.unwrap_or_default();
+    let api_version = if group.is_empty() {
+        version.to_string()
+    } else {
+        format!("{group}/{version}")
+    };
+    // NB: mirrors parse_apiresource, where the group and version may be overridden for the kind
+    let or_default = |value: String, default: &str| {
+        if value.is_empty() {
+            default.to_string()
+        } else {
+            value
+        }
+    };
+    ApiResource {
+        group: or_default(gvk.group, group),
+        version: or_default(gvk.version, version),
+        api_version,
+        kind: gvk.kind,
+        plural,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::ApiGroup;
+
+    #[test]
+    fn aggregated_discovery_populates_api_groups() {
+        let list: APIGroupDiscoveryList = serde_json::from_value(serde_json::json!({
+            "kind": "APIGroupDiscoveryList",
+            "apiVersion": "apidiscovery.k8s.io/v2",
+            "metadata": {},
+            "items": [{
+                "metadata": { "name": "apps" },
+                "versions": [{
+                    "version": "v1",
+                    "resources": [{
+                        "resource": "deployments",
+                        "responseKind": { "group": "", "version": "", "kind": "Deployment" },
+                        "scope": "Namespaced",
+                        "singularResource": "deployment",
+                        "verbs": ["create", "delete", "get", "list", "patch", "update", "watch"],
+                        "shortNames": ["deploy"],
+                        "subresources": [{
+                            "subresource": "scale",
+                            "responseKind": { "group": "autoscaling", "version": "v1", "kind": "Scale" },
+                            "verbs": ["get", "patch", "update"]
+                        }, {
+                            "subresource": "status",
+                            "responseKind": { "group": "", "version": "", "kind": "Deployment" },
+                            "verbs": ["get", "patch", "update"]
+                        }]
+                    }],
+                    "freshness": "Current"
+                }, {
+                    "version": "v1beta1",
+                    "resources": [],
+                    "freshness": "Stale"
+                }]
+            }]
+        }))
+        .unwrap();
+
+        let group = list.items.into_iter().next().unwrap();
+        let group = ApiGroup::from_aggregated(group).unwrap();
+        assert_eq!(group.name(), "apps");
+        assert_eq!(group.versions().collect::<Vec<_>>(), ["v1"]);
+        assert_eq!(group.preferred_version(), Some("v1"));
+
+        let (ar, caps) = group.recommended_kind("Deployment").unwrap();
+        assert_eq!(ar, ApiResource {
+            group: "apps".into(),
+            version: "v1".into(),
+            api_version: "apps/v1".into(),
+            kind: "Deployment".into(),
+            plural: "deployments".into(),
+        });
+        assert_eq!(caps.scope, Scope::Namespaced);
+        assert!(caps.supports_operation("watch"));
+
+        let (scale, scale_caps) = caps
+            .subresources
+            .iter()
+            .find(|(ar, _)| ar.plural == "scale")
+            .unwrap();
+        assert_eq!(
+            (scale.group.as_str(), scale.kind.as_str()),
+            ("autoscaling", "Scale")
+        );
+        assert_eq!(scale.api_version, "apps/v1");
+        assert_eq!(scale_caps.scope, Scope::Namespaced);
+        assert_eq!(scale_caps.operations, ["get", "patch", "update"]);
+        assert_eq!(caps.subresources.len(), 2);
+    }
+}
@@ -1,4 +1,7 @@
-use super::parse::{self, GroupVersionData};
+use super::{
+    aggregated::APIGroupDiscovery,
+    parse::{self, GroupVersionData},
+};
 use crate::{error::DiscoveryError, Client, Error, Result};
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::{APIGroup, APIVersions};
 pub use kube_core::discovery::{ApiCapabilities, ApiResource};
@@ -72,11 +75,11 @@ use std::{cmp::Reverse, collections::HashMap, iter::Iterator};
 /// [`ApiGroup::recommended_kind`]: crate::discovery::ApiGroup::recommended_kind
 pub struct ApiGroup {
     /// Name of the group e.g. apiregistration.k8s.io
-    name: String,
+    pub(crate) name: String,
     /// List of resource information, capabilities at particular versions
-    data: Vec<GroupVersionData>,
+    pub(crate) data: Vec<GroupVersionData>,
     /// Preferred version if exported by the `APIGroup`
-    preferred: Option<String>,
+    pub(crate) preferred: Option<String>,
 }
 
 /// Internal queriers to convert from an APIGroup (or APIVersions for core) to our ApiGroup
@@ -124,6 +127,44 @@ impl ApiGroup {
         Ok(group)
     }
 
+    /// Converts a group from aggregated discovery, where versions are listed in order of preference
+    ///
+    /// Versions that the server could not refresh are skipped, and `None` is returned if none remain.
+    pub(crate) fn from_aggregated(g: APIGroupDiscovery) -> Option<Self> {
+        let name = g.name().to_string();
+        let data: Vec<_> = g
+            .versions
+            .into_iter()
+            .filter(|v| {
+                if v.is_stale() {
+                    tracing::warn!(
+                        group = name.as_str(),
+                        version = v.version.as_str(),
+                        "Skipping stale version"
+                    );
+                }
+                !v.is_stale()
+            })
+            .map(|v| GroupVersionData::from_aggregated(&name, v))
+            .collect();
+        if data.is_empty() {
+            tracing::warn!(group = name.as_str(), "Skipping group without current versions");
+            return None;
+        }
+        let preferred = if name == ApiGroup::CORE_GROUP {
+            "v1".to_string()
+        } else {
+            data[0].version.clone()
+        };
+        let mut group = ApiGroup {
+            name,
+            data,
+            preferred: Some(preferred),
+        };
+        group.sort_versions();
+        Some(group)
+    }
+
     fn sort_versions(&mut self) {
         self.data
             .sort_by_cached_key(|gvd| Reverse(Version::parse(gvd.version.as_str()).priority()))
@@ -322,6 +363,36 @@ impl ApiGroup {
         }
         None
     }
+
+    /// Returns a copy of this group without the resource with the plural name `plural`, at any version
+    ///
+    /// Versions that no longer serve any resources are left out, and `None` is returned if no version remains.
+    /// This is useful to forget a resource that is known to be removed, such as the resource of a deleted CRD,
+    /// without waiting for the api server to stop serving it.
+    pub fn without_resource(&self, plural: &str) -> Option<ApiGroup> {
+        let data: Vec<_> = self
+            .data
+            .iter()
+            .filter_map(|gvd| {
+                let mut gvd = gvd.clone();
+                gvd.resources.retain(|(ar, _)| ar.plural != plural);
+                gvd.names.remove(plural);
+                (!gvd.resources.is_empty()).then_some(gvd)
+            })
+            .collect();
+        if data.is_empty() {
+            return None;
+        }
+        let preferred = self
+            .preferred
+            .clone()
+            .filter(|preferred| data.iter().any(|gvd| &gvd.version == preferred));
+        Some(ApiGroup {
+            name: self.name.clone(),
+            data,
+            preferred,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -367,14 +438,17 @@ mod tests {
                 GroupVersionData {
                     version: "v1alpha1".to_string(),
                     resources: vec![(testlowversioncr_v1alpha1, ac.clone())],
+                    names: HashMap::new(),
                 },
                 GroupVersionData {
                     version: "v1".to_string(),
                     resources: vec![(testcr_v1, ac.clone())],
+                    names: HashMap::new(),
                 },
                 GroupVersionData {
                     version: "v2alpha1".to_string(),
                     resources: vec![(testcr_v2alpha1, ac)],
+                    names: HashMap::new(),
                 },
             ],
             preferred: Some(String::from("v1")),
@@ -393,5 +467,11 @@ mod tests {
                 .any(|(ar, _)| ar.kind == "TestLowVersionCr" && ar.version == "v1alpha1"),
             "lost low version resource"
         );
+
+        let without = group.without_resource("testcrs").unwrap();
+        assert_eq!(without.versions().collect::<Vec<_>>(), ["v1alpha1"]);
+        assert_eq!(without.preferred_version(), None);
+        assert!(without.recommended_kind("TestLowVersionCr").is_some());
+        assert!(without.without_resource("testlowversioncrs").is_none());
     }
 }
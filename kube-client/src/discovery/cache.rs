@@ -0,0 +1,258 @@
+//! Persisting [`Discovery`](super::Discovery) results between runs
+use super::{parse::GroupVersionData, ApiGroup, DiscoveryMode};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+const CACHE_FILE: &str = "discovery.json";
+
+/// The default time to live for cached discovery results, matching kubectl
+const DEFAULT_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// An on-disk cache for [`Discovery`](super::Discovery) results
+///
+/// Results are stored in a subdirectory per api server, similar to kubectl's `~/.kube/cache/discovery`.
+/// They are reused until they are older than the [`ttl`](DiscoveryCache::ttl),
+/// or until [`Discovery::resolve_gvk_or_refresh`](super::Discovery::resolve_gvk_or_refresh)
+/// fails to find a kind in them.
+///
+/// The files are read and written on the blocking thread pool of the tokio runtime.
+///
+/// ```no_run
+/// use kube::{discovery::{Discovery, DiscoveryCache}, Client, Config};
+/// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+/// let config = Config::infer().await?;
+/// let cache = DiscoveryCache::new("/home/user/.kube/cache/kube-rs", &config.cluster_url);
+/// let client = Client::try_from(config)?;
+/// let discovery = Discovery::new(client).with_cache(cache).run().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct DiscoveryCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl DiscoveryCache {
+    /// Cache results for the api server at `cluster_url` in a subdirectory of `dir`
+    pub fn new(dir: impl Into<PathBuf>, cluster_url: &http::Uri) -> Self {
+        Self {
+            dir: dir.into().join(server_dir(cluster_url)),
+            ttl: DEFAULT_TTL,
+        }
+    }
+
+    /// Set how long cached results are used before discovery is run again
+    ///
+    /// Defaults to 6 hours.
+    #[must_use]
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Remove the cached results, so that the next run queries the api server
+    pub async fn invalidate(&self) -> io::Result<()> {
+        let path = self.path();
+        match blocking(move || fs::remove_file(path)).await {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    fn path(&self) -> PathBuf {
+        self.dir.join(CACHE_FILE)
+    }
+
+    /// Load fresh results that were discovered with the same `mode`
+    pub(super) async fn load(&self, mode: &DiscoveryMode) -> Option<HashMap<String, ApiGroup>> {
+        let (path, ttl) = (self.path(), self.ttl);
+        let cached = blocking(move || Ok(read_fresh(&path, ttl))).await.ok()??;
+        let path = self.path();
+        if cached.mode != *mode {
+            tracing::debug!(path = %path.display(), "Cached discovery used a different filter");
+            return None;
+        }
+        let groups = cached
+            .groups
+            .into_iter()
+            .map(|g| {
+                let group = ApiGroup {
+                    name: g.name,
+                    data: g.versions,
+                    preferred: g.preferred,
+                };
+                (group.name.clone(), group)
+            })
+            .collect();
+        Some(groups)
+    }
+
+    /// Replace the cached results
+    pub(super) async fn store(
+        &self,
+        mode: &DiscoveryMode,
+        groups: &HashMap<String, ApiGroup>,
+    ) -> io::Result<()> {
+        let cached = CachedRef {
+            mode,
+            groups: groups
+                .values()
+                .map(|g| CachedGroupRef {
+                    name: &g.name,
+                    preferred: g.preferred.as_deref(),
+                    versions: &g.data,
+                })
+                .collect(),
+        };
+        let data = serde_json::to_vec(&cached)?;
+        let (dir, path) = (self.dir.clone(), self.path());
+        blocking(move || {
+            fs::create_dir_all(&dir)?;
+            // Write to a temporary file first, so that concurrent runs never read a partial file
+            let tmp = dir.join(format!(".{CACHE_FILE}.{}", std::process::id()));
+            fs::write(&tmp, data)?;
+            fs::rename(&tmp, path)
+        })
+        .await
+    }
+}
+
+/// Read the cached results at `path`, unless they are older than `ttl`
+fn read_fresh(path: &Path, ttl: Duration) -> Option<Cached> {
+    let age = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()?
+        .elapsed()
+        .ok()?;
+    if age >= ttl {
+        tracing::debug!(path = %path.display(), "Cached discovery expired");
+        return None;
+    }
+    match fs::read(path).map(|data| serde_json::from_slice(&data)) {
+        Ok(Ok(cached)) => Some(cached),
+        Ok(Err(err)) => {
+            tracing::warn!(path = %path.display(), "Ignoring invalid cached discovery: {err}");
+            None
+        }
+        Err(err) => {
+            tracing::debug!(path = %path.display(), "Failed to read cached discovery: {err}");
+            None
+        }
+    }
+}
+
+/// Run file system operations on the blocking thread pool, so they do not stall the async runtime
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> io::Result<T> + Send + 'static) -> io::Result<T> {
+    tokio::task::spawn_blocking(f).await.map_err(io::Error::other)?
+}
+
+/// A directory name for the api server, from its host, port and path
+fn server_dir(cluster_url: &http::Uri) -> String {
+    let authority = cluster_url.authority().map(|a| a.as_str()).unwrap_or_default();
+    let server = format!("{authority}{}", cluster_url.path().trim_end_matches('/'));
+    server
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct Cached {
+    mode: DiscoveryMode,
+    groups: Vec<CachedGroup>,
+}
+
+#[derive(Deserialize)]
+struct CachedGroup {
+    name: String,
+    preferred: Option<String>,
+    versions: Vec<GroupVersionData>,
+}
+
+#[derive(Serialize)]
+struct CachedRef<'a> {
+    mode: &'a DiscoveryMode,
+    groups: Vec<CachedGroupRef<'a>>,
+}
+
+#[derive(Serialize)]
+struct CachedGroupRef<'a> {
+    name: &'a str,
+    preferred: Option<&'a str>,
+    versions: &'a [GroupVersionData],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kube_core::discovery::{ApiCapabilities, ApiResource, Scope};
+
+    #[test]
+    fn server_dir_is_filesystem_safe() {
+        let url = "https://10.0.0.1:6443".parse().unwrap();
+        assert_eq!(server_dir(&url), "10.0.0.1_6443");
+        let url = "https://rancher.example.com/k8s/clusters/c-abc12/"
+            .parse()
+            .unwrap();
+        assert_eq!(server_dir(&url), "rancher.example.com_k8s_clusters_c_abc12");
+    }
+
+    #[tokio::test]
+    async fn cache_roundtrip_respects_ttl_and_mode() {
+        let dir = std::env::temp_dir().join(format!("kube-discovery-cache-{}", std::process::id()));
+        let cache = DiscoveryCache::new(&dir, &"https://127.0.0.1:6443".parse().unwrap());
+        let mode = DiscoveryMode::Block(vec![]);
+        let ar = ApiResource {
+            group: "apps".into(),
+            version: "v1".into(),
+            api_version: "apps/v1".into(),
+            kind: "Deployment".into(),
+            plural: "deployments".into(),
+        };
+        let caps = ApiCapabilities {
+            scope: Scope::Namespaced,
+            subresources: vec![],
+            operations: vec!["get".into()],
+        };
+        let group = ApiGroup {
+            name: "apps".into(),
+            data: vec![GroupVersionData {
+                version: "v1".into(),
+                resources: vec![(ar.clone(), caps)],
+                names: HashMap::new(),
+            }],
+            preferred: Some("v1".into()),
+        };
+        cache
+            .store(&mode, &HashMap::from([("apps".into(), group)]))
+            .await
+            .unwrap();
+
+        let groups = cache.load(&mode).await.unwrap();
+        let (found, caps) = groups["apps"].recommended_kind("Deployment").unwrap();
+        assert_eq!((found, caps.scope), (ar, Scope::Namespaced));
+
+        assert!(cache
+            .load(&DiscoveryMode::Allow(vec!["apps".into()]))
+            .await
+            .is_none());
+        assert!(cache.clone().ttl(Duration::ZERO).load(&mode).await.is_none());
+
+        cache.invalidate().await.unwrap();
+        assert!(cache.load(&mode).await.is_none());
+        cache.invalidate().await.unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
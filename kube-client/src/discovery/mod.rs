@@ -1,18 +1,27 @@
 //! High-level utilities for runtime API discovery.
 
 use crate::{Client, Result};
+use aggregated::Discovered;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{APIGroupList, APIVersions};
 pub use kube_core::discovery::{verbs, ApiCapabilities, ApiResource, Scope};
 use kube_core::gvk::GroupVersionKind;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+mod aggregated;
 mod apigroup;
+mod cache;
 pub mod oneshot;
 pub use apigroup::ApiGroup;
+pub use cache::DiscoveryCache;
 mod parse;
+mod rest_mapper;
+pub use rest_mapper::RestMapper;
 
 // re-export one-shots
 pub use oneshot::{group, pinned_group, pinned_kind};
 
 /// How the Discovery client decides what api groups to scan
+#[derive(PartialEq, Serialize, Deserialize)]
 enum DiscoveryMode {
     /// Only allow explicitly listed apigroups
     Allow(Vec<String>),
@@ -45,6 +54,8 @@ impl DiscoveryMode {
 /// To make use of discovered apis, extract one or more [`ApiGroup`]s from it,
 /// or resolve a precise one using [`Discovery::resolve_gvk`](crate::discovery::Discovery::resolve_gvk).
 ///
+/// Results can also be persisted between runs with a [`DiscoveryCache`].
+///
 /// If caching of results is __not required__, then a simpler [`oneshot`](crate::discovery::oneshot) discovery system can be used.
 ///
 /// [`ApiGroup`]: crate::discovery::ApiGroup
@@ -53,6 +64,8 @@ pub struct Discovery {
     client: Client,
     groups: HashMap<String, ApiGroup>,
     mode: DiscoveryMode,
+    cache: Option<DiscoveryCache>,
+    from_cache: bool,
 }
 
 /// Caching discovery interface
@@ -64,7 +77,13 @@ impl Discovery {
     pub fn new(client: Client) -> Self {
         let groups = HashMap::new();
         let mode = DiscoveryMode::Block(vec![]);
-        Self { client, groups, mode }
+        Self {
+            client,
+            groups,
+            mode,
+            cache: None,
+            from_cache: false,
+        }
     }
 
     /// Configure the discovery client to only look for the listed apigroups
@@ -81,11 +100,23 @@ impl Discovery {
         self
     }
 
+    /// Persist results in an on-disk [`DiscoveryCache`], and reuse them while they are fresh
+    #[must_use]
+    pub fn with_cache(mut self, cache: DiscoveryCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
     /// Runs or re-runs the configured discovery algorithm and updates/populates the cache
     ///
-    /// The cache is empty cleared when this is started. By default, every api group found is checked,
+    /// The cache is empty cleared when this is started. Servers supporting aggregated discovery
+    /// (`apidiscovery.k8s.io/v2`, Kubernetes 1.30+) describe every api group in `2` queries.
+    /// Older servers fall back to checking every api group found,
     /// causing `N+2` queries to the api server (where `N` is number of api groups).
     ///
+    /// With a [`DiscoveryCache`], fresh results from a previous run are loaded instead of querying the api server,
+    /// and new results are stored in it.
+    ///
     /// ```no_run
     /// use kube::{Client, api::{Api, DynamicObject}, discovery::{Discovery, verbs, Scope}, ResourceExt};
     /// #[tokio::main]
@@ -109,24 +140,85 @@ impl Discovery {
     /// ```
     /// See a bigger example in [examples/dynamic.api](https://github.com/kube-rs/kube/blob/main/examples/dynamic_api.rs)
     pub async fn run(mut self) -> Result<Self> {
+        if let Some(cache) = &self.cache {
+            if let Some(groups) = cache.load(&self.mode).await {
+                self.groups = groups;
+                self.from_cache = true;
+                return Ok(self);
+            }
+        }
+        self.discover().await?;
+        Ok(self)
+    }
+
+    /// Finds an [`ApiResource`] and its [`ApiCapabilities`] like [`Discovery::resolve_gvk`],
+    /// but reruns discovery if the kind is missing from results loaded from the [`DiscoveryCache`]
+    ///
+    /// This picks up resources that were installed after the results were cached, such as new CRDs.
+    pub async fn resolve_gvk_or_refresh(
+        &mut self,
+        gvk: &GroupVersionKind,
+    ) -> Result<Option<(ApiResource, ApiCapabilities)>> {
+        if let Some(found) = self.resolve_gvk(gvk) {
+            return Ok(Some(found));
+        }
+        if !self.from_cache {
+            return Ok(None);
+        }
+        if let Some(cache) = &self.cache {
+            if let Err(err) = cache.invalidate().await {
+                tracing::warn!("Failed to invalidate cached discovery: {err}");
+            }
+        }
+        self.discover().await?;
+        Ok(self.resolve_gvk(gvk))
+    }
+
+    async fn discover(&mut self) -> Result<()> {
         self.groups.clear();
-        let api_groups = self.client.list_api_groups().await?;
         // query regular groups + crds under /apis
-        for g in api_groups.groups {
-            let key = g.name.clone();
-            if self.mode.is_queryable(&key) {
-                let apigroup = ApiGroup::query_apis(&self.client, g).await?;
-                self.groups.insert(key, apigroup);
+        match aggregated::discover::<APIGroupList>(&self.client, "/apis").await? {
+            Discovered::Aggregated(list) => {
+                for g in list.items {
+                    if self.mode.is_queryable(&g.name().to_string()) {
+                        if let Some(apigroup) = ApiGroup::from_aggregated(g) {
+                            self.groups.insert(apigroup.name().to_string(), apigroup);
+                        }
+                    }
+                }
+            }
+            Discovered::Legacy(api_groups) => {
+                for g in api_groups.groups {
+                    let key = g.name.clone();
+                    if self.mode.is_queryable(&key) {
+                        let apigroup = ApiGroup::query_apis(&self.client, g).await?;
+                        self.groups.insert(key, apigroup);
+                    }
+                }
             }
         }
         // query core versions under /api
         let corekey = ApiGroup::CORE_GROUP.to_string();
         if self.mode.is_queryable(&corekey) {
-            let coreapis = self.client.list_core_api_versions().await?;
-            let apigroup = ApiGroup::query_core(&self.client, coreapis).await?;
-            self.groups.insert(corekey, apigroup);
+            let apigroup = match aggregated::discover::<APIVersions>(&self.client, "/api").await? {
+                Discovered::Aggregated(list) => list
+                    .items
+                    .into_iter()
+                    .find(|g| g.name() == ApiGroup::CORE_GROUP)
+                    .and_then(ApiGroup::from_aggregated),
+                Discovered::Legacy(coreapis) => Some(ApiGroup::query_core(&self.client, coreapis).await?),
+            };
+            if let Some(apigroup) = apigroup {
+                self.groups.insert(corekey, apigroup);
+            }
         }
-        Ok(self)
+        self.from_cache = false;
+        if let Some(cache) = &self.cache {
+            if let Err(err) = cache.store(&self.mode, &self.groups).await {
+                tracing::warn!("Failed to cache discovery: {err}");
+            }
+        }
+        Ok(())
     }
 }
 
@@ -137,6 +229,11 @@ impl Discovery {
         self.groups.values()
     }
 
+    /// Returns all served groups, consuming the cache
+    pub fn into_groups(self) -> impl Iterator<Item = ApiGroup> {
+        self.groups.into_values()
+    }
+
     /// Returns a sorted vector of all served groups
     ///
     /// This vector is in kubectl's normal alphabetical group order
@@ -157,6 +254,11 @@ impl Discovery {
         self.groups.contains_key(group)
     }
 
+    /// Returns a [`RestMapper`] to resolve the resource names accepted by kubectl, like `deploy`
+    pub fn rest_mapper(&self) -> RestMapper<'_> {
+        RestMapper::new(self.groups())
+    }
+
     /// Finds an [`ApiResource`] and its [`ApiCapabilities`] after discovery by matching a GVK
     ///
     /// This is for quick extraction after having done a complete discovery.
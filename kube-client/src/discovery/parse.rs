@@ -5,6 +5,8 @@ use kube_core::{
     discovery::{ApiCapabilities, ApiResource, Scope},
     gvk::{GroupVersion, ParseGroupVersionError},
 };
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Creates an `ApiResource` from a `meta::v1::APIResource` instance + its groupversion.
 ///
@@ -60,22 +62,40 @@ pub(crate) fn parse_apicapabilities(list: &APIResourceList, name: &str) -> Resul
 }
 
 /// Internal resource information and capabilities for a particular ApiGroup at a particular version
+#[derive(Clone, Serialize, Deserialize)]
 pub(crate) struct GroupVersionData {
     /// Pinned api version
     pub(crate) version: String,
     /// Pair of dynamic resource info along with what it supports.
     pub(crate) resources: Vec<(ApiResource, ApiCapabilities)>,
+    /// Alternative names of resources, keyed by their plural
+    #[serde(default)]
+    pub(crate) names: HashMap<String, ResourceNames>,
+}
+
+/// The names a resource can be referred to by, other than its plural and kind
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct ResourceNames {
+    /// Singular name, e.g. `deployment`
+    pub(crate) singular: String,
+    /// Short names, e.g. `deploy`
+    pub(crate) short_names: Vec<String>,
 }
 
 impl GroupVersionData {
     /// Given an APIResourceList, extract all information for a given version
     pub(crate) fn new(version: String, list: APIResourceList) -> Result<Self> {
         let mut resources = vec![];
+        let mut names = HashMap::new();
         for res in &list.resources {
             // skip subresources
             if res.name.contains('/') {
                 continue;
             }
+            names.insert(res.name.clone(), ResourceNames {
+                singular: res.singular_name.clone(),
+                short_names: res.short_names.clone().unwrap_or_default(),
+            });
             // NB: these two should be infallible from discovery when k8s api is well-behaved, but..
             let ar = parse_apiresource(res, &list.group_version).map_err(|ParseGroupVersionError(s)| {
                 Error::Discovery(DiscoveryError::InvalidGroupVersion(s))
@@ -83,6 +103,10 @@ impl GroupVersionData {
             let caps = parse_apicapabilities(&list, &res.name)?;
             resources.push((ar, caps));
         }
-        Ok(GroupVersionData { version, resources })
+        Ok(GroupVersionData {
+            version,
+            resources,
+            names,
+        })
     }
 }
@@ -0,0 +1,296 @@
+//! Resolving the resource names users type, like kubectl
+use super::{parse::GroupVersionData, ApiGroup};
+use crate::{error::DiscoveryError, Error, Result};
+use kube_core::discovery::{ApiCapabilities, ApiResource};
+
+/// Resolves the resource names accepted by kubectl to discovered resources
+///
+/// A name can be any of:
+/// - a plural (`deployments`), singular (`deployment`), kind (`Deployment`) or short name (`deploy`)
+/// - any of those qualified by a group (`deployments.apps`)
+/// - any of those qualified by a version and group (`Certificate.v1.cert-manager.io`)
+///
+/// Names are matched case-insensitively. Unless a version is given, the preferred version of a group is used
+/// if it serves the resource, and the most stable version otherwise.
+///
+/// Plurals take precedence over singulars and kinds, which take precedence over short names.
+/// If a name still matches resources in several groups, the core group is used if it is one of them,
+/// like `events` in kubectl. Otherwise resolution fails with [`DiscoveryError::AmbiguousResource`].
+///
+/// ```no_run
+/// use kube::{api::{Api, DynamicObject}, discovery::Discovery, Client};
+/// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+/// # let client: Client = todo!();
+/// let discovery = Discovery::new(client.clone()).run().await?;
+/// let (ar, caps) = discovery.rest_mapper().resolve("deploy")?;
+/// let api: Api<DynamicObject> = Api::default_namespaced_with(client, &ar);
+/// # Ok(())
+/// # }
+/// ```
+pub struct RestMapper<'a> {
+    groups: Vec<&'a ApiGroup>,
+}
+
+/// How closely a name matched a resource, from weakest to strongest
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Match {
+    ShortName,
+    Singular,
+    Plural,
+}
+
+struct Candidate<'a> {
+    strength: Match,
+    group: &'a str,
+    resource: &'a (ApiResource, ApiCapabilities),
+}
+
+impl<'a> RestMapper<'a> {
+    /// Create a mapper over discovered groups
+    pub fn new(groups: impl IntoIterator<Item = &'a ApiGroup>) -> Self {
+        let mut groups: Vec<_> = groups.into_iter().collect();
+        groups.sort_by_key(|g| g.name());
+        Self { groups }
+    }
+
+    /// Resolve a resource name to an [`ApiResource`] and its [`ApiCapabilities`]
+    ///
+    /// Returns [`DiscoveryError::UnknownResource`] if no resource matches,
+    /// and [`DiscoveryError::AmbiguousResource`] if the name matches resources in several groups.
+    pub fn resolve(&self, name: &str) -> Result<(ApiResource, ApiCapabilities)> {
+        let input = name.to_lowercase();
+        let mut interpretations = vec![];
+        // NB: resource names never contain dots, so anything after the first dot qualifies the name
+        let parts: Vec<&str> = input.splitn(3, '.').collect();
+        if let [resource, version, group] = parts[..] {
+            interpretations.push((resource, Some(version), Some(group)));
+        }
+        match input.split_once('.') {
+            Some((resource, group)) => interpretations.push((resource, None, Some(group))),
+            None => interpretations.push((input.as_str(), None, None)),
+        }
+
+        for (resource, version, group) in interpretations {
+            let mut candidates = self.candidates(resource, version, group);
+            let Some(strongest) = candidates.iter().map(|c| c.strength).max() else {
+                continue;
+            };
+            candidates.retain(|c| c.strength == strongest);
+            // NB: groups are sorted by name, so a candidate in the core group comes first
+            if candidates[0].group == ApiGroup::CORE_GROUP {
+                candidates.truncate(1);
+            }
+            if let [candidate] = &candidates[..] {
+                return Ok(candidate.resource.clone());
+            }
+            return Err(Error::Discovery(DiscoveryError::AmbiguousResource {
+                name: name.to_string(),
+                candidates: candidates
+                    .iter()
+                    .map(|c| {
+                        let ar = &c.resource.0;
+                        if c.group.is_empty() {
+                            format!("{}.{}", ar.plural, ar.version)
+                        } else {
+                            format!("{}.{}.{}", ar.plural, ar.version, c.group)
+                        }
+                    })
+                    .collect(),
+            }));
+        }
+        Err(Error::Discovery(DiscoveryError::UnknownResource(
+            name.to_string(),
+        )))
+    }
+
+    /// The best match of `resource` in each group, at the preferred or most stable version serving it
+    fn candidates(&self, resource: &str, version: Option<&str>, group: Option<&str>) -> Vec<Candidate<'a>> {
+        let mut candidates = vec![];
+        for g in &self.groups {
+            if group.is_some_and(|group| group != g.name()) {
+                continue;
+            }
+            // NB: data is sorted by version priority, so only the preferred version needs to move to the front
+            let preferred = g
+                .data
+                .iter()
+                .filter(|gvd| g.preferred.as_deref() == Some(&gvd.version));
+            let others = g
+                .data
+                .iter()
+                .filter(|gvd| g.preferred.as_deref() != Some(&gvd.version));
+            let best = preferred
+                .chain(others)
+                .filter(|gvd| version.map_or(true, |version| version == gvd.version))
+                .find_map(|gvd| best_match(gvd, resource));
+            if let Some((strength, resource)) = best {
+                candidates.push(Candidate {
+                    strength,
+                    group: g.name(),
+                    resource,
+                });
+            }
+        }
+        candidates
+    }
+}
+
+/// The strongest match of `name` among the resources of a version
+fn best_match<'a>(
+    gvd: &'a GroupVersionData,
+    name: &str,
+) -> Option<(Match, &'a (ApiResource, ApiCapabilities))> {
+    gvd.resources
+        .iter()
+        .filter_map(|res| {
+            let ar = &res.0;
+            let names = gvd.names.get(&ar.plural);
+            let strength = if ar.plural == name {
+                Match::Plural
+            } else if ar.kind.to_lowercase() == name || names.is_some_and(|n| n.singular == name) {
+                Match::Singular
+            } else if names.is_some_and(|n| n.short_names.iter().any(|short| short == name)) {
+                Match::ShortName
+            } else {
+                return None;
+            };
+            Some((strength, res))
+        })
+        .max_by_key(|(strength, _)| *strength)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::parse::ResourceNames;
+    use kube_core::discovery::Scope;
+    use std::collections::HashMap;
+
+    /// Kind, plural and short names of resources
+    type Resources<'a> = &'a [(&'a str, &'a str, &'a [&'a str])];
+
+    fn group(name: &str, preferred: &str, versions: &[(&str, Resources)]) -> ApiGroup {
+        let data = versions
+            .iter()
+            .map(|(version, resources)| {
+                let api_version = if name.is_empty() {
+                    version.to_string()
+                } else {
+                    format!("{name}/{version}")
+                };
+                let mut names = HashMap::new();
+                let resources = resources
+                    .iter()
+                    .map(|(kind, plural, short_names)| {
+                        names.insert(plural.to_string(), ResourceNames {
+                            singular: kind.to_lowercase(),
+                            short_names: short_names.iter().map(ToString::to_string).collect(),
+                        });
+                        let ar = ApiResource {
+                            group: name.into(),
+                            version: version.to_string(),
+                            api_version: api_version.clone(),
+                            kind: kind.to_string(),
+                            plural: plural.to_string(),
+                        };
+                        let caps = ApiCapabilities {
+                            scope: Scope::Namespaced,
+                            subresources: vec![],
+                            operations: vec![],
+                        };
+                        (ar, caps)
+                    })
+                    .collect();
+                GroupVersionData {
+                    version: version.to_string(),
+                    resources,
+                    names,
+                }
+            })
+            .collect();
+        ApiGroup {
+            name: name.into(),
+            data,
+            preferred: Some(preferred.into()),
+        }
+    }
+
+    fn groups() -> Vec<ApiGroup> {
+        vec![
+            group("", "v1", &[("v1", &[
+                ("Pod", "pods", &["po"]),
+                ("Event", "events", &["ev"]),
+            ])]),
+            group("apps", "v1", &[("v1", &[("Deployment", "deployments", &[
+                "deploy",
+            ])])]),
+            group("events.k8s.io", "v1", &[("v1", &[("Event", "events", &["ev"])])]),
+            group("cert-manager.io", "v1", &[
+                ("v1", &[("Certificate", "certificates", &["cert", "certs"])]),
+                ("v1alpha2", &[
+                    ("Certificate", "certificates", &["cert", "certs"]),
+                    ("Issuer", "issuers", &[]),
+                ]),
+            ]),
+            group("networking.example.com", "v1", &[("v1", &[(
+                "Certificate",
+                "certificates",
+                &["cert"],
+            )])]),
+        ]
+    }
+
+    fn resolve(name: &str) -> Result<String> {
+        let groups = groups();
+        let (ar, _) = RestMapper::new(&groups).resolve(name)?;
+        Ok(format!("{}.{}.{}", ar.plural, ar.version, ar.group))
+    }
+
+    #[test]
+    fn resolves_kubectl_names() {
+        assert_eq!(resolve("po").unwrap(), "pods.v1.");
+        assert_eq!(resolve("deploy").unwrap(), "deployments.v1.apps");
+        assert_eq!(resolve("Deployment").unwrap(), "deployments.v1.apps");
+        assert_eq!(resolve("deployment").unwrap(), "deployments.v1.apps");
+        assert_eq!(resolve("deployments.apps").unwrap(), "deployments.v1.apps");
+        assert_eq!(resolve("deploy.apps").unwrap(), "deployments.v1.apps");
+        assert_eq!(
+            resolve("Certificate.v1alpha2.cert-manager.io").unwrap(),
+            "certificates.v1alpha2.cert-manager.io"
+        );
+        assert_eq!(
+            resolve("certificates.cert-manager.io").unwrap(),
+            "certificates.v1.cert-manager.io"
+        );
+        // only served at a non-preferred version
+        assert_eq!(resolve("issuer").unwrap(), "issuers.v1alpha2.cert-manager.io");
+        // the core group wins ties
+        assert_eq!(resolve("events").unwrap(), "events.v1.");
+        assert_eq!(
+            resolve("events.events.k8s.io").unwrap(),
+            "events.v1.events.k8s.io"
+        );
+    }
+
+    #[test]
+    fn reports_unknown_and_ambiguous_names() {
+        assert!(matches!(
+            resolve("nope"),
+            Err(Error::Discovery(DiscoveryError::UnknownResource(name))) if name == "nope"
+        ));
+        assert!(matches!(
+            resolve("deployments.v2.apps"),
+            Err(Error::Discovery(DiscoveryError::UnknownResource(_)))
+        ));
+        match resolve("cert") {
+            Err(Error::Discovery(DiscoveryError::AmbiguousResource { name, candidates })) => {
+                assert_eq!(name, "cert");
+                assert_eq!(candidates, [
+                    "certificates.v1.cert-manager.io",
+                    "certificates.v1.networking.example.com"
+                ]);
+            }
+            other => panic!("expected an ambiguous resource, got {other:?}"),
+        }
+    }
+}
@@ -2,7 +2,7 @@
 use http::Uri;
 use thiserror::Error;
 
-pub use kube_core::ErrorResponse;
+pub use kube_core::{ErrorResponse, FieldError, FieldErrorKind, StatusReason};
 
 /// Possible errors from the [`Client`](crate::Client)
 #[cfg_attr(docsrs, doc(cfg(any(feature = "config", feature = "client"))))]
@@ -55,6 +55,10 @@ pub enum Error {
     #[error("Error reading events stream: {0}")]
     ReadEvents(#[source] std::io::Error),
 
+    /// Returned on `std::io::Error` when reading a list response.
+    #[error("Error reading list response: {0}")]
+    ReadList(#[source] std::io::Error),
+
     /// Http based error
     #[error("HttpError: {0}")]
     HttpError(#[source] http::Error),
@@ -91,12 +95,30 @@ pub enum Error {
     #[error("TLS required but no TLS stack selected")]
     TlsRequired,
 
-    /// Failed to upgrade to a WebSocket connection
+    /// Failed to upgrade to a WebSocket or SPDY connection
     #[cfg(feature = "ws")]
     #[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
-    #[error("failed to upgrade to a WebSocket connection: {0}")]
+    #[error("failed to upgrade the connection: {0}")]
     UpgradeConnection(#[source] crate::client::UpgradeConnectionError),
 
+    /// Failed to bind a local port for port forwarding
+    #[cfg(feature = "ws")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
+    #[error("failed to bind local port {port}: {source}")]
+    BindLocalPort {
+        /// The local port that could not be bound.
+        port: u16,
+        /// The underlying IO error.
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// Failed to resolve a pod to port-forward to
+    #[cfg(feature = "ws")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
+    #[error("failed to resolve port-forward target: {0}")]
+    PortforwardTarget(#[source] crate::api::PortforwardTargetError),
+
     /// Errors related to client auth
     #[cfg(feature = "client")]
     #[cfg_attr(docsrs, doc(cfg(feature = "client")))]
@@ -110,6 +132,139 @@ pub enum Error {
     RefResolve(String),
 }
 
+impl Error {
+    /// The error returned by the apiserver, if the request reached it and was rejected
+    pub fn api_error(&self) -> Option<&ErrorResponse> {
+        match self {
+            Self::Api(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Whether the apiserver responded that the object or resource does not exist
+    ///
+    /// See [`ErrorResponse::is_not_found`].
+    pub fn is_not_found(&self) -> bool {
+        self.api_error().is_some_and(ErrorResponse::is_not_found)
+    }
+
+    /// Whether the apiserver responded that the object to create already exists
+    ///
+    /// See [`ErrorResponse::is_already_exists`].
+    pub fn is_already_exists(&self) -> bool {
+        self.api_error().is_some_and(ErrorResponse::is_already_exists)
+    }
+
+    /// Whether the apiserver responded that the request conflicts with the current object
+    ///
+    /// See [`ErrorResponse::is_conflict`].
+    pub fn is_conflict(&self) -> bool {
+        self.api_error().is_some_and(ErrorResponse::is_conflict)
+    }
+
+    /// Whether the apiserver responded that the request is not allowed
+    ///
+    /// See [`ErrorResponse::is_forbidden`].
+    pub fn is_forbidden(&self) -> bool {
+        self.api_error().is_some_and(ErrorResponse::is_forbidden)
+    }
+
+    /// Whether the apiserver throttled the request
+    ///
+    /// See [`ErrorResponse::is_too_many_requests`].
+    pub fn is_too_many_requests(&self) -> bool {
+        self.api_error().is_some_and(ErrorResponse::is_too_many_requests)
+    }
+
+    /// Whether the apiserver responded that the request did not complete in time
+    ///
+    /// See [`ErrorResponse::is_timeout`].
+    pub fn is_timeout(&self) -> bool {
+        self.api_error().is_some_and(ErrorResponse::is_timeout)
+    }
+
+    /// Whether the apiserver responded that the requested resource version is no longer available
+    ///
+    /// See [`ErrorResponse::is_gone`].
+    pub fn is_gone(&self) -> bool {
+        self.api_error().is_some_and(ErrorResponse::is_gone)
+    }
+
+    /// Whether the apiserver responded that the submitted object is invalid
+    ///
+    /// See [`ErrorResponse::is_invalid`] and [`Error::field_errors`].
+    pub fn is_invalid(&self) -> bool {
+        self.api_error().is_some_and(ErrorResponse::is_invalid)
+    }
+
+    /// The fields the apiserver rejected, if any
+    ///
+    /// See [`ErrorResponse::field_errors`].
+    pub fn field_errors(&self) -> Vec<FieldError> {
+        self.api_error()
+            .map(ErrorResponse::field_errors)
+            .unwrap_or_default()
+    }
+
+    /// Whether the same request may succeed if it is retried later
+    ///
+    /// This covers [retryable](ErrorResponse::is_retryable) responses from the apiserver,
+    /// as well as connection failures, timeouts, and connections closed while reading a response.
+    /// Other errors, like invalid requests or configuration, fail the same way when retried.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Api(err) => err.is_retryable(),
+            #[cfg(feature = "client")]
+            Self::HyperError(err) => is_transient(err),
+            #[cfg(feature = "client")]
+            Self::Service(err) => is_transient(err.as_ref()),
+            Self::ReadEvents(err) | Self::ReadList(err) => is_transient(err),
+            _ => false,
+        }
+    }
+}
+
+/// Whether `err`, or any error that caused it, is a transient connection failure
+fn is_transient(err: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        if let Some(err) = err.downcast_ref::<std::io::Error>() {
+            use std::io::ErrorKind;
+            if matches!(
+                err.kind(),
+                ErrorKind::TimedOut
+                    | ErrorKind::ConnectionRefused
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::NotConnected
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::UnexpectedEof
+                    | ErrorKind::Interrupted
+            ) {
+                return true;
+            }
+            // NB: the source of an io error skips the error it wraps, so check that one separately
+            if err.get_ref().is_some_and(|inner| is_transient(inner)) {
+                return true;
+            }
+        }
+        #[cfg(feature = "client")]
+        if let Some(err) = err.downcast_ref::<hyper::Error>() {
+            if err.is_timeout() || err.is_incomplete_message() || err.is_closed() || err.is_canceled() {
+                return true;
+            }
+        }
+        #[cfg(feature = "client")]
+        if let Some(err) = err.downcast_ref::<hyper_util::client::legacy::Error>() {
+            if err.is_connect() {
+                return true;
+            }
+        }
+        source = err.source();
+    }
+    false
+}
+
 #[derive(Error, Debug)]
 /// Possible errors when using API [discovery](crate::discovery)
 pub enum DiscoveryError {
@@ -132,4 +287,41 @@ pub enum DiscoveryError {
     /// Empty ApiGroup
     #[error("Empty Api Group: {0}")]
     EmptyApiGroup(String),
+
+    /// No resource matches a name
+    #[error("Unknown Resource: {0}")]
+    UnknownResource(String),
+
+    /// A name matches resources in several groups
+    #[error("Ambiguous Resource: {name} matches {}", candidates.join(", "))]
+    AmbiguousResource {
+        /// The name that was resolved
+        name: String,
+        /// The matching resources, as `resource.version.group`
+        candidates: Vec<String>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, ErrorResponse};
+
+    #[test]
+    fn classifies_transport_errors() {
+        let reset = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+        assert!(Error::ReadEvents(reset).is_retryable());
+        let eof = std::io::Error::from(std::io::ErrorKind::UnexpectedEof);
+        assert!(Error::ReadList(eof).is_retryable());
+        let wrapped = std::io::Error::other(std::io::Error::from(std::io::ErrorKind::TimedOut));
+        assert!(Error::Service(Box::new(wrapped)).is_retryable());
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert!(!Error::Service(Box::new(denied)).is_retryable());
+        assert!(!Error::TlsRequired.is_retryable());
+
+        let throttled = Error::Api(ErrorResponse::new("Failure", "slow down", "TooManyRequests", 429));
+        assert!(throttled.is_retryable());
+        assert!(throttled.is_too_many_requests());
+        assert!(!throttled.is_not_found());
+        assert!(throttled.field_errors().is_empty());
+    }
 }
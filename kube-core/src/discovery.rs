@@ -58,7 +58,7 @@ impl ApiResource {
 }
 
 /// Resource scope
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Scope {
     /// Objects are global
     Cluster,
@@ -87,7 +87,7 @@ pub mod verbs {
 }
 
 /// Contains the capabilities of an API resource
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiCapabilities {
     /// Scope of the resource
     pub scope: Scope,
@@ -1,9 +1,14 @@
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::response::StatusDetails;
+
 /// An error response from the API.
+///
+/// Fields may be added to this struct over time, so construct it with [`ErrorResponse::new`].
 #[derive(Error, Deserialize, Serialize, Debug, Clone, Eq, PartialEq)]
 #[error("{message}: {reason}")]
+#[non_exhaustive]
 pub struct ErrorResponse {
     /// The status
     pub status: String,
@@ -15,4 +20,393 @@ pub struct ErrorResponse {
     pub reason: String,
     /// The error code
     pub code: u16,
+    /// Extended data associated with the reason, like the invalid fields of an object
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Box<StatusDetails>>,
+}
+
+impl ErrorResponse {
+    /// Create an error response without [`details`](Self::details)
+    pub fn new(status: &str, message: &str, reason: &str, code: u16) -> Self {
+        Self {
+            status: status.to_string(),
+            message: message.to_string(),
+            reason: reason.to_string(),
+            code,
+            details: None,
+        }
+    }
+
+    /// Set the [`details`](Self::details) of the error
+    #[must_use]
+    pub fn with_details(mut self, details: StatusDetails) -> Self {
+        self.details = Some(Box::new(details));
+        self
+    }
+
+    /// The [`reason`](Self::reason) of the error, as a [`StatusReason`]
+    pub fn status_reason(&self) -> StatusReason {
+        StatusReason::from(self.reason.as_str())
+    }
+
+    /// Whether the error has the reason `reason`, or no known reason and the status code `code`
+    ///
+    /// This matches how `k8s.io/apimachinery/pkg/api/errors` classifies errors.
+    fn is_reason_or_code(&self, reason: StatusReason, code: u16) -> bool {
+        match self.status_reason() {
+            StatusReason::Other(_) => self.code == code,
+            known => known == reason,
+        }
+    }
+
+    /// Whether the requested object or resource does not exist
+    pub fn is_not_found(&self) -> bool {
+        self.is_reason_or_code(StatusReason::NotFound, 404)
+    }
+
+    /// Whether the object to create already exists
+    pub fn is_already_exists(&self) -> bool {
+        self.status_reason() == StatusReason::AlreadyExists
+    }
+
+    /// Whether the request conflicts with the current state of the object, like an outdated `resourceVersion`
+    pub fn is_conflict(&self) -> bool {
+        self.is_reason_or_code(StatusReason::Conflict, 409)
+    }
+
+    /// Whether the request was authenticated, but not allowed
+    pub fn is_forbidden(&self) -> bool {
+        self.is_reason_or_code(StatusReason::Forbidden, 403)
+    }
+
+    /// Whether the request was not authenticated
+    pub fn is_unauthorized(&self) -> bool {
+        self.is_reason_or_code(StatusReason::Unauthorized, 401)
+    }
+
+    /// Whether the request was rejected because too many requests are being made
+    pub fn is_too_many_requests(&self) -> bool {
+        self.is_reason_or_code(StatusReason::TooManyRequests, 429)
+    }
+
+    /// Whether the request did not complete in time, and may still complete
+    pub fn is_timeout(&self) -> bool {
+        self.is_reason_or_code(StatusReason::Timeout, 504)
+    }
+
+    /// Whether the server could not complete the request in time, and the request was not processed
+    pub fn is_server_timeout(&self) -> bool {
+        self.status_reason() == StatusReason::ServerTimeout
+    }
+
+    /// Whether the requested resource version is no longer available
+    pub fn is_gone(&self) -> bool {
+        self.is_reason_or_code(StatusReason::Gone, 410)
+    }
+
+    /// Whether the requested resource version or continue token has expired
+    pub fn is_resource_expired(&self) -> bool {
+        self.status_reason() == StatusReason::Expired
+    }
+
+    /// Whether the submitted object failed validation
+    ///
+    /// The invalid fields are listed by [`ErrorResponse::field_errors`].
+    pub fn is_invalid(&self) -> bool {
+        self.is_reason_or_code(StatusReason::Invalid, 422)
+    }
+
+    /// Whether the server failed with an unexpected error
+    pub fn is_internal_error(&self) -> bool {
+        self.is_reason_or_code(StatusReason::InternalError, 500)
+    }
+
+    /// Whether the server is temporarily unavailable
+    pub fn is_service_unavailable(&self) -> bool {
+        self.is_reason_or_code(StatusReason::ServiceUnavailable, 503)
+    }
+
+    /// Whether the same request may succeed if it is retried later
+    ///
+    /// This is the case for throttled requests, timeouts, and internal or unavailable servers.
+    pub fn is_retryable(&self) -> bool {
+        self.is_too_many_requests()
+            || self.is_timeout()
+            || self.is_server_timeout()
+            || self.is_internal_error()
+            || self.is_service_unavailable()
+    }
+
+    /// How long the server asked to wait before retrying, if it did
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        self.details
+            .as_ref()
+            .filter(|details| details.retry_after_seconds > 0)
+            .map(|details| std::time::Duration::from_secs(details.retry_after_seconds.into()))
+    }
+
+    /// The fields that caused the error, from the causes in its [`details`](Self::details)
+    ///
+    /// These are set for [invalid](Self::is_invalid) objects, and name the fields that failed validation.
+    pub fn field_errors(&self) -> Vec<FieldError> {
+        self.details
+            .iter()
+            .flat_map(|details| &details.causes)
+            .filter(|cause| !cause.field.is_empty())
+            .map(|cause| FieldError {
+                field: cause.field.clone(),
+                kind: FieldErrorKind::from(cause.reason.as_str()),
+                message: cause.message.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Define an enum of known string values, with a fallback for unknown ones
+macro_rules! string_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $value:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            /// Any other value, or an empty one if none was given
+            Other(String),
+        }
+
+        impl $name {
+            /// The string representation, as sent by the apiserver
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $value,)*
+                    Self::Other(value) => value,
+                }
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                match value {
+                    $($value => Self::$variant,)*
+                    other => Self::Other(other.to_string()),
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+    };
+}
+
+string_enum! {
+    /// A machine-readable reason for a failed request
+    ///
+    /// See the [`StatusReason`](https://pkg.go.dev/k8s.io/apimachinery/pkg/apis/meta/v1#StatusReason)
+    /// constants of apimachinery.
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    pub enum StatusReason {
+        /// The request was not authenticated (401)
+        Unauthorized = "Unauthorized",
+        /// The request was not allowed (403)
+        Forbidden = "Forbidden",
+        /// The requested object or resource does not exist (404)
+        NotFound = "NotFound",
+        /// The object to create already exists (409)
+        AlreadyExists = "AlreadyExists",
+        /// The request conflicts with the current state of the object (409)
+        Conflict = "Conflict",
+        /// The requested resource version is no longer available (410)
+        Gone = "Gone",
+        /// The submitted object failed validation (422)
+        Invalid = "Invalid",
+        /// The server could not process the request in time (500)
+        ServerTimeout = "ServerTimeout",
+        /// The request did not complete in time, and may still complete (504)
+        Timeout = "Timeout",
+        /// Too many requests are being made (429)
+        TooManyRequests = "TooManyRequests",
+        /// The request is malformed (400)
+        BadRequest = "BadRequest",
+        /// The action is not supported for the resource (405)
+        MethodNotAllowed = "MethodNotAllowed",
+        /// None of the requested media types can be returned (406)
+        NotAcceptable = "NotAcceptable",
+        /// The request body is too large (413)
+        RequestEntityTooLarge = "RequestEntityTooLarge",
+        /// The content type of the request body is not supported (415)
+        UnsupportedMediaType = "UnsupportedMediaType",
+        /// The server failed with an unexpected error (500)
+        InternalError = "InternalError",
+        /// The requested resource version or continue token has expired (410)
+        Expired = "Expired",
+        /// The server is temporarily unavailable (503)
+        ServiceUnavailable = "ServiceUnavailable",
+    }
+}
+
+string_enum! {
+    /// Why a field failed validation
+    ///
+    /// See the [`ErrorType`](https://pkg.go.dev/k8s.io/apimachinery/pkg/util/validation/field#ErrorType)
+    /// constants of apimachinery.
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    pub enum FieldErrorKind {
+        /// A value was not found
+        NotFound = "FieldValueNotFound",
+        /// A required value was not set
+        Required = "FieldValueRequired",
+        /// A value that must be unique was duplicated
+        Duplicate = "FieldValueDuplicate",
+        /// The value is invalid
+        Invalid = "FieldValueInvalid",
+        /// The value is not one of the supported values
+        NotSupported = "FieldValueNotSupported",
+        /// The value is not allowed, like a field that may not be changed
+        Forbidden = "FieldValueForbidden",
+        /// The value is too long
+        TooLong = "FieldValueTooLong",
+        /// The value has too many items
+        TooMany = "FieldValueTooMany",
+        /// The value has the wrong type
+        TypeInvalid = "FieldValueTypeInvalid",
+    }
+}
+
+/// A field that failed validation, from the causes of an [`ErrorResponse`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldError {
+    /// The path of the field, like `spec.containers[0].image`
+    pub field: String,
+    /// Why the field is invalid
+    pub kind: FieldErrorKind,
+    /// A human-readable description of the error
+    pub message: String,
+}
+
+impl FieldError {
+    /// The path of the field as a JSON pointer, like `/spec/containers/0/image`
+    ///
+    /// This locates the field in the serialized object. Map keys in brackets,
+    /// like `metadata.labels[app.kubernetes.io/name]`, become a single escaped segment.
+    pub fn json_pointer(&self) -> String {
+        let mut pointer = String::new();
+        let mut segment = String::new();
+        let mut in_brackets = false;
+        let push = |pointer: &mut String, segment: &mut String| {
+            if !segment.is_empty() {
+                pointer.push('/');
+                pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+                segment.clear();
+            }
+        };
+        for c in self.field.chars() {
+            match c {
+                ']' if in_brackets => {
+                    in_brackets = false;
+                    push(&mut pointer, &mut segment);
+                }
+                _ if in_brackets => segment.push(c),
+                '.' => push(&mut pointer, &mut segment),
+                '[' => {
+                    push(&mut pointer, &mut segment);
+                    in_brackets = true;
+                }
+                _ => segment.push(c),
+            }
+        }
+        push(&mut pointer, &mut segment);
+        pointer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorResponse, FieldError, FieldErrorKind, StatusReason};
+
+    fn error(reason: &str, code: u16) -> ErrorResponse {
+        ErrorResponse::new("Failure", "", reason, code)
+    }
+
+    #[test]
+    fn classifies_by_reason_then_code() {
+        assert!(error("NotFound", 404).is_not_found());
+        assert!(error("", 404).is_not_found());
+        assert!(error("SomethingNew", 404).is_not_found());
+        // a known reason takes precedence over the code
+        assert!(!error("AlreadyExists", 409).is_conflict());
+        assert!(error("AlreadyExists", 409).is_already_exists());
+        assert!(!error("", 409).is_already_exists());
+        assert!(error("Conflict", 409).is_conflict());
+        assert!(error("Expired", 410).is_resource_expired());
+        assert!(!error("Expired", 410).is_gone());
+        assert!(error("", 410).is_gone());
+        assert_eq!(error("Forbidden", 403).status_reason(), StatusReason::Forbidden);
+        assert_eq!(
+            error("Unheard", 400).status_reason(),
+            StatusReason::Other("Unheard".into())
+        );
+
+        assert!(error("TooManyRequests", 429).is_retryable());
+        assert!(error("ServerTimeout", 500).is_retryable());
+        assert!(error("", 503).is_retryable());
+        assert!(!error("NotFound", 404).is_retryable());
+        assert!(!error("Invalid", 422).is_retryable());
+    }
+
+    #[test]
+    fn parses_field_errors() {
+        let status = r#"{
+            "kind": "Status",
+            "apiVersion": "v1",
+            "status": "Failure",
+            "message": "Deployment.apps \"web\" is invalid: ...",
+            "reason": "Invalid",
+            "details": {
+                "name": "web",
+                "group": "apps",
+                "kind": "Deployment",
+                "causes": [
+                    {
+                        "reason": "FieldValueRequired",
+                        "message": "Required value",
+                        "field": "spec.template.spec.containers[0].image"
+                    },
+                    {
+                        "reason": "FieldValueInvalid",
+                        "message": "Invalid value: \"a b\"",
+                        "field": "metadata.labels[app.kubernetes.io/name]"
+                    },
+                    { "reason": "FieldValueUnexplained", "message": "?", "field": "spec.replicas" },
+                    { "message": "not about a field" }
+                ]
+            },
+            "code": 422
+        }"#;
+        let err: ErrorResponse = serde_json::from_str(status).unwrap();
+        assert!(err.is_invalid());
+        let errors = err.field_errors();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0], FieldError {
+            field: "spec.template.spec.containers[0].image".into(),
+            kind: FieldErrorKind::Required,
+            message: "Required value".into(),
+        });
+        assert_eq!(errors[0].json_pointer(), "/spec/template/spec/containers/0/image");
+        assert_eq!(errors[1].kind, FieldErrorKind::Invalid);
+        assert_eq!(
+            errors[1].json_pointer(),
+            "/metadata/labels/app.kubernetes.io~1name"
+        );
+        assert_eq!(
+            errors[2].kind,
+            FieldErrorKind::Other("FieldValueUnexplained".into())
+        );
+        assert_eq!(errors[2].json_pointer(), "/spec/replicas");
+    }
 }
@@ -72,7 +72,7 @@ pub mod watch;
 pub use watch::WatchEvent;
 
 mod error;
-pub use error::ErrorResponse;
+pub use error::{ErrorResponse, FieldError, FieldErrorKind, StatusReason};
 
 mod version;
 pub use version::Version;
@@ -747,6 +747,8 @@ impl PatchParams {
     }
 
     /// Set the validation directive to `Warn`
+    ///
+    /// The warnings are passed to the warning handler of the client.
     #[must_use]
     pub fn validation_warn(self) -> Self {
         self.validation(ValidationDirective::Warn)
@@ -114,7 +114,7 @@ impl Request {
         let urlstr = qp.finish();
         // eviction body parameters are awkward, need metadata with name
         let data = serde_json::to_vec(&serde_json::json!({
-            "delete_options": ep.delete_options,
+            "deleteOptions": ep.delete_options,
             "metadata": { "name": name }
         }))
         .map_err(Error::SerializeBody)?;
@@ -417,7 +417,10 @@ mod test {
     use k8s::core::v1 as corev1;
     use k8s_openapi::api as k8s;
 
-    use crate::subresource::LogParams;
+    use crate::{
+        params::DeleteParams,
+        subresource::{EvictParams, LogParams},
+    };
 
     #[test]
     fn logs_all_params() {
@@ -452,4 +455,26 @@ mod test {
             "/api/v1/namespaces/ns/pods/mypod/log?&sinceTime=2023-10-19T13%3A14%3A26Z" // cross-referenced with kubectl
         );
     }
+
+    #[test]
+    fn evict_body() {
+        let url = corev1::Pod::url_path(&(), Some("ns"));
+        let ep = EvictParams {
+            delete_options: Some(DeleteParams {
+                grace_period_seconds: Some(0),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let req = Request::new(url).evict("mypod", &ep).unwrap();
+        assert_eq!(req.uri(), "/api/v1/namespaces/ns/pods/mypod/eviction?");
+        let body: serde_json::Value = serde_json::from_slice(req.body()).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "deleteOptions": { "gracePeriodSeconds": 0 },
+                "metadata": { "name": "mypod" }
+            })
+        );
+    }
 }
@@ -0,0 +1,628 @@
+//! Prune objects that were removed from a set of manifests, with applysets
+//!
+//! An [applyset](https://github.com/kubernetes/enhancements/tree/master/keps/sig-cli/3659-kubectl-apply-prune)
+//! is a parent object that records which kinds and namespaces the members of the set live in. Members are
+//! labelled with the id of the set, so that the ones that are no longer applied can be found and deleted.
+//! The labels and annotations are the ones used by `kubectl apply --prune --applyset`.
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+use kube_client::{
+    api::{Api, ApiResource, DeleteParams, DynamicObject, ListParams, Patch, PatchParams, PropagationPolicy},
+    discovery::{Discovery, Scope},
+    Client, ResourceExt,
+};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::manifest::{apply_objects, gvk_of, AppliedObject, ApplyParams};
+
+const ID_LABEL: &str = "applyset.kubernetes.io/id";
+const PART_OF_LABEL: &str = "applyset.kubernetes.io/part-of";
+const TOOLING_ANNOTATION: &str = "applyset.kubernetes.io/tooling";
+const GROUP_KINDS_ANNOTATION: &str = "applyset.kubernetes.io/contains-group-kinds";
+const NAMESPACES_ANNOTATION: &str = "applyset.kubernetes.io/additional-namespaces";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to get the applyset parent: {0}")]
+    GetParent(#[source] kube_client::Error),
+    #[error("{0} exists but is not the parent of this applyset")]
+    InvalidParent(String),
+    #[error("the applyset is managed by {0}")]
+    ToolingMismatch(String),
+    #[error("failed to update the applyset parent: {0}")]
+    UpdateParent(#[source] kube_client::Error),
+    #[error("failed to discover api resources: {0}")]
+    Discovery(#[source] kube_client::Error),
+    #[error("failed to list applyset members: {0}")]
+    ListMembers(#[source] kube_client::Error),
+    #[error("failed to prune object: {0}")]
+    Prune(#[source] kube_client::Error),
+}
+
+/// A set of objects that are applied together, and pruned when they are no longer part of the set
+///
+/// ```no_run
+/// use kube::runtime::{applyset::ApplySet, manifest::{parse_manifests, ApplyParams}};
+/// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+/// # let client: kube::Client = todo!();
+///
+/// let objects = parse_manifests(&std::fs::read_to_string("install.yaml")?)?;
+/// let applyset = ApplySet::new(client, "my-app", "default");
+/// let result = applyset.apply(objects, &ApplyParams::new("my-installer")).await?;
+/// for pruned in result.pruned {
+///     println!("pruned {} {}", pruned.kind, pruned.name);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ApplySet {
+    client: Client,
+    name: String,
+    namespace: String,
+    parent: ApiResource,
+    tooling: String,
+}
+
+/// The outcome of [`ApplySet::apply`]
+#[derive(Debug)]
+pub struct ApplySetResult {
+    /// The result of applying each member, in the order they were given
+    pub applied: Vec<AppliedObject>,
+    /// The members that were pruned, or failed to be pruned
+    ///
+    /// Nothing is pruned if any member failed to apply.
+    pub pruned: Vec<PrunedObject>,
+    /// The kinds whose members could not be listed, so none of them were pruned
+    pub unlisted: Vec<UnlistedMembers>,
+}
+
+/// A member that was no longer part of an [`ApplySet`]
+#[derive(Debug)]
+pub struct PrunedObject {
+    /// The group of the object
+    pub group: String,
+    /// The kind of the object
+    pub kind: String,
+    /// The namespace of the object, if it is namespaced
+    pub namespace: Option<String>,
+    /// The name of the object
+    pub name: String,
+    /// Whether the object was deleted
+    pub result: Result<(), Error>,
+}
+
+/// A kind of member of an [`ApplySet`] that could not be listed for pruning
+#[derive(Debug)]
+pub struct UnlistedMembers {
+    /// The group of the members
+    pub group: String,
+    /// The kind of the members
+    pub kind: String,
+    /// The namespace that was listed, if the kind is namespaced
+    pub namespace: Option<String>,
+    /// Why the members could not be listed
+    pub error: Error,
+}
+
+/// Kinds and namespaces of an applyset's members, as recorded on the parent
+#[derive(Default)]
+struct Contents {
+    group_kinds: BTreeSet<(String, String)>,
+    namespaces: BTreeSet<String>,
+}
+
+impl ApplySet {
+    /// An applyset whose parent is the `Secret` `name` in `namespace`, like `kubectl apply --applyset=name`
+    #[must_use]
+    pub fn new(client: Client, name: &str, namespace: &str) -> Self {
+        Self {
+            client,
+            name: name.to_string(),
+            namespace: namespace.to_string(),
+            parent: ApiResource::erase::<Secret>(&()),
+            tooling: concat!("kube-rs/v", env!("CARGO_PKG_VERSION")).to_string(),
+        }
+    }
+
+    /// Use a `ConfigMap` as the parent, like `kubectl apply --applyset=configmaps/name`
+    #[must_use]
+    pub fn config_map(mut self) -> Self {
+        self.parent = ApiResource::erase::<ConfigMap>(&());
+        self
+    }
+
+    /// Set the tooling that manages the applyset, as `name/version`
+    ///
+    /// An applyset can only be managed by tools of the same name, so use `kubectl/<version>`
+    /// to share an applyset with `kubectl apply --applyset`.
+    #[must_use]
+    pub fn tooling(mut self, tooling: &str) -> Self {
+        self.tooling = tooling.to_string();
+        self
+    }
+
+    /// The id that members are labelled with
+    #[must_use]
+    pub fn id(&self) -> String {
+        applyset_id(&self.name, &self.namespace, &self.parent)
+    }
+
+    /// Apply `objects` as the members of the set, and prune members that are no longer in it
+    ///
+    /// Objects are labelled as members and applied like [`apply_manifests`](crate::manifest::apply_manifests).
+    /// The parent is updated to include their kinds and namespaces before they are applied, so that they can
+    /// still be pruned if applying is interrupted. If every member was applied, the members of this set that
+    /// were not applied are deleted. Once every kind was listed and every stale member deleted,
+    /// the parent is updated to only list the current kinds and namespaces.
+    ///
+    /// With [`ApplyParams::dry_run`], the parent is not updated and members are only pruned in a dry run.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the parent belongs to another applyset or tool, or if discovery or updating the parent fails.
+    /// Failures to apply, list or prune members are reported in the [`ApplySetResult`] instead.
+    pub async fn apply(
+        &self,
+        mut objects: Vec<DynamicObject>,
+        params: &ApplyParams,
+    ) -> Result<ApplySetResult, Error> {
+        let id = self.id();
+        let parents: Api<DynamicObject> =
+            Api::namespaced_with(self.client.clone(), &self.namespace, &self.parent);
+        let mut superset = match parents.get_opt(&self.name).await.map_err(Error::GetParent)? {
+            Some(parent) => self.contents_of(&parent, &id)?,
+            None => Contents::default(),
+        };
+        let discovery = Discovery::new(self.client.clone())
+            .run()
+            .await
+            .map_err(Error::Discovery)?;
+
+        for object in &mut objects {
+            object.labels_mut().insert(PART_OF_LABEL.to_string(), id.clone());
+        }
+        for object in &objects {
+            let Some(gvk) = gvk_of(object) else { continue };
+            let namespaced = discovery
+                .resolve_gvk(&gvk)
+                .map_or(object.metadata.namespace.is_some(), |(_, caps)| {
+                    caps.scope == Scope::Namespaced
+                });
+            if namespaced {
+                superset.namespaces.insert(self.namespace_of(object, params));
+            }
+            superset.group_kinds.insert((gvk.group, gvk.kind));
+        }
+        if !params.dry_run {
+            self.update_parent(&parents, &id, &superset, params).await?;
+        }
+
+        let applied = apply_objects(&self.client, &discovery, &objects, params).await;
+        if applied.iter().any(|a| a.result.is_err()) {
+            return Ok(ApplySetResult {
+                applied,
+                pruned: Vec::new(),
+                unlisted: Vec::new(),
+            });
+        }
+
+        let mut current = Contents::default();
+        let mut members = HashSet::new();
+        for a in &applied {
+            current
+                .group_kinds
+                .insert((a.gvk.group.clone(), a.gvk.kind.clone()));
+            current.namespaces.extend(a.namespace.clone());
+            members.insert((
+                a.gvk.group.clone(),
+                a.gvk.kind.clone(),
+                a.namespace.clone(),
+                a.name.clone().unwrap_or_default(),
+            ));
+        }
+        let (pruned, unlisted) = self.prune(&discovery, &id, &superset, &members, params).await;
+        // NB: kinds and namespaces that may still have members must stay on the parent
+        if !params.dry_run && unlisted.is_empty() && pruned.iter().all(|p| p.result.is_ok()) {
+            self.update_parent(&parents, &id, &current, params).await?;
+        }
+        Ok(ApplySetResult {
+            applied,
+            pruned,
+            unlisted,
+        })
+    }
+
+    fn namespace_of(&self, object: &DynamicObject, params: &ApplyParams) -> String {
+        object
+            .metadata
+            .namespace
+            .clone()
+            .or_else(|| params.namespace.clone())
+            .unwrap_or_else(|| self.client.default_namespace().to_string())
+    }
+
+    /// Read the recorded contents of an existing parent, checking that it belongs to this applyset
+    fn contents_of(&self, parent: &DynamicObject, id: &str) -> Result<Contents, Error> {
+        if parent.labels().get(ID_LABEL).map(String::as_str) != Some(id) {
+            return Err(Error::InvalidParent(format!(
+                "{} {}/{}",
+                self.parent.kind, self.namespace, self.name
+            )));
+        }
+        let annotations = parent.annotations();
+        if let Some(tooling) = annotations.get(TOOLING_ANNOTATION) {
+            if tooling_name(tooling) != tooling_name(&self.tooling) {
+                return Err(Error::ToolingMismatch(tooling.clone()));
+            }
+        }
+        let list = |annotation: &str| {
+            annotations
+                .get(annotation)
+                .into_iter()
+                .flat_map(|value| value.split(','))
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        Ok(Contents {
+            group_kinds: list(GROUP_KINDS_ANNOTATION)
+                .iter()
+                .map(|gk| parse_group_kind(gk))
+                .collect(),
+            namespaces: list(NAMESPACES_ANNOTATION).into_iter().collect(),
+        })
+    }
+
+    async fn update_parent(
+        &self,
+        parents: &Api<DynamicObject>,
+        id: &str,
+        contents: &Contents,
+        params: &ApplyParams,
+    ) -> Result<(), Error> {
+        let mut parent = DynamicObject::new(&self.name, &self.parent).within(&self.namespace);
+        parent.metadata.labels = Some(BTreeMap::from([(ID_LABEL.to_string(), id.to_string())]));
+        let group_kinds: Vec<_> = contents
+            .group_kinds
+            .iter()
+            .map(|(group, kind)| format_group_kind(group, kind))
+            .collect();
+        let namespaces: Vec<_> = contents
+            .namespaces
+            .iter()
+            .filter(|ns| **ns != self.namespace)
+            .map(String::as_str)
+            .collect();
+        parent.metadata.annotations = Some(BTreeMap::from([
+            (TOOLING_ANNOTATION.to_string(), self.tooling.clone()),
+            (GROUP_KINDS_ANNOTATION.to_string(), group_kinds.join(",")),
+            (NAMESPACES_ANNOTATION.to_string(), namespaces.join(",")),
+        ]));
+        let pp = PatchParams::apply(&params.field_manager).force();
+        parents
+            .patch(&self.name, &pp, &Patch::Apply(&parent))
+            .await
+            .map_err(Error::UpdateParent)?;
+        Ok(())
+    }
+
+    /// Delete the members of the set, in any of the recorded kinds and namespaces, that were not applied
+    async fn prune(
+        &self,
+        discovery: &Discovery,
+        id: &str,
+        contents: &Contents,
+        members: &HashSet<(String, String, Option<String>, String)>,
+        params: &ApplyParams,
+    ) -> (Vec<PrunedObject>, Vec<UnlistedMembers>) {
+        let lp = ListParams::default().labels(&format!("{PART_OF_LABEL}={id}"));
+        let dp = DeleteParams {
+            dry_run: params.dry_run,
+            propagation_policy: Some(PropagationPolicy::Background),
+            ..DeleteParams::default()
+        };
+        let mut namespaces = contents.namespaces.clone();
+        namespaces.insert(self.namespace.clone());
+
+        let mut pruned = Vec::new();
+        let mut unlisted = Vec::new();
+        for (group, kind) in &contents.group_kinds {
+            // Kinds that are no longer served cannot have members left
+            let Some((ar, caps)) = discovery.get(group).and_then(|g| g.recommended_kind(kind)) else {
+                continue;
+            };
+            let apis: Vec<(Option<&String>, Api<DynamicObject>)> = if caps.scope == Scope::Namespaced {
+                namespaces
+                    .iter()
+                    .map(|ns| (Some(ns), Api::namespaced_with(self.client.clone(), ns, &ar)))
+                    .collect()
+            } else {
+                vec![(None, Api::all_with(self.client.clone(), &ar))]
+            };
+            for (namespace, api) in apis {
+                let objects = match api.list_metadata(&lp).await {
+                    Ok(objects) => objects,
+                    Err(err) => {
+                        unlisted.push(UnlistedMembers {
+                            group: group.clone(),
+                            kind: kind.clone(),
+                            namespace: namespace.cloned(),
+                            error: Error::ListMembers(err),
+                        });
+                        continue;
+                    }
+                };
+                for object in objects {
+                    let name = object.name_any();
+                    let key = (group.clone(), kind.clone(), namespace.cloned(), name.clone());
+                    if members.contains(&key) {
+                        continue;
+                    }
+                    let result = api.delete(&name, &dp).await.map(|_| ()).map_err(Error::Prune);
+                    pruned.push(PrunedObject {
+                        group: group.clone(),
+                        kind: kind.clone(),
+                        namespace: namespace.cloned(),
+                        name,
+                        result,
+                    });
+                }
+            }
+        }
+        (pruned, unlisted)
+    }
+}
+
+/// The id of an applyset from the identity of its parent, as specified by KEP-3659
+fn applyset_id(name: &str, namespace: &str, parent: &ApiResource) -> String {
+    let key = format!("{name}.{namespace}.{}.{}", parent.kind, parent.group);
+    format!("applyset-{}-v1", URL_SAFE_NO_PAD.encode(Sha256::digest(key)))
+}
+
+fn tooling_name(tooling: &str) -> &str {
+    tooling.split_once('/').map_or(tooling, |(name, _)| name)
+}
+
+/// `Kind.group`, or `Kind` for the core group
+fn format_group_kind(group: &str, kind: &str) -> String {
+    if group.is_empty() {
+        kind.to_string()
+    } else {
+        format!("{kind}.{group}")
+    }
+}
+
+fn parse_group_kind(group_kind: &str) -> (String, String) {
+    match group_kind.split_once('.') {
+        Some((kind, group)) => (group.to_string(), kind.to_string()),
+        None => (String::new(), group_kind.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::pin;
+
+    use super::{
+        applyset_id, format_group_kind, parse_group_kind, tooling_name, ApplySet, Error,
+        GROUP_KINDS_ANNOTATION, ID_LABEL, PART_OF_LABEL, TOOLING_ANNOTATION,
+    };
+    use crate::manifest::{parse_manifests, ApplyParams};
+    use http::{Method, Request, Response, StatusCode};
+    use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+    use kube_client::{api::ApiResource, client::Body, Client};
+    use serde_json::{json, Value};
+    use tower_test::mock;
+
+    #[test]
+    fn id_matches_kubectl() {
+        let secret = ApiResource::erase::<Secret>(&());
+        assert_eq!(
+            applyset_id("my-set", "default", &secret),
+            "applyset-kdySOVBWs584aaOTmku9Ul1xDuN7LXBjs-R96jQcisk-v1"
+        );
+        let config_map = ApiResource::erase::<ConfigMap>(&());
+        assert_ne!(
+            applyset_id("my-set", "default", &config_map),
+            applyset_id("my-set", "default", &secret)
+        );
+    }
+
+    #[test]
+    fn group_kinds_roundtrip() {
+        assert_eq!(format_group_kind("", "ConfigMap"), "ConfigMap");
+        assert_eq!(format_group_kind("apps", "Deployment"), "Deployment.apps");
+        assert_eq!(parse_group_kind("ConfigMap"), (String::new(), "ConfigMap".into()));
+        assert_eq!(
+            parse_group_kind("Certificate.cert-manager.io"),
+            ("cert-manager.io".into(), "Certificate".into())
+        );
+        assert_eq!(tooling_name("kubectl/v1.30.0"), "kubectl");
+    }
+
+    fn aggregated(group: &str, version: &str, resources: &[(&str, &str)]) -> Value {
+        let resources: Vec<_> = resources
+            .iter()
+            .map(|(plural, kind)| {
+                json!({
+                    "resource": plural,
+                    "responseKind": { "group": group, "version": version, "kind": kind },
+                    "scope": "Namespaced",
+                    "verbs": ["delete", "get", "list", "patch"],
+                })
+            })
+            .collect();
+        json!({
+            "apiVersion": "apidiscovery.k8s.io/v2",
+            "kind": "APIGroupDiscoveryList",
+            "items": [{ "metadata": { "name": group }, "versions": [{ "version": version, "resources": resources }] }],
+        })
+    }
+
+    fn member_list(names: &[&str]) -> Value {
+        let items: Vec<_> = names
+            .iter()
+            .map(|name| json!({ "apiVersion": "meta.k8s.io/v1", "kind": "PartialObjectMetadata", "metadata": { "name": name } }))
+            .collect();
+        json!({ "apiVersion": "meta.k8s.io/v1", "kind": "PartialObjectMetadataList", "metadata": {}, "items": items })
+    }
+
+    /// Answer the requests of an apply as a cluster with a stale `ConfigMap` and `Deployment` in the set,
+    /// returning the method, path and body of every request
+    async fn serve(
+        handle: mock::Handle<Request<Body>, Response<Body>>,
+        id: String,
+        fail_deployments: bool,
+    ) -> Vec<(Method, String, Value)> {
+        let mut handle = pin!(handle);
+        let mut requests = Vec::new();
+        while let Some((req, send)) = handle.next_request().await {
+            let (method, path) = (req.method().clone(), req.uri().path().to_string());
+            let body = req.into_body().collect_bytes().await.unwrap();
+            let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+            let (status, response) = match (&method, path.as_str()) {
+                (&Method::GET, "/api/v1/namespaces/default/secrets/my-set") => (
+                    StatusCode::OK,
+                    json!({
+                        "apiVersion": "v1",
+                        "kind": "Secret",
+                        "metadata": {
+                            "name": "my-set",
+                            "namespace": "default",
+                            "labels": { ID_LABEL: id },
+                            "annotations": { TOOLING_ANNOTATION: "kube-rs/v0.1.0", GROUP_KINDS_ANNOTATION: "Deployment.apps" },
+                        },
+                    }),
+                ),
+                (&Method::GET, "/apis") => (
+                    StatusCode::OK,
+                    aggregated("apps", "v1", &[("deployments", "Deployment")]),
+                ),
+                (&Method::GET, "/api") => (
+                    StatusCode::OK,
+                    aggregated("", "v1", &[("configmaps", "ConfigMap"), ("secrets", "Secret")]),
+                ),
+                (&Method::GET, "/api/v1/namespaces/default/configmaps") => {
+                    (StatusCode::OK, member_list(&["settings", "old-settings"]))
+                }
+                (&Method::GET, "/apis/apps/v1/namespaces/default/deployments") if fail_deployments => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    json!({
+                        "status": "Failure",
+                        "message": "etcd is unavailable",
+                        "reason": "InternalError",
+                        "code": 500,
+                    }),
+                ),
+                (&Method::GET, "/apis/apps/v1/namespaces/default/deployments") => {
+                    (StatusCode::OK, member_list(&["old-app"]))
+                }
+                (&Method::PATCH, _) => (StatusCode::OK, body.clone()),
+                (&Method::DELETE, _) => (
+                    StatusCode::OK,
+                    json!({
+                        "apiVersion": "v1",
+                        "kind": "Status",
+                        "status": "Success",
+                    }),
+                ),
+                _ => panic!("unexpected request {method} {path}"),
+            };
+            send.send_response(
+                Response::builder()
+                    .status(status)
+                    .body(Body::from(serde_json::to_vec(&response).unwrap()))
+                    .unwrap(),
+            );
+            requests.push((method, path, body));
+        }
+        requests
+    }
+
+    fn paths(requests: &[(Method, String, Value)], method: &Method) -> Vec<String> {
+        requests
+            .iter()
+            .filter(|(m, ..)| m == method)
+            .map(|(_, path, _)| path.clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn apply_labels_members_and_prunes_the_rest() {
+        let (mock_service, handle) = mock::pair::<Request<Body>, Response<Body>>();
+        let applyset = ApplySet::new(Client::new(mock_service, "default"), "my-set", "default");
+        let id = applyset.id();
+        let spawned = tokio::spawn(serve(handle, id.clone(), false));
+        let objects =
+            parse_manifests("{ apiVersion: v1, kind: ConfigMap, metadata: { name: settings } }").unwrap();
+        let result = applyset.apply(objects, &ApplyParams::new("tests")).await.unwrap();
+        drop(applyset);
+        let requests = spawned.await.unwrap();
+
+        assert!(result.applied.iter().all(|a| a.result.is_ok()));
+        assert!(result.unlisted.is_empty());
+        let mut pruned: Vec<_> = result
+            .pruned
+            .iter()
+            .map(|p| (p.kind.as_str(), p.name.as_str()))
+            .collect();
+        pruned.sort_unstable();
+        assert_eq!(pruned, [("ConfigMap", "old-settings"), ("Deployment", "old-app")]);
+        assert!(result.pruned.iter().all(|p| p.result.is_ok()));
+        assert_eq!(paths(&requests, &Method::DELETE), [
+            "/api/v1/namespaces/default/configmaps/old-settings",
+            "/apis/apps/v1/namespaces/default/deployments/old-app",
+        ]);
+
+        // the member is stamped with the id of the set
+        let (_, _, member) = requests
+            .iter()
+            .find(|(method, path, _)| method == Method::PATCH && path.ends_with("/configmaps/settings"))
+            .unwrap();
+        assert_eq!(member["metadata"]["labels"][PART_OF_LABEL], json!(id));
+
+        // the parent lists the kinds of old and new members until the old ones are pruned
+        let parents: Vec<_> = requests
+            .iter()
+            .filter(|(method, path, _)| method == Method::PATCH && path.ends_with("/secrets/my-set"))
+            .map(|(_, _, parent)| parent["metadata"]["annotations"][GROUP_KINDS_ANNOTATION].clone())
+            .collect();
+        assert_eq!(parents, [json!("ConfigMap,Deployment.apps"), json!("ConfigMap")]);
+    }
+
+    #[tokio::test]
+    async fn prune_reports_kinds_that_cannot_be_listed() {
+        let (mock_service, handle) = mock::pair::<Request<Body>, Response<Body>>();
+        let applyset = ApplySet::new(Client::new(mock_service, "default"), "my-set", "default");
+        let spawned = tokio::spawn(serve(handle, applyset.id(), true));
+        let objects =
+            parse_manifests("{ apiVersion: v1, kind: ConfigMap, metadata: { name: settings } }").unwrap();
+        let result = applyset.apply(objects, &ApplyParams::new("tests")).await.unwrap();
+        drop(applyset);
+        let requests = spawned.await.unwrap();
+
+        // other kinds are still pruned
+        assert_eq!(paths(&requests, &Method::DELETE), [
+            "/api/v1/namespaces/default/configmaps/old-settings"
+        ]);
+        assert_eq!(result.unlisted.len(), 1);
+        let unlisted = &result.unlisted[0];
+        assert_eq!(
+            (unlisted.group.as_str(), unlisted.kind.as_str()),
+            ("apps", "Deployment")
+        );
+        assert_eq!(unlisted.namespace.as_deref(), Some("default"));
+        assert!(matches!(unlisted.error, Error::ListMembers(_)));
+
+        // the parent keeps listing the kind that may still have members
+        let parents = paths(&requests, &Method::PATCH)
+            .into_iter()
+            .filter(|path| path.ends_with("/secrets/my-set"))
+            .count();
+        assert_eq!(parents, 1);
+    }
+}
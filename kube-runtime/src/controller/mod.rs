@@ -2,6 +2,7 @@
 
 use self::runner::Runner;
 use crate::{
+    rbac::{Permissions, WATCH_VERBS},
     reflector::{
         self, reflector,
         store::{Store, Writer},
@@ -642,6 +643,7 @@ where
     dyntype: K::DynamicType,
     reader: Store<K>,
     config: Config,
+    permissions: Permissions,
 }
 
 impl<K> Controller<K>
@@ -679,6 +681,7 @@ where
     /// [`dynamic`]: kube_client::core::dynamic
     /// [`Config::default`]: crate::watcher::Config::default
     pub fn new_with(main_api: Api<K>, wc: watcher::Config, dyntype: K::DynamicType) -> Self {
+        let permissions = Permissions::new().allow_api(&main_api, &dyntype, WATCH_VERBS);
         let writer = Writer::<K>::new(dyntype.clone());
         let reader = writer.as_reader();
         let mut trigger_selector = stream::SelectAll::new();
@@ -702,6 +705,7 @@ where
             dyntype,
             reader,
             config: Default::default(),
+            permissions,
         }
     }
 
@@ -788,6 +792,7 @@ where
             dyntype,
             reader,
             config: Default::default(),
+            permissions: Permissions::default(),
         }
     }
 
@@ -899,6 +904,7 @@ where
             dyntype,
             reader,
             config: Default::default(),
+            permissions: Permissions::default(),
         }
     }
 
@@ -926,6 +932,28 @@ where
         self.reader.clone()
     }
 
+    /// Declare additional permissions needed by the reconciler
+    ///
+    /// These are merged with the permissions the controller collects from its watches,
+    /// and are included in [`Controller::permissions`].
+    #[must_use]
+    pub fn with_permissions(mut self, permissions: Permissions) -> Self {
+        self.permissions = self.permissions.merge(permissions);
+        self
+    }
+
+    /// The RBAC permissions this controller requires
+    ///
+    /// Includes `list` and `watch` on every resource watched through [`Controller::new`],
+    /// [`Controller::owns`] and [`Controller::watches`], along with anything declared through
+    /// [`Controller::with_permissions`]. Resources passed in as streams are not included, as
+    /// the controller does not know how they were created.
+    ///
+    /// See the [`rbac`](crate::rbac) module for how to render or verify these.
+    pub fn permissions(&self) -> Permissions {
+        self.permissions.clone()
+    }
+
     /// Specify `Child` objects which `K` owns and should be watched
     ///
     /// Takes an [`Api`] object that determines how the `Controller` listens for changes to the `Child`.
@@ -958,6 +986,7 @@ where
     where
         Child::DynamicType: Debug + Eq + Hash + Clone,
     {
+        self.permissions = self.permissions.allow_api(&api, &dyntype, WATCH_VERBS);
         // TODO: call owns_stream_with when it's stable
         let child_watcher = trigger_owners(
             metadata_watcher(api, wc).touched_objects(),
@@ -1238,6 +1267,7 @@ where
         I::IntoIter: Send,
         Other::DynamicType: Debug + Clone + Eq + Hash,
     {
+        self.permissions = self.permissions.allow_api(&api, &dyntype, WATCH_VERBS);
         let other_watcher = trigger_others(watcher(api, wc).touched_objects(), mapper, dyntype);
         self.trigger_selector.push(other_watcher.boxed());
         self
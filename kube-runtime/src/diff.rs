@@ -0,0 +1,313 @@
+//! Preview the changes a patch would make, like `kubectl diff`
+//!
+//! [`diff`] patches an object in a server-side dry run, and compares the object the server would persist to the
+//! live object. Fields that always change, like `metadata.managedFields`, `metadata.resourceVersion`,
+//! `metadata.generation` and `status`, are left out of the comparison.
+use std::fmt::Debug;
+
+use jsonptr::PointerBuf;
+use kube_client::{
+    api::{Api, Patch, PatchParams},
+    Resource,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use similar::TextDiff;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to get the live object: {0}")]
+    GetLive(#[source] kube_client::Error),
+    #[error("failed to patch the object in a dry run: {0}")]
+    DryRun(#[source] kube_client::Error),
+    #[error("failed to serialize the object: {0}")]
+    Serialize(#[source] serde_json::Error),
+}
+
+/// Fields that differ on every write, and are not part of what a patch changes
+const IGNORED_FIELDS: &[&[&str]] = &[
+    &["metadata", "managedFields"],
+    &["metadata", "resourceVersion"],
+    &["metadata", "generation"],
+    &["status"],
+];
+
+/// A change to a single field
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldChange {
+    /// The field is set by the patch
+    Added {
+        /// The location of the field
+        path: PointerBuf,
+        /// The new value
+        value: Value,
+    },
+    /// The field is removed by the patch
+    Removed {
+        /// The location of the field
+        path: PointerBuf,
+        /// The live value
+        value: Value,
+    },
+    /// The value of the field is changed by the patch
+    Changed {
+        /// The location of the field
+        path: PointerBuf,
+        /// The live value
+        old: Value,
+        /// The new value
+        new: Value,
+    },
+}
+
+impl FieldChange {
+    /// The location of the changed field
+    #[must_use]
+    pub fn path(&self) -> &PointerBuf {
+        match self {
+            Self::Added { path, .. } | Self::Removed { path, .. } | Self::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// The difference between a live object and the object a patch would produce
+#[derive(Clone, Debug)]
+pub struct Diff {
+    name: String,
+    live: Option<Value>,
+    merged: Value,
+    changes: Vec<FieldChange>,
+}
+
+impl Diff {
+    /// Compare a live object, or `None` if it does not exist yet, to the object a patch would produce
+    ///
+    /// # Errors
+    ///
+    /// Fails if either object cannot be serialized.
+    pub fn new<K: Resource + Serialize>(live: Option<&K>, merged: &K) -> Result<Self, Error> {
+        let name = merged.meta().name.clone().unwrap_or_default();
+        let live = live
+            .map(|live| serde_json::to_value(live).map(without_ignored_fields))
+            .transpose()
+            .map_err(Error::Serialize)?;
+        let merged = without_ignored_fields(serde_json::to_value(merged).map_err(Error::Serialize)?);
+        let mut changes = Vec::new();
+        let empty = Value::Object(Map::new());
+        compare(
+            &mut PointerBuf::new(),
+            live.as_ref().unwrap_or(&empty),
+            &merged,
+            &mut changes,
+        );
+        Ok(Self {
+            name,
+            live,
+            merged,
+            changes,
+        })
+    }
+
+    /// The changed fields, in the order they appear in the objects
+    #[must_use]
+    pub fn changes(&self) -> &[FieldChange] {
+        &self.changes
+    }
+
+    /// Whether the patch would leave the object unchanged
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Whether the object would be created by the patch
+    #[must_use]
+    pub fn is_creation(&self) -> bool {
+        self.live.is_none()
+    }
+
+    /// Render the diff as a unified diff of the YAML representations of the objects
+    ///
+    /// This is empty if there are no changes.
+    #[must_use]
+    pub fn unified(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        let live = self.live.as_ref().map(to_yaml).unwrap_or_default();
+        let merged = to_yaml(&self.merged);
+        TextDiff::from_lines(&live, &merged)
+            .unified_diff()
+            .header(&format!("live/{}", self.name), &format!("merged/{}", self.name))
+            .to_string()
+    }
+}
+
+/// Compare the live object `name` to the result of applying `patch` to it in a server-side dry run
+///
+/// The dry run is always enabled, regardless of `pp.dry_run`. If the object does not exist,
+/// the patch is compared to an empty object, which is what a server-side apply would create.
+///
+/// ```no_run
+/// use k8s_openapi::api::apps::v1::Deployment;
+/// use kube::{api::{Api, Patch, PatchParams}, runtime::diff::diff};
+/// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+/// # let client: kube::Client = todo!();
+/// # let deployment: Deployment = todo!();
+///
+/// let deployments: Api<Deployment> = Api::default_namespaced(client);
+/// let pp = PatchParams::apply("my-manager");
+/// let diff = diff(&deployments, "my-app", &pp, &Patch::Apply(&deployment)).await?;
+/// print!("{}", diff.unified());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Fails if the live object cannot be fetched, or the server rejects the patch.
+pub async fn diff<K, P>(api: &Api<K>, name: &str, pp: &PatchParams, patch: &Patch<P>) -> Result<Diff, Error>
+where
+    K: Resource + Clone + DeserializeOwned + Serialize + Debug,
+    P: Serialize + Debug,
+{
+    let live = api.get_opt(name).await.map_err(Error::GetLive)?;
+    let pp = pp.clone().dry_run();
+    let merged = api.patch(name, &pp, patch).await.map_err(Error::DryRun)?;
+    Diff::new(live.as_ref(), &merged)
+}
+
+fn without_ignored_fields(mut value: Value) -> Value {
+    for path in IGNORED_FIELDS {
+        if let Some((field, parents)) = path.split_last() {
+            let parent = parents
+                .iter()
+                .try_fold(&mut value, |value, key| value.get_mut(*key));
+            if let Some(Value::Object(parent)) = parent {
+                parent.remove(*field);
+            }
+        }
+    }
+    value
+}
+
+/// Collect the changes from `old` to `new` below `path`
+///
+/// Objects are compared field by field, and arrays item by item.
+fn compare(path: &mut PointerBuf, old: &Value, new: &Value, changes: &mut Vec<FieldChange>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old {
+                path.push_back(key);
+                match new.get(key) {
+                    Some(new_value) => compare(path, old_value, new_value, changes),
+                    None => changes.push(FieldChange::Removed {
+                        path: path.clone(),
+                        value: old_value.clone(),
+                    }),
+                }
+                path.pop_back();
+            }
+            for (key, new_value) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+                path.push_back(key);
+                changes.push(FieldChange::Added {
+                    path: path.clone(),
+                    value: new_value.clone(),
+                });
+                path.pop_back();
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for i in 0..old.len().max(new.len()) {
+                path.push_back(i);
+                match (old.get(i), new.get(i)) {
+                    (Some(old_value), Some(new_value)) => compare(path, old_value, new_value, changes),
+                    (Some(old_value), None) => changes.push(FieldChange::Removed {
+                        path: path.clone(),
+                        value: old_value.clone(),
+                    }),
+                    (None, Some(new_value)) => changes.push(FieldChange::Added {
+                        path: path.clone(),
+                        value: new_value.clone(),
+                    }),
+                    (None, None) => {}
+                }
+                path.pop_back();
+            }
+        }
+        (old, new) if old != new => changes.push(FieldChange::Changed {
+            path: path.clone(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+fn to_yaml(value: &Value) -> String {
+    // NB: serializing a json value to yaml cannot fail
+    serde_yaml::to_string(value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Diff, FieldChange};
+    use k8s_openapi::api::core::v1::ConfigMap;
+    use serde_json::json;
+
+    fn config_map(value: serde_json::Value) -> ConfigMap {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn diffs_fields_without_noise() {
+        let live = config_map(json!({
+            "metadata": {
+                "name": "cm",
+                "resourceVersion": "1",
+                "labels": { "app.kubernetes.io/name": "cm", "stale": "yes" },
+            },
+            "data": { "a": "1", "b": "2" },
+        }));
+        let merged = config_map(json!({
+            "metadata": {
+                "name": "cm",
+                "resourceVersion": "2",
+                "labels": { "app.kubernetes.io/name": "cm", "tier": "web" },
+                "managedFields": [{ "manager": "test" }],
+            },
+            "data": { "a": "1", "b": "3" },
+        }));
+        let diff = Diff::new(Some(&live), &merged).unwrap();
+        let paths: Vec<_> = diff.changes().iter().map(|c| c.path().to_string()).collect();
+        let expected = ["/data/b", "/metadata/labels/stale", "/metadata/labels/tier"];
+        assert_eq!(paths, expected);
+        assert_eq!(diff.changes()[0], FieldChange::Changed {
+            path: "/data/b".parse().unwrap(),
+            old: json!("2"),
+            new: json!("3"),
+        });
+        assert!(matches!(diff.changes()[1], FieldChange::Removed { .. }));
+        assert!(matches!(diff.changes()[2], FieldChange::Added { .. }));
+
+        let unified = diff.unified();
+        assert!(unified.starts_with("--- live/cm\n+++ merged/cm\n"));
+        assert!(unified.contains("\n-  b: '2'\n+  b: '3'\n"));
+        assert!(!unified.contains("resourceVersion"));
+    }
+
+    #[test]
+    fn unchanged_and_created_objects() {
+        let cm = config_map(json!({ "metadata": { "name": "cm" }, "data": { "a": "1" } }));
+        let diff = Diff::new(Some(&cm), &cm).unwrap();
+        assert!(diff.is_empty());
+        assert_eq!(diff.unified(), "");
+
+        let created = Diff::new(None, &cm).unwrap();
+        assert!(created.is_creation());
+        let paths: Vec<_> = created.changes().iter().map(|c| c.path().to_string()).collect();
+        assert_eq!(paths, ["/apiVersion", "/data", "/kind", "/metadata"]);
+        assert!(created.unified().contains("+data:\n"));
+    }
+}
@@ -0,0 +1,261 @@
+//! A continuously refreshed view of the API groups served by the cluster
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
+
+use futures::{stream, Stream, StreamExt};
+use k8s_openapi::{
+    apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition,
+    kube_aggregator::pkg::apis::apiregistration::v1::APIService,
+};
+use kube_client::{
+    api::Api,
+    core::GroupVersionKind,
+    discovery::{self, ApiCapabilities, ApiGroup, ApiResource, Discovery},
+    error::DiscoveryError,
+    Client, Resource, ResourceExt,
+};
+use parking_lot::RwLock;
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+use crate::{
+    watcher::{self, metadata_watcher},
+    WatchStreamExt,
+};
+
+/// Errors from [`live_discovery`]
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The watch on `CustomResourceDefinition` or `APIService` objects failed, and will be retried
+    #[error("failed to watch api extensions: {0}")]
+    Watch(#[source] watcher::Error),
+
+    /// Querying the api server for its api groups failed
+    #[error("failed to discover api groups: {0}")]
+    Discovery(#[source] kube_client::Error),
+}
+
+/// A change to the groups in a [`LiveDiscovery`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiscoveryEvent {
+    /// All groups were discovered again, because a watch was (re)started
+    Refreshed,
+    /// A group was added, or its resources may have changed
+    Applied(String),
+    /// A group is no longer served
+    Deleted(String),
+}
+
+/// A handle to the API groups discovered by [`live_discovery`]
+///
+/// This is cheap to clone, and can be queried concurrently while it is being updated.
+#[derive(Clone, Default)]
+pub struct LiveDiscovery {
+    groups: Arc<RwLock<HashMap<String, Arc<ApiGroup>>>>,
+}
+
+impl LiveDiscovery {
+    /// Returns the [`ApiGroup`] for a given group if served
+    #[must_use]
+    pub fn get(&self, group: &str) -> Option<Arc<ApiGroup>> {
+        self.groups.read().get(group).cloned()
+    }
+
+    /// Check if a group is served by the apiserver
+    #[must_use]
+    pub fn has_group(&self, group: &str) -> bool {
+        self.groups.read().contains_key(group)
+    }
+
+    /// Returns all served groups
+    #[must_use]
+    pub fn groups(&self) -> Vec<Arc<ApiGroup>> {
+        self.groups.read().values().cloned().collect()
+    }
+
+    /// Finds an [`ApiResource`] and its [`ApiCapabilities`] by matching a GVK,
+    /// like [`Discovery::resolve_gvk`]
+    #[must_use]
+    pub fn resolve_gvk(&self, gvk: &GroupVersionKind) -> Option<(ApiResource, ApiCapabilities)> {
+        self.get(&gvk.group)?
+            .versioned_resources(&gvk.version)
+            .into_iter()
+            .find(|res| res.0.kind == gvk.kind)
+    }
+
+    fn replace(&self, groups: impl Iterator<Item = ApiGroup>) {
+        let groups = groups.map(|g| (g.name().to_string(), Arc::new(g))).collect();
+        *self.groups.write() = groups;
+    }
+
+    fn insert(&self, group: ApiGroup) {
+        self.groups
+            .write()
+            .insert(group.name().to_string(), Arc::new(group));
+    }
+
+    fn remove(&self, group: &str) -> bool {
+        self.groups.write().remove(group).is_some()
+    }
+
+    /// Forget the resource `plural` in `group`, returning whether the group is still served
+    fn remove_resource(&self, group: &str, plural: &str) -> Option<bool> {
+        let mut groups = self.groups.write();
+        let remaining = groups.get(group)?.without_resource(plural);
+        let served = remaining.is_some();
+        match remaining {
+            Some(remaining) => groups.insert(group.to_string(), Arc::new(remaining)),
+            None => groups.remove(group),
+        };
+        Some(served)
+    }
+}
+
+/// The kinds of objects watched for changes to the served api groups
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Watched {
+    Crds,
+    ApiServices,
+}
+
+enum Trigger {
+    /// A watch (re)started, so changes may have been missed
+    Resync(Watched),
+    /// An object serving this group changed
+    Group(String),
+    /// A CRD was deleted, so its resource is no longer served
+    ///
+    /// The group is not queried again, as the api server can keep serving the resource for a while.
+    CrdDeleted { group: String, plural: String },
+}
+
+/// Keep a [`LiveDiscovery`] up to date with the API groups served by the cluster
+///
+/// All groups are discovered once the watches on `CustomResourceDefinition` and `APIService` objects have both
+/// started, and again whenever either of them restarts. After that, only the group of a changed CRD or `APIService`
+/// is queried again, so newly installed CRDs and aggregated apis can be used without restarting.
+/// The resource of a deleted CRD is removed from its group directly.
+///
+/// The [`LiveDiscovery`] is only updated while the returned stream is polled, which yields a [`DiscoveryEvent`]
+/// for every change. It is empty until the first [`DiscoveryEvent::Refreshed`].
+/// Errors are yielded without ending the stream: watch errors are retried with the default backoff,
+/// and groups that fail to be discovered are queried again on their next change.
+///
+/// ```no_run
+/// use futures::StreamExt;
+/// use kube::{core::GroupVersionKind, runtime::discovery::{live_discovery, DiscoveryEvent}};
+/// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+/// # let client: kube::Client = todo!();
+///
+/// let (discovery, events) = live_discovery(client);
+/// tokio::spawn(events.for_each(|event| async move {
+///     match event {
+///         Ok(DiscoveryEvent::Applied(group)) => println!("updated api group {group}"),
+///         Ok(_) => {}
+///         Err(err) => eprintln!("discovery failed: {err}"),
+///     }
+/// }));
+///
+/// let gvk = GroupVersionKind::gvk("clux.dev", "v1", "Foo");
+/// if let Some((ar, caps)) = discovery.resolve_gvk(&gvk) {
+///     println!("{} is served as {}", gvk.kind, ar.plural);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn live_discovery(client: Client) -> (LiveDiscovery, impl Stream<Item = Result<DiscoveryEvent, Error>>) {
+    let live = LiveDiscovery::default();
+    let store = live.clone();
+    let crds = triggers(
+        Api::<CustomResourceDefinition>::all(client.clone()),
+        Watched::Crds,
+    );
+    let apiservices = triggers(Api::<APIService>::all(client.clone()), Watched::ApiServices);
+    let events = async_stream::stream! {
+        let mut triggers = std::pin::pin!(stream::select(crds, apiservices));
+        // NB: the initial discovery waits for both watches, so that it is not run once for each of them
+        let mut unsynced = vec![Watched::Crds, Watched::ApiServices];
+        while let Some(trigger) = triggers.next().await {
+            match trigger {
+                Ok(Trigger::Resync(watched)) => {
+                    unsynced.retain(|w| *w != watched);
+                    if !unsynced.is_empty() {
+                        continue;
+                    }
+                    match Discovery::new(client.clone()).run().await {
+                        Ok(discovery) => {
+                            store.replace(discovery.into_groups());
+                            yield Ok(DiscoveryEvent::Refreshed);
+                        }
+                        Err(err) => yield Err(Error::Discovery(err)),
+                    }
+                }
+                // changes before the initial discovery are picked up by it
+                Ok(Trigger::Group(_) | Trigger::CrdDeleted { .. }) if !unsynced.is_empty() => {}
+                Ok(Trigger::CrdDeleted { group, plural }) => match store.remove_resource(&group, &plural) {
+                    Some(true) => yield Ok(DiscoveryEvent::Applied(group)),
+                    Some(false) => yield Ok(DiscoveryEvent::Deleted(group)),
+                    None => {}
+                },
+                Ok(Trigger::Group(group)) => match discovery::group(&client, &group).await {
+                    Ok(apigroup) => {
+                        store.insert(apigroup);
+                        yield Ok(DiscoveryEvent::Applied(group));
+                    }
+                    Err(kube_client::Error::Discovery(DiscoveryError::MissingApiGroup(_))) => {
+                        if store.remove(&group) {
+                            yield Ok(DiscoveryEvent::Deleted(group));
+                        }
+                    }
+                    Err(err) => yield Err(Error::Discovery(err)),
+                },
+                Err(err) => yield Err(Error::Watch(err)),
+            }
+        }
+    };
+    (live, events)
+}
+
+/// Watch the metadata of objects that serve api groups, since their names end in the group they serve
+fn triggers<K>(api: Api<K>, watched: Watched) -> impl Stream<Item = Result<Trigger, watcher::Error>>
+where
+    K: Resource + Clone + DeserializeOwned + Debug + Send + 'static,
+{
+    metadata_watcher(api, watcher::Config::default())
+        .default_backoff()
+        .filter_map(move |event| {
+            std::future::ready(match event {
+                Ok(watcher::Event::Delete(obj)) if watched == Watched::Crds => {
+                    obj.name_any().split_once('.').map(|(plural, group)| {
+                        Ok(Trigger::CrdDeleted {
+                            group: group.to_string(),
+                            plural: plural.to_string(),
+                        })
+                    })
+                }
+                Ok(watcher::Event::Apply(obj) | watcher::Event::Delete(obj)) => {
+                    group_of(&obj.name_any()).map(|group| Ok(Trigger::Group(group.to_string())))
+                }
+                Ok(watcher::Event::InitDone) => Some(Ok(Trigger::Resync(watched))),
+                Ok(watcher::Event::Init | watcher::Event::InitApply(_)) => None,
+                Err(err) => Some(Err(err)),
+            })
+        })
+}
+
+/// The group served by a CRD (`<plural>.<group>`) or an `APIService` (`<version>.<group>`)
+fn group_of(name: &str) -> Option<&str> {
+    name.split_once('.').map(|(_, group)| group)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::group_of;
+
+    #[test]
+    fn group_from_name() {
+        assert_eq!(group_of("foos.clux.dev"), Some("clux.dev"));
+        assert_eq!(group_of("v1beta1.metrics.k8s.io"), Some("metrics.k8s.io"));
+        assert_eq!(group_of("v1."), Some(""));
+        assert_eq!(group_of("invalid"), None);
+    }
+}
@@ -0,0 +1,485 @@
+//! Safely evict all pods from a node, like `kubectl drain`
+//!
+//! [`drain`] cordons the node, works out which of its pods can be removed, and then evicts them through the
+//! eviction API so that [`PodDisruptionBudget`](k8s_openapi::api::policy::v1::PodDisruptionBudget)s are respected.
+//!
+//! The caller needs permission to `patch` nodes, `list` and `get` pods, and `create` the `pods/eviction` subresource.
+use std::{fmt, future::Future, time::Duration};
+
+use futures::{stream, Stream, TryStreamExt};
+use k8s_openapi::api::core::v1::{Node, Pod};
+use kube_client::{
+    api::{Api, DeleteParams, EvictParams, ListParams},
+    Client, ResourceExt,
+};
+use thiserror::Error;
+use tokio::time::Instant;
+
+use crate::reflector::ObjectRef;
+
+/// Annotation set on the API server's copy of static pods
+const MIRROR_POD_ANNOTATION: &str = "kubernetes.io/config.mirror";
+/// How often to check whether an evicted pod has been removed
+const DELETION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Number of pods requested per page when listing the node's pods
+const LIST_PAGE_SIZE: u32 = 500;
+
+/// Errors from [`drain`]
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Failed to mark the node as unschedulable
+    #[error("failed to cordon node: {0}")]
+    Cordon(#[source] kube_client::Error),
+
+    /// Failed to list the pods running on the node
+    #[error("failed to list pods on node: {0}")]
+    ListPods(#[source] kube_client::Error),
+
+    /// Some pods cannot be evicted with the given [`DrainParams`], so no pods were evicted
+    #[error("cannot drain node, {} pod(s) cannot be safely evicted", .0.len())]
+    Blocked(Vec<(ObjectRef<Pod>, BlockReason)>),
+
+    /// The eviction of a pod failed for a reason other than a disruption budget
+    #[error("failed to evict pod {pod}: {source}")]
+    Evict {
+        /// The pod that could not be evicted
+        pod: ObjectRef<Pod>,
+        /// The error from the eviction request
+        #[source]
+        source: kube_client::Error,
+    },
+
+    /// Failed to check whether an evicted pod has been removed
+    #[error("failed to wait for pod {pod} to be deleted: {source}")]
+    AwaitDeletion {
+        /// The evicted pod
+        pod: ObjectRef<Pod>,
+        /// The error from looking up the pod
+        #[source]
+        source: kube_client::Error,
+    },
+
+    /// The node was not drained within [`DrainParams::timeout`]
+    #[error("timed out draining node")]
+    TimedOut,
+}
+
+/// Parameters for [`drain`]
+///
+/// The defaults match `kubectl drain`, which refuses to touch `DaemonSet` pods, pods using `emptyDir` volumes,
+/// and pods that are not managed by a controller.
+#[derive(Clone, Debug)]
+pub struct DrainParams {
+    /// Skip `DaemonSet`-managed pods instead of refusing to drain
+    ///
+    /// `DaemonSet` pods tolerate the unschedulable taint, so evicting them would only have them recreated on the same node.
+    pub ignore_daemonsets: bool,
+    /// Evict pods using `emptyDir` volumes, whose data is lost when the pod is evicted
+    pub delete_emptydir_data: bool,
+    /// Evict pods that are not managed by a controller, and will therefore not be recreated elsewhere
+    pub force: bool,
+    /// Grace period given to each pod, overriding the pod's own `terminationGracePeriodSeconds`
+    pub grace_period_seconds: Option<u32>,
+    /// Only evict pods matching this label selector
+    pub label_selector: Option<String>,
+    /// Give up if the node is not drained within this duration
+    ///
+    /// Applies to the whole eviction phase, including waiting for pods to terminate.
+    pub timeout: Option<Duration>,
+    /// How long to wait before retrying an eviction rejected by a disruption budget
+    pub eviction_retry_interval: Duration,
+}
+
+impl Default for DrainParams {
+    fn default() -> Self {
+        Self {
+            ignore_daemonsets: false,
+            delete_emptydir_data: false,
+            force: false,
+            grace_period_seconds: None,
+            label_selector: None,
+            timeout: None,
+            eviction_retry_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+impl DrainParams {
+    /// Skip `DaemonSet`-managed pods instead of refusing to drain
+    #[must_use]
+    pub fn ignore_daemonsets(mut self) -> Self {
+        self.ignore_daemonsets = true;
+        self
+    }
+
+    /// Evict pods using `emptyDir` volumes
+    #[must_use]
+    pub fn delete_emptydir_data(mut self) -> Self {
+        self.delete_emptydir_data = true;
+        self
+    }
+
+    /// Evict pods that are not managed by a controller
+    #[must_use]
+    pub fn force(mut self) -> Self {
+        self.force = true;
+        self
+    }
+
+    /// Override the grace period given to each evicted pod
+    #[must_use]
+    pub fn grace_period(mut self, seconds: u32) -> Self {
+        self.grace_period_seconds = Some(seconds);
+        self
+    }
+
+    /// Only evict pods matching a label selector
+    #[must_use]
+    pub fn labels(mut self, label_selector: &str) -> Self {
+        self.label_selector = Some(label_selector.to_string());
+        self
+    }
+
+    /// Give up if the node is not drained within `timeout`
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// Why a pod was left on the node
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The pod is managed by a `DaemonSet`, and [`DrainParams::ignore_daemonsets`] was set
+    DaemonSet,
+    /// The pod is a mirror of a static pod, which can only be removed by the kubelet
+    Mirror,
+}
+
+/// Why a pod prevents the node from being drained
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockReason {
+    /// The pod is managed by a `DaemonSet`, see [`DrainParams::ignore_daemonsets`]
+    DaemonSet,
+    /// The pod uses `emptyDir` volumes, see [`DrainParams::delete_emptydir_data`]
+    LocalStorage,
+    /// The pod is not managed by a controller, see [`DrainParams::force`]
+    Unmanaged,
+}
+
+impl fmt::Display for BlockReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DaemonSet => f.write_str("managed by a DaemonSet"),
+            Self::LocalStorage => f.write_str("uses emptyDir local storage"),
+            Self::Unmanaged => f.write_str("not managed by a controller"),
+        }
+    }
+}
+
+/// Progress reported by [`drain`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DrainEvent {
+    /// The node was marked as unschedulable
+    Cordoned,
+    /// A pod was left on the node
+    Skipped { pod: ObjectRef<Pod>, reason: SkipReason },
+    /// The eviction of a pod was accepted, and the pod is terminating
+    Evicting(ObjectRef<Pod>),
+    /// The eviction of a pod was rejected (typically by a disruption budget), and will be retried
+    EvictionRejected { pod: ObjectRef<Pod>, message: String },
+    /// A pod has been removed from the node
+    Evicted(ObjectRef<Pod>),
+    /// All pods that were to be evicted are gone
+    Drained,
+}
+
+enum Disposition {
+    Evict,
+    Skip(SkipReason),
+    Block(BlockReason),
+}
+
+fn disposition(pod: &Pod, params: &DrainParams) -> Disposition {
+    if pod.annotations().contains_key(MIRROR_POD_ANNOTATION) {
+        return Disposition::Skip(SkipReason::Mirror);
+    }
+    // Finished pods have nothing left to lose, so they are always safe to remove
+    let finished = pod
+        .status
+        .as_ref()
+        .and_then(|s| s.phase.as_deref())
+        .is_some_and(|phase| phase == "Succeeded" || phase == "Failed");
+    let controller = pod.owner_references().iter().find(|o| o.controller == Some(true));
+    if !finished && controller.is_some_and(|c| c.kind == "DaemonSet") {
+        return if params.ignore_daemonsets {
+            Disposition::Skip(SkipReason::DaemonSet)
+        } else {
+            Disposition::Block(BlockReason::DaemonSet)
+        };
+    }
+    let uses_empty_dir = pod
+        .spec
+        .as_ref()
+        .and_then(|s| s.volumes.as_ref())
+        .is_some_and(|volumes| volumes.iter().any(|v| v.empty_dir.is_some()));
+    if !finished && uses_empty_dir && !params.delete_emptydir_data {
+        return Disposition::Block(BlockReason::LocalStorage);
+    }
+    if !finished && controller.is_none() && !params.force {
+        return Disposition::Block(BlockReason::Unmanaged);
+    }
+    Disposition::Evict
+}
+
+/// Cordon a node and evict its pods, reporting progress as it goes
+///
+/// Pods are classified up front according to `params`: if any pod would have to be evicted unsafely, the stream
+/// fails with [`Error::Blocked`] before anything is evicted (the node stays cordoned). All remaining pods are then
+/// evicted concurrently. Evictions rejected with `429 Too Many Requests` (because evicting the pod would violate a
+/// disruption budget) are retried every [`DrainParams::eviction_retry_interval`] until the timeout.
+///
+/// The stream ends with [`DrainEvent::Drained`] once every evicted pod has been removed.
+///
+/// ```no_run
+/// use futures::TryStreamExt;
+/// use kube::runtime::drain::{drain, DrainParams};
+/// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+/// # let client: kube::Client = todo!();
+///
+/// let params = DrainParams::default()
+///     .ignore_daemonsets()
+///     .timeout(std::time::Duration::from_secs(300));
+/// let mut progress = std::pin::pin!(drain(client, "worker-1", params));
+/// while let Some(event) = progress.try_next().await? {
+///     println!("{event:?}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn drain(
+    client: Client,
+    node: &str,
+    params: DrainParams,
+) -> impl Stream<Item = Result<DrainEvent, Error>> {
+    let node = node.to_string();
+    async_stream::try_stream! {
+        let nodes: Api<Node> = Api::all(client.clone());
+        nodes.cordon(&node).await.map_err(Error::Cordon)?;
+        yield DrainEvent::Cordoned;
+
+        let mut lp = ListParams::default().fields(&format!("spec.nodeName={node}"));
+        if let Some(selector) = &params.label_selector {
+            lp = lp.labels(selector);
+        }
+        let pods = list_pods(&Api::all(client.clone()), lp).await.map_err(Error::ListPods)?;
+
+        let mut to_evict = Vec::new();
+        let mut skipped = Vec::new();
+        let mut blocked = Vec::new();
+        for pod in pods {
+            match disposition(&pod, &params) {
+                Disposition::Evict => to_evict.push(pod),
+                Disposition::Skip(reason) => skipped.push((ObjectRef::from_obj(&pod), reason)),
+                Disposition::Block(reason) => blocked.push((ObjectRef::from_obj(&pod), reason)),
+            }
+        }
+        if !blocked.is_empty() {
+            Err(Error::Blocked(blocked))?;
+        }
+        for (pod, reason) in skipped {
+            yield DrainEvent::Skipped { pod, reason };
+        }
+
+        let ep = EvictParams {
+            delete_options: Some(DeleteParams {
+                grace_period_seconds: params.grace_period_seconds,
+                ..DeleteParams::default()
+            }),
+            ..EvictParams::default()
+        };
+        let deadline = params.timeout.map(|timeout| Instant::now() + timeout);
+        let mut evictions = stream::select_all(
+            to_evict
+                .into_iter()
+                .map(|pod| Box::pin(evict_pod(client.clone(), pod, ep.clone(), params.eviction_retry_interval))),
+        );
+        while let Some(event) = until(deadline, evictions.try_next()).await?? {
+            yield event;
+        }
+        yield DrainEvent::Drained;
+    }
+}
+
+/// List all pages of pods matching `lp`
+async fn list_pods(api: &Api<Pod>, lp: ListParams) -> Result<Vec<Pod>, kube_client::Error> {
+    let mut lp = lp.limit(LIST_PAGE_SIZE);
+    let mut pods = Vec::new();
+    loop {
+        let page = api.list(&lp).await?;
+        pods.extend(page.items);
+        match page.metadata.continue_.filter(|token| !token.is_empty()) {
+            Some(token) => lp = lp.continue_token(&token),
+            None => return Ok(pods),
+        }
+    }
+}
+
+async fn until<F: Future>(deadline: Option<Instant>, fut: F) -> Result<F::Output, Error> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, fut)
+            .await
+            .map_err(|_| Error::TimedOut),
+        None => Ok(fut.await),
+    }
+}
+
+fn evict_pod(
+    client: Client,
+    pod: Pod,
+    ep: EvictParams,
+    retry_interval: Duration,
+) -> impl Stream<Item = Result<DrainEvent, Error>> {
+    async_stream::try_stream! {
+        let pod_ref = ObjectRef::from_obj(&pod);
+        let name = pod.name_any();
+        let uid = pod.uid();
+        let api: Api<Pod> = Api::namespaced(client, pod.namespace().as_deref().unwrap_or_default());
+        loop {
+            match api.evict(&name, &ep).await {
+                Ok(_) => {
+                    yield DrainEvent::Evicting(pod_ref.clone());
+                    break;
+                }
+                // Already gone
+                Err(kube_client::Error::Api(resp)) if resp.code == 404 => break,
+                Err(kube_client::Error::Api(resp)) if resp.code == 429 => {
+                    yield DrainEvent::EvictionRejected { pod: pod_ref.clone(), message: resp.message };
+                    tokio::time::sleep(retry_interval).await;
+                }
+                Err(source) => Err(Error::Evict { pod: pod_ref.clone(), source })?,
+            }
+        }
+        loop {
+            match api.get_opt(&name).await {
+                // A pod with a different uid is a replacement (such as from a StatefulSet), so ours is gone
+                Ok(Some(current)) if current.uid() == uid => tokio::time::sleep(DELETION_POLL_INTERVAL).await,
+                Ok(_) => break,
+                Err(source) => Err(Error::AwaitDeletion { pod: pod_ref.clone(), source })?,
+            }
+        }
+        yield DrainEvent::Evicted(pod_ref);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::pin;
+
+    use super::{disposition, list_pods, BlockReason, Disposition, DrainParams, SkipReason};
+    use http::{Request, Response};
+    use k8s_openapi::api::core::v1::Pod;
+    use kube_client::{
+        api::{Api, ListParams},
+        client::Body,
+        Client, ResourceExt,
+    };
+    use tower_test::mock;
+
+    fn pod(json: serde_json::Value) -> Pod {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn owned_by(kind: &str) -> serde_json::Value {
+        serde_json::json!([{ "apiVersion": "apps/v1", "kind": kind, "name": "owner", "uid": "1", "controller": true }])
+    }
+
+    #[test]
+    fn pods_are_classified_like_kubectl() {
+        let defaults = DrainParams::default();
+        let lenient = DrainParams::default()
+            .ignore_daemonsets()
+            .delete_emptydir_data()
+            .force();
+
+        let managed = pod(
+            serde_json::json!({ "metadata": { "name": "a", "ownerReferences": owned_by("ReplicaSet") } }),
+        );
+        assert!(matches!(disposition(&managed, &defaults), Disposition::Evict));
+
+        let mirror = pod(serde_json::json!({
+            "metadata": { "name": "b", "annotations": { "kubernetes.io/config.mirror": "abc" } }
+        }));
+        assert!(matches!(
+            disposition(&mirror, &defaults),
+            Disposition::Skip(SkipReason::Mirror)
+        ));
+
+        let daemon =
+            pod(serde_json::json!({ "metadata": { "name": "c", "ownerReferences": owned_by("DaemonSet") } }));
+        assert!(matches!(
+            disposition(&daemon, &defaults),
+            Disposition::Block(BlockReason::DaemonSet)
+        ));
+        assert!(matches!(
+            disposition(&daemon, &lenient),
+            Disposition::Skip(SkipReason::DaemonSet)
+        ));
+
+        let scratch = pod(serde_json::json!({
+            "metadata": { "name": "d", "ownerReferences": owned_by("ReplicaSet") },
+            "spec": { "containers": [], "volumes": [{ "name": "tmp", "emptyDir": {} }] }
+        }));
+        assert!(matches!(
+            disposition(&scratch, &defaults),
+            Disposition::Block(BlockReason::LocalStorage)
+        ));
+        assert!(matches!(disposition(&scratch, &lenient), Disposition::Evict));
+
+        let bare = pod(serde_json::json!({ "metadata": { "name": "e" } }));
+        assert!(matches!(
+            disposition(&bare, &defaults),
+            Disposition::Block(BlockReason::Unmanaged)
+        ));
+        assert!(matches!(disposition(&bare, &lenient), Disposition::Evict));
+
+        let finished =
+            pod(serde_json::json!({ "metadata": { "name": "f" }, "status": { "phase": "Succeeded" } }));
+        assert!(matches!(disposition(&finished, &defaults), Disposition::Evict));
+    }
+
+    #[tokio::test]
+    async fn pods_are_listed_across_pages() {
+        let (mock_service, handle) = mock::pair::<Request<Body>, Response<Body>>();
+        let spawned = tokio::spawn(async move {
+            let mut handle = pin!(handle);
+            let mut queries = Vec::new();
+            for (name, token) in [("a", "next"), ("b", "")] {
+                let (req, send) = handle.next_request().await.expect("service not called");
+                queries.push(req.uri().query().unwrap_or_default().to_string());
+                let page = serde_json::json!({
+                    "apiVersion": "v1",
+                    "kind": "PodList",
+                    "metadata": { "continue": token },
+                    "items": [{ "metadata": { "name": name, "namespace": "default" } }],
+                });
+                send.send_response(Response::new(Body::from(serde_json::to_vec(&page).unwrap())));
+            }
+            queries
+        });
+        let api: Api<Pod> = Api::all(Client::new(mock_service, "default"));
+        let lp = ListParams::default().fields("spec.nodeName=worker-1");
+        let pods = list_pods(&api, lp).await.unwrap();
+        assert_eq!(pods.iter().map(ResourceExt::name_any).collect::<Vec<_>>(), [
+            "a", "b"
+        ]);
+
+        let queries = spawned.await.unwrap();
+        assert!(queries
+            .iter()
+            .all(|query| query.contains("fieldSelector=spec.nodeName%3Dworker-1")));
+        assert!(!queries[0].contains("continue="));
+        assert!(queries[1].contains("continue=next"));
+    }
+}
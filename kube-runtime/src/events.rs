@@ -1,29 +1,37 @@
 //! Publishes events for objects for kubernetes >= 1.19
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     hash::{Hash, Hasher},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
 };
 
+use backon::BackoffBuilder;
 use k8s_openapi::{
     api::{
-        core::v1::ObjectReference,
+        core::v1::{Event as CoreEvent, EventSource, ObjectReference},
         events::v1::{Event as K8sEvent, EventSeries},
     },
-    apimachinery::pkg::apis::meta::v1::{MicroTime, ObjectMeta},
-    chrono::{Duration, Utc},
+    apimachinery::pkg::apis::meta::v1::{MicroTime, ObjectMeta, Time},
+    chrono::{DateTime, Duration, Utc},
 };
 use kube_client::{
     api::{Api, Patch, PatchParams, PostParams},
     Client, ResourceExt,
 };
-use tokio::sync::RwLock;
+use parking_lot::Mutex;
+use thiserror::Error;
+use tokio::sync::{mpsc, oneshot, RwLock};
 
 const CACHE_TTL: Duration = Duration::minutes(6);
+const AGGREGATED_NOTE_PREFIX: &str = "(combined from similar events): ";
 
 /// Minimal event type for publishing through [`Recorder::publish`].
 ///
 /// All string fields must be human readable.
+#[derive(Clone, Debug)]
 pub struct Event {
     /// The event severity.
     ///
@@ -76,6 +84,75 @@ pub enum EventType {
     Warning,
 }
 
+/// The API that a [`Recorder`] publishes events to.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum EventsApi {
+    /// `events.k8s.io/v1` events, available since Kubernetes 1.19.
+    #[default]
+    EventsV1,
+    /// Legacy `v1` events from the core api group.
+    ///
+    /// For distributions or RBAC setups that only allow core events.
+    /// The [`Reporter`] is mapped to the event `source` and `reportingComponent`, and an [`EventSeries`]
+    /// is mapped to the `count` and `lastTimestamp` fields.
+    CoreV1,
+}
+
+impl EventsApi {
+    /// Pick the events API based on the apiserver's discovery information
+    ///
+    /// Prefers [`EventsApi::EventsV1`] when the apiserver serves `events.k8s.io/v1`,
+    /// and falls back to [`EventsApi::CoreV1`] otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`](kube_client::Error) if the api groups could not be listed.
+    pub async fn detect(client: &Client) -> Result<Self, kube_client::Error> {
+        let groups = client.list_api_groups().await?;
+        let serves_events_v1 = groups
+            .groups
+            .iter()
+            .filter(|g| g.name == "events.k8s.io")
+            .flat_map(|g| &g.versions)
+            .any(|v| v.version == "v1");
+        Ok(if serves_events_v1 {
+            Self::EventsV1
+        } else {
+            Self::CoreV1
+        })
+    }
+}
+
+/// Convert an `events.k8s.io/v1` event into its legacy core `v1` representation
+fn to_core_event(event: &K8sEvent) -> CoreEvent {
+    let first_timestamp = event.event_time.as_ref().map(|t| Time(t.0));
+    let last_timestamp = event
+        .series
+        .as_ref()
+        .map(|s| Time(s.last_observed_time.0))
+        .or_else(|| first_timestamp.clone());
+    CoreEvent {
+        metadata: event.metadata.clone(),
+        involved_object: event.regarding.clone().unwrap_or_default(),
+        related: event.related.clone(),
+        action: event.action.clone(),
+        reason: event.reason.clone(),
+        message: event.note.clone(),
+        type_: event.type_.clone(),
+        count: Some(event.series.as_ref().map_or(1, |s| s.count)),
+        first_timestamp,
+        last_timestamp,
+        source: Some(EventSource {
+            component: event.reporting_controller.clone(),
+            host: event.reporting_instance.clone(),
+        }),
+        reporting_component: event.reporting_controller.clone(),
+        reporting_instance: event.reporting_instance.clone(),
+        event_time: None,
+        series: None,
+    }
+}
+
 /// [`ObjectReference`] with Hash and Eq implementations
 ///
 /// [`ObjectReference`]: k8s_openapi::api::core::v1::ObjectReference
@@ -104,6 +181,179 @@ struct EventKey {
     pub reporting_instance: Option<String>,
     pub regarding: Reference,
     pub related: Option<Reference>,
+    /// The note of the event, only part of the key when correlating events
+    pub note: Option<String>,
+}
+
+/// Configuration for event correlation in a [`Recorder`].
+///
+/// Mirrors the `EventCorrelator` from client-go, which combines two mechanisms:
+///
+/// - A spam filter, which rate limits the events published for each object through a token bucket.
+///   Events over the limit are not published, but they are counted, and the count is added to the
+///   [`EventSeries`] of the next published event with the same key.
+/// - An aggregator, which combines similar events that only differ in their `note`.
+///   Once `max_similar_events` distinct notes are seen for an object within
+///   `similar_events_window`, they are published as a single event series whose note is
+///   prefixed with `(combined from similar events): `.
+///
+/// The defaults match client-go.
+#[derive(Clone, Debug)]
+pub struct CorrelatorConfig {
+    /// The number of events that can be published for a single object in a burst.
+    ///
+    /// Defaults to 25.
+    pub spam_burst: u32,
+
+    /// The time it takes to refill one token in the per-object token bucket.
+    ///
+    /// Defaults to 5 minutes.
+    pub spam_refill_interval: std::time::Duration,
+
+    /// The number of distinct notes for similar events at which they are aggregated.
+    ///
+    /// Defaults to 10.
+    pub max_similar_events: usize,
+
+    /// The window in which similar events are counted towards `max_similar_events`.
+    ///
+    /// Defaults to 10 minutes.
+    pub similar_events_window: std::time::Duration,
+}
+
+impl Default for CorrelatorConfig {
+    fn default() -> Self {
+        Self {
+            spam_burst: 25,
+            spam_refill_interval: std::time::Duration::from_secs(5 * 60),
+            max_similar_events: 10,
+            similar_events_window: std::time::Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+/// Token bucket used for spam filtering events on a single object
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: DateTime<Utc>,
+}
+
+impl TokenBucket {
+    fn refill(&mut self, config: &CorrelatorConfig, now: DateTime<Utc>) {
+        let interval = config.spam_refill_interval.as_secs_f64();
+        let elapsed = (now - self.last_refill)
+            .to_std()
+            .unwrap_or_default()
+            .as_secs_f64();
+        let refilled = if interval > 0.0 {
+            elapsed / interval
+        } else {
+            f64::INFINITY
+        };
+        self.tokens = (self.tokens + refilled).min(f64::from(config.spam_burst));
+        self.last_refill = now;
+    }
+}
+
+/// Similar events seen for an aggregation key
+#[derive(Debug)]
+struct SimilarEvents {
+    notes: HashSet<Option<String>>,
+    first_seen: DateTime<Utc>,
+}
+
+/// The outcome of correlating an event
+#[derive(Clone, Debug, PartialEq)]
+struct Correlated {
+    key: EventKey,
+    note: Option<String>,
+    /// Number of occurrences of this event that were not published on their own
+    suppressed: i32,
+}
+
+/// Spam filter and aggregation state shared between clones of a [`Recorder`]
+#[derive(Debug)]
+struct Correlator {
+    config: CorrelatorConfig,
+    buckets: HashMap<Reference, TokenBucket>,
+    similar: HashMap<EventKey, SimilarEvents>,
+    suppressed: HashMap<EventKey, i32>,
+    suppressed_total: u64,
+}
+
+impl Correlator {
+    fn new(config: CorrelatorConfig) -> Self {
+        Self {
+            config,
+            buckets: HashMap::new(),
+            similar: HashMap::new(),
+            suppressed: HashMap::new(),
+            suppressed_total: 0,
+        }
+    }
+
+    /// Correlate `occurrences` identical events with a note-less `key`, returning `None` if they should be suppressed
+    ///
+    /// The occurrences are published in a single request, so they take a single token.
+    fn correlate(
+        &mut self,
+        mut key: EventKey,
+        note: Option<&str>,
+        occurrences: i32,
+        now: DateTime<Utc>,
+    ) -> Option<Correlated> {
+        let window = Duration::from_std(self.config.similar_events_window).unwrap_or(Duration::MAX);
+        self.similar
+            .retain(|_, similar| similar.first_seen + window > now);
+        let config = &self.config;
+        self.buckets.retain(|_, bucket| {
+            bucket.refill(config, now);
+            bucket.tokens < f64::from(config.spam_burst)
+        });
+        // suppressed counts are forgotten along with the bucket once it is full again,
+        // so objects that stop publishing do not keep their entries forever
+        let buckets = &self.buckets;
+        self.suppressed
+            .retain(|key, _| buckets.contains_key(&key.regarding));
+
+        // aggregate similar events once too many distinct notes have been seen
+        let similar = self.similar.entry(key.clone()).or_insert_with(|| SimilarEvents {
+            notes: HashSet::new(),
+            first_seen: now,
+        });
+        similar.notes.insert(note.map(String::from));
+        let note = if similar.notes.len() >= self.config.max_similar_events {
+            key.note = Some(AGGREGATED_NOTE_PREFIX.into());
+            Some(format!("{AGGREGATED_NOTE_PREFIX}{}", note.unwrap_or_default()))
+        } else {
+            key.note = Some(note.unwrap_or_default().into());
+            note.map(String::from)
+        };
+
+        // rate limit events per object
+        let bucket = self
+            .buckets
+            .entry(key.regarding.clone())
+            .or_insert_with(|| TokenBucket {
+                tokens: f64::from(self.config.spam_burst),
+                last_refill: now,
+            });
+        if bucket.tokens < 1.0 {
+            let suppressed = self.suppressed.entry(key).or_default();
+            *suppressed = suppressed.saturating_add(occurrences);
+            self.suppressed_total += u64::from(occurrences.unsigned_abs());
+            return None;
+        }
+        bucket.tokens -= 1.0;
+
+        let suppressed = self.suppressed.remove(&key).unwrap_or_default();
+        Some(Correlated {
+            key,
+            note,
+            suppressed,
+        })
+    }
 }
 
 /// Information about the reporting controller.
@@ -218,11 +468,21 @@ impl From<&str> for Reporter {
 ///   resources: ["events"]
 ///   verbs: ["create", "patch"]
 /// ```
+///
+/// or, when publishing to [`EventsApi::CoreV1`]:
+///
+/// ```yaml
+/// - apiGroups: [""]
+///   resources: ["events"]
+///   verbs: ["create", "patch"]
+/// ```
 #[derive(Clone)]
 pub struct Recorder {
     client: Client,
     reporter: Reporter,
+    events_api: EventsApi,
     cache: Arc<RwLock<HashMap<EventKey, K8sEvent>>>,
+    correlator: Option<Arc<Mutex<Correlator>>>,
 }
 
 impl Recorder {
@@ -237,10 +497,60 @@ impl Recorder {
         Self {
             client,
             reporter,
+            events_api: EventsApi::default(),
             cache,
+            correlator: None,
         }
     }
 
+    /// Set the API that events are published to
+    ///
+    /// Defaults to [`EventsApi::EventsV1`]. Use [`EventsApi::detect`] to pick one based on the
+    /// apiserver's discovery information.
+    #[must_use]
+    pub fn with_events_api(mut self, events_api: EventsApi) -> Self {
+        self.events_api = events_api;
+        self
+    }
+
+    /// Enable spam filtering and aggregation of similar events
+    ///
+    /// By default, every call to [`Recorder::publish`] results in an apiserver request.
+    /// With a correlator, a crash-looping reconciler can no longer flood the events API.
+    /// See [`CorrelatorConfig`] for details.
+    #[must_use]
+    pub fn with_correlator(mut self, config: CorrelatorConfig) -> Self {
+        self.correlator = Some(Arc::new(Mutex::new(Correlator::new(config))));
+        self
+    }
+
+    /// Move publishing into a background task
+    ///
+    /// Returns a [`BackgroundRecorder`] whose [`publish`](BackgroundRecorder::publish) only enqueues events,
+    /// so a slow events API does not slow down reconciliation.
+    /// The background task is spawned on the current tokio runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    #[must_use]
+    pub fn into_background(self, config: BackgroundConfig) -> BackgroundRecorder {
+        let (tx, rx) = mpsc::channel(config.queue_size.max(1));
+        let dropped = Arc::<AtomicU64>::default();
+        tokio::spawn(run_background(self, rx, dropped.clone(), config));
+        BackgroundRecorder { tx, dropped }
+    }
+
+    /// The total number of events suppressed by the spam filter
+    ///
+    /// Always zero unless the recorder was configured using [`Recorder::with_correlator`].
+    #[must_use]
+    pub fn suppressed_events(&self) -> u64 {
+        self.correlator
+            .as_ref()
+            .map_or(0, |correlator| correlator.lock().suppressed_total)
+    }
+
     /// Builds unique event key based on reportingController, reportingInstance, regarding, reason
     ///  and note
     fn get_event_key(&self, ev: &Event, regarding: &ObjectReference) -> EventKey {
@@ -252,13 +562,14 @@ impl Recorder {
             reporting_instance: self.reporter.instance.clone(),
             regarding: Reference(regarding.clone()),
             related: ev.secondary.clone().map(Reference),
+            note: None,
         }
     }
 
     // See https://kubernetes.io/docs/reference/generated/kubernetes-api/v1.22/#event-v1-events-k8s-io
     // for more detail on the fields
     // and what's expected: https://kubernetes.io/docs/reference/using-api/deprecation-guide/#event-v125
-    fn generate_event(&self, ev: &Event, reference: &ObjectReference) -> K8sEvent {
+    fn generate_event(&self, ev: &Event, note: Option<String>, reference: &ObjectReference) -> K8sEvent {
         let now = Utc::now();
         K8sEvent {
             action: Some(ev.action.clone()),
@@ -269,7 +580,7 @@ impl Recorder {
             deprecated_source: None,
             event_time: Some(MicroTime(now)),
             regarding: Some(reference.clone()),
-            note: ev.note.clone(),
+            note,
             metadata: ObjectMeta {
                 namespace: reference.namespace.clone(),
                 name: Some(format!(
@@ -301,12 +612,54 @@ impl Recorder {
     ///
     /// The event object is created in the same namespace of the [`ObjectReference`].
     /// Make sure that your controller has `create` permissions in the required namespaces
-    /// for the `event` resource in the API group `events.k8s.io`.
+    /// for the `event` resource in the API group `events.k8s.io`
+    /// (or the core API group when publishing to [`EventsApi::CoreV1`]).
     ///
     /// # Errors
     ///
     /// Returns an [`Error`](`kube_client::Error`) if the event is rejected by Kubernetes.
     pub async fn publish(&self, ev: &Event, reference: &ObjectReference) -> Result<(), kube_client::Error> {
+        match self.correlate(ev, reference, 1) {
+            Some(correlated) => self.send(ev, reference, &correlated).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Correlate `occurrences` identical events, returning `None` if they are suppressed by the spam filter
+    ///
+    /// This must happen once for every occurrence, however many times the request is retried.
+    fn correlate(&self, ev: &Event, reference: &ObjectReference, occurrences: i32) -> Option<Correlated> {
+        let key = self.get_event_key(ev, reference);
+        // occurrences that were not published on their own, and are accounted for in the series count
+        let extra = occurrences - 1;
+        let Some(correlator) = &self.correlator else {
+            return Some(Correlated {
+                key,
+                note: ev.note.clone(),
+                suppressed: extra,
+            });
+        };
+        let Some(mut correlated) =
+            correlator
+                .lock()
+                .correlate(key, ev.note.as_deref(), occurrences, Utc::now())
+        else {
+            tracing::debug!(reason = ev.reason, "event suppressed by spam filter");
+            return None;
+        };
+        correlated.suppressed = correlated.suppressed.saturating_add(extra);
+        Some(correlated)
+    }
+
+    /// Publish correlated events in a single request
+    ///
+    /// The `suppressed` occurrences of `correlated` are added to the series count of the event.
+    async fn send(
+        &self,
+        ev: &Event,
+        reference: &ObjectReference,
+        correlated: &Correlated,
+    ) -> Result<(), kube_client::Error> {
         let now = Utc::now();
 
         // gc past events older than now + CACHE_TTL
@@ -320,31 +673,68 @@ impl Recorder {
             }
         });
 
-        let key = self.get_event_key(ev, reference);
-        let event = match self.cache.read().await.get(&key) {
-            Some(e) => {
-                let count = if let Some(s) = &e.series { s.count + 1 } else { 2 };
-                let series = EventSeries {
-                    count,
+        let Correlated {
+            key,
+            note,
+            suppressed: extra,
+        } = correlated.clone();
+
+        let cached = self.cache.read().await.get(&key).cloned();
+        let is_new = cached.is_none();
+        let event = if let Some(mut event) = cached {
+            let count = event
+                .series
+                .as_ref()
+                .map_or(1, |s| s.count)
+                .saturating_add(1)
+                .saturating_add(extra);
+            event.series = Some(EventSeries {
+                count,
+                last_observed_time: MicroTime(now),
+            });
+            if key.note.as_deref() == Some(AGGREGATED_NOTE_PREFIX) {
+                // keep the combined note pointing at the latest occurrence
+                event.note = note;
+            }
+            event
+        } else {
+            let mut event = self.generate_event(ev, note, reference);
+            if extra > 0 {
+                event.series = Some(EventSeries {
+                    count: extra.saturating_add(1),
                     last_observed_time: MicroTime(now),
-                };
-                let mut event = e.clone();
-                event.series = Some(series);
-                event
+                });
             }
-            None => self.generate_event(ev, reference),
+            event
         };
 
-        let events = Api::namespaced(
-            self.client.clone(),
-            reference.namespace.as_ref().unwrap_or(&"default".to_string()),
-        );
-        if event.series.is_some() {
-            events
-                .patch(&event.name_any(), &PatchParams::default(), &Patch::Merge(&event))
-                .await?;
-        } else {
-            events.create(&PostParams::default(), &event).await?;
+        let namespace = reference.namespace.as_deref().unwrap_or("default");
+        match self.events_api {
+            EventsApi::EventsV1 => {
+                let events: Api<K8sEvent> = Api::namespaced(self.client.clone(), namespace);
+                if is_new {
+                    events.create(&PostParams::default(), &event).await?;
+                } else {
+                    events
+                        .patch(&event.name_any(), &PatchParams::default(), &Patch::Merge(&event))
+                        .await?;
+                }
+            }
+            EventsApi::CoreV1 => {
+                let events: Api<CoreEvent> = Api::namespaced(self.client.clone(), namespace);
+                let core_event = to_core_event(&event);
+                if is_new {
+                    events.create(&PostParams::default(), &core_event).await?;
+                } else {
+                    events
+                        .patch(
+                            &event.name_any(),
+                            &PatchParams::default(),
+                            &Patch::Merge(&core_event),
+                        )
+                        .await?;
+                }
+            }
         }
 
         {
@@ -355,19 +745,256 @@ impl Recorder {
     }
 }
 
+/// Configuration for a [`BackgroundRecorder`].
+#[derive(Clone, Debug)]
+pub struct BackgroundConfig {
+    /// The number of events that can be queued before [`BackgroundRecorder::publish`] starts dropping them.
+    ///
+    /// Defaults to 1024.
+    pub queue_size: usize,
+
+    /// The maximum number of queued events that are taken off the queue at a time.
+    ///
+    /// Identical events within a batch are coalesced into a single request.
+    /// Defaults to 100.
+    pub max_batch_size: usize,
+
+    /// The number of times a failed request is retried before the event is discarded.
+    ///
+    /// Only transient failures are retried, as decided by [`kube_client::Error::is_retryable`].
+    /// Defaults to 5.
+    pub max_retries: usize,
+}
+
+impl Default for BackgroundConfig {
+    fn default() -> Self {
+        Self {
+            queue_size: 1024,
+            max_batch_size: 100,
+            max_retries: 5,
+        }
+    }
+}
+
+/// Errors from enqueueing an event on a [`BackgroundRecorder`].
+#[derive(Debug, Error)]
+pub enum QueueError {
+    #[error("event queue is full, event was dropped")]
+    Full,
+    #[error("background recorder has shut down, event was dropped")]
+    Closed,
+}
+
+enum Command {
+    Publish(Box<(Event, ObjectReference)>),
+    Shutdown(oneshot::Sender<()>),
+}
+
+/// A [`Recorder`] that publishes events from a background task.
+///
+/// Created through [`Recorder::into_background`]. Events are put on a bounded queue that is drained by a
+/// background task, which deduplicates them through the [`Recorder`] cache, coalesces identical events,
+/// and retries transient failures.
+///
+/// ```no_run
+/// use kube::runtime::events::{BackgroundConfig, Event, EventType, Recorder};
+/// # use k8s_openapi::api::core::v1::ObjectReference;
+///
+/// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+/// # let client: kube::Client = todo!();
+/// # let reference: ObjectReference = todo!();
+/// let recorder = Recorder::new(client, "my-awesome-controller".into())
+///     .into_background(BackgroundConfig::default());
+///
+/// // returns immediately
+/// recorder.publish(
+///     &Event {
+///         action: "Scheduling".into(),
+///         reason: "Pulling".into(),
+///         note: Some("Pulling image `nginx`".into()),
+///         type_: EventType::Normal,
+///         secondary: None,
+///     },
+///     &reference,
+/// )?;
+///
+/// // flush queued events before exiting
+/// recorder.shutdown().await;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Cloning a `BackgroundRecorder` shares the queue and background task.
+/// The task exits once [`BackgroundRecorder::shutdown`] is called, or all clones are dropped,
+/// after publishing every event that was already queued.
+#[derive(Clone)]
+pub struct BackgroundRecorder {
+    tx: mpsc::Sender<Command>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl BackgroundRecorder {
+    /// Queue an event for publishing, without waiting for it to be published
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueueError::Full`] if the queue is full, and [`QueueError::Closed`] after a shutdown.
+    /// The event is dropped in either case, and counted in [`BackgroundRecorder::dropped_events`].
+    /// Events that fail to publish in the background are counted there as well.
+    pub fn publish(&self, ev: &Event, reference: &ObjectReference) -> Result<(), QueueError> {
+        let res = self
+            .tx
+            .try_send(Command::Publish(Box::new((ev.clone(), reference.clone()))))
+            .map_err(|err| match err {
+                mpsc::error::TrySendError::Full(_) => QueueError::Full,
+                mpsc::error::TrySendError::Closed(_) => QueueError::Closed,
+            });
+        if let Err(err) = &res {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(reason = ev.reason, error = %err, "dropping event");
+        }
+        res
+    }
+
+    /// The total number of events dropped
+    ///
+    /// This counts events rejected because the queue was full or closed,
+    /// and queued events that failed to publish after [`BackgroundConfig::max_retries`],
+    /// or with an error that is not retried.
+    #[must_use]
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Publish all queued events and stop the background task
+    ///
+    /// Events published after this (including through clones) are dropped,
+    /// and counted in [`BackgroundRecorder::dropped_events`].
+    pub async fn shutdown(self) {
+        let (done_tx, done_rx) = oneshot::channel();
+        if self.tx.send(Command::Shutdown(done_tx)).await.is_ok() {
+            let _ = done_rx.await;
+        }
+    }
+}
+
+async fn run_background(
+    recorder: Recorder,
+    mut rx: mpsc::Receiver<Command>,
+    dropped: Arc<AtomicU64>,
+    config: BackgroundConfig,
+) {
+    let mut shutdown = None;
+    while shutdown.is_none() {
+        let Some(first) = rx.recv().await else {
+            break;
+        };
+        let mut batch = Vec::new();
+        let mut next = Some(first);
+        while let Some(cmd) = next.take() {
+            match cmd {
+                Command::Publish(event) => batch.push(*event),
+                Command::Shutdown(done) => {
+                    shutdown = Some(done);
+                    break;
+                }
+            }
+            if batch.len() < config.max_batch_size {
+                next = rx.try_recv().ok();
+            }
+        }
+
+        // coalesce identical events into a single request
+        let mut coalesced: Vec<(Event, ObjectReference, i32)> = Vec::new();
+        let mut index: HashMap<(EventKey, Option<String>), usize> = HashMap::new();
+        for (ev, reference) in batch {
+            let key = (recorder.get_event_key(&ev, &reference), ev.note.clone());
+            if let Some(&i) = index.get(&key) {
+                coalesced[i].2 = coalesced[i].2.saturating_add(1);
+            } else {
+                index.insert(key, coalesced.len());
+                coalesced.push((ev, reference, 1));
+            }
+        }
+        for (ev, reference, occurrences) in coalesced {
+            // NB: correlate before retrying, so failed attempts do not take tokens from the spam filter
+            if let Some(correlated) = recorder.correlate(&ev, &reference, occurrences) {
+                if !publish_with_retries(&recorder, &ev, &reference, &correlated, config.max_retries).await {
+                    dropped.fetch_add(u64::from(occurrences.unsigned_abs()), Ordering::Relaxed);
+                }
+            }
+        }
+    }
+    rx.close();
+    // events sent before the queue was closed are never published
+    while let Ok(cmd) = rx.try_recv() {
+        match cmd {
+            Command::Publish(event) => {
+                dropped.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!(reason = event.0.reason, "dropping event queued after shutdown");
+            }
+            Command::Shutdown(done) => {
+                let _ = done.send(());
+            }
+        }
+    }
+    if let Some(done) = shutdown {
+        let _ = done.send(());
+    }
+}
+
+/// Returns whether the event was published
+async fn publish_with_retries(
+    recorder: &Recorder,
+    ev: &Event,
+    reference: &ObjectReference,
+    correlated: &Correlated,
+    max_retries: usize,
+) -> bool {
+    let mut backoff = backon::ExponentialBuilder::default()
+        .with_min_delay(std::time::Duration::from_millis(200))
+        .with_max_delay(std::time::Duration::from_secs(30))
+        .with_max_times(max_retries)
+        .with_jitter()
+        .build();
+    loop {
+        let err = match recorder.send(ev, reference, correlated).await {
+            Ok(()) => return true,
+            Err(err) => err,
+        };
+        match backoff.next() {
+            Some(delay) if err.is_retryable() => {
+                tracing::debug!(reason = ev.reason, error = %err, ?delay, "retrying event publish");
+                tokio::time::sleep(delay).await;
+            }
+            _ => {
+                tracing::warn!(reason = ev.reason, error = %err, "dropping event that failed to publish");
+                return false;
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::{Event, EventKey, EventType, Recorder, Reference, Reporter};
+    use std::pin::pin;
+
+    use super::{
+        to_core_event, BackgroundConfig, Correlator, CorrelatorConfig, Event, EventKey, EventType, Recorder,
+        Reference, Reporter, AGGREGATED_NOTE_PREFIX,
+    };
 
+    use http::{Request, Response};
     use k8s_openapi::{
         api::{
-            core::v1::{ComponentStatus, Service},
-            events::v1::Event as K8sEvent,
+            core::v1::{ComponentStatus, ObjectReference, Service},
+            events::v1::{Event as K8sEvent, EventSeries},
         },
         apimachinery::pkg::apis::meta::v1::MicroTime,
         chrono::{Duration, Utc},
     };
-    use kube::{Api, Client, Resource};
+    use kube::{client::Body, Api, Client, Resource};
+    use tower_test::mock;
 
     #[tokio::test]
     #[ignore = "needs cluster (creates an event for the default kubernetes service)"]
@@ -500,6 +1127,7 @@ mod test {
             regarding: Reference(reference.clone()),
             reporting_instance: None,
             related: None,
+            note: None,
         };
 
         let reporter = Reporter {
@@ -528,4 +1156,294 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    #[ignore = "needs cluster (creates an event for the default kubernetes service)"]
+    async fn background_recorder_flushes_on_shutdown() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::try_default().await?;
+
+        let svcs: Api<Service> = Api::namespaced(client.clone(), "default");
+        let s = svcs.get("kubernetes").await?; // always a kubernetes service in default
+        let recorder =
+            Recorder::new(client.clone(), "kube".into()).into_background(BackgroundConfig::default());
+        let ev = Event {
+            type_: EventType::Normal,
+            reason: "BackgroundService".into(),
+            note: Some("Sending kubernetes to detention in the background".into()),
+            action: "Test event - plz ignore".into(),
+            secondary: None,
+        };
+        for _ in 0..3 {
+            recorder.publish(&ev, &s.object_ref(&()))?;
+        }
+        assert_eq!(recorder.dropped_events(), 0);
+        recorder.shutdown().await;
+
+        let events: Api<K8sEvent> = Api::namespaced(client, "default");
+        let event_list = events.list(&Default::default()).await?;
+        let found_event = event_list
+            .into_iter()
+            .find(|e| std::matches!(e.reason.as_deref(), Some("BackgroundService")))
+            .unwrap();
+        assert_eq!(found_event.series.map(|s| s.count), Some(3));
+        Ok(())
+    }
+
+    #[test]
+    fn core_event_maps_reporter_and_series() {
+        let recorder_event = K8sEvent {
+            reason: Some("Failed".into()),
+            note: Some("something broke".into()),
+            type_: Some("Warning".into()),
+            event_time: Some(MicroTime(Utc::now() - Duration::minutes(1))),
+            regarding: Some(ObjectReference {
+                name: Some("a".into()),
+                ..Default::default()
+            }),
+            reporting_controller: Some("kube".into()),
+            reporting_instance: Some("kube-0".into()),
+            ..Default::default()
+        };
+        let core = to_core_event(&recorder_event);
+        assert_eq!(core.message.as_deref(), Some("something broke"));
+        assert_eq!(core.involved_object.name.as_deref(), Some("a"));
+        assert_eq!(core.count, Some(1));
+        assert_eq!(core.first_timestamp, core.last_timestamp);
+        let source = core.source.unwrap();
+        assert_eq!(source.component.as_deref(), Some("kube"));
+        assert_eq!(source.host.as_deref(), Some("kube-0"));
+        assert_eq!(core.reporting_component.as_deref(), Some("kube"));
+        assert!(core.event_time.is_none());
+
+        let now = Utc::now();
+        let series_event = K8sEvent {
+            series: Some(EventSeries {
+                count: 4,
+                last_observed_time: MicroTime(now),
+            }),
+            ..recorder_event
+        };
+        let core = to_core_event(&series_event);
+        assert_eq!(core.count, Some(4));
+        assert_eq!(core.last_timestamp.map(|t| t.0), Some(now));
+        assert_ne!(core.first_timestamp.map(|t| t.0), Some(now));
+    }
+
+    fn correlation_key(name: &str) -> EventKey {
+        EventKey {
+            event_type: EventType::Warning,
+            action: "Reconcile".into(),
+            reason: "Failed".into(),
+            reporting_controller: "kube".into(),
+            reporting_instance: None,
+            regarding: Reference(ObjectReference {
+                name: Some(name.into()),
+                ..Default::default()
+            }),
+            related: None,
+            note: None,
+        }
+    }
+
+    fn test_event(reason: &str) -> Event {
+        Event {
+            type_: EventType::Normal,
+            reason: reason.into(),
+            note: Some("Sending kubernetes to detention".into()),
+            action: "Test event - plz ignore".into(),
+            secondary: None,
+        }
+    }
+
+    fn test_reference() -> ObjectReference {
+        ObjectReference {
+            api_version: Some("v1".into()),
+            kind: Some("Service".into()),
+            name: Some("kubernetes".into()),
+            namespace: Some("default".into()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn background_recorder_counts_events_queued_after_shutdown() {
+        let (mock_service, handle) = mock::pair::<Request<Body>, Response<Body>>();
+        let mut handle = pin!(handle);
+        let recorder = Recorder::new(Client::new(mock_service, "default"), "kube".into())
+            .into_background(BackgroundConfig::default());
+        let (ev, reference) = (test_event("Queued"), test_reference());
+        recorder.publish(&ev, &reference).unwrap();
+        let (req, send) = handle.next_request().await.expect("service not called");
+
+        // shut down while the first event is being published, and queue another one behind the shutdown
+        let mut shutdown = pin!(recorder.clone().shutdown());
+        assert!(futures::poll!(&mut shutdown).is_pending());
+        recorder.publish(&ev, &reference).unwrap();
+        send.send_response(Response::new(req.into_body()));
+        shutdown.await;
+        assert_eq!(recorder.dropped_events(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn background_recorder_coalesces_and_retries() {
+        let (mock_service, handle) = mock::pair::<Request<Body>, Response<Body>>();
+        let spawned = tokio::spawn(async move {
+            let mut handle = pin!(handle);
+            let (req, send) = handle.next_request().await.expect("service not called");
+            let body = req.into_body().collect_bytes().await.unwrap();
+            let first: K8sEvent = serde_json::from_slice(&body).unwrap();
+            let status = serde_json::json!({
+                "status": "Failure",
+                "message": "etcdserver: request timed out",
+                "reason": "ServiceUnavailable",
+                "code": 503,
+            });
+            send.send_response(
+                Response::builder()
+                    .status(503)
+                    .body(Body::from(serde_json::to_vec(&status).unwrap()))
+                    .unwrap(),
+            );
+            let (req, send) = handle.next_request().await.expect("request not retried");
+            let body = req.into_body().collect_bytes().await.unwrap();
+            let retried: K8sEvent = serde_json::from_slice(&body).unwrap();
+            send.send_response(Response::new(Body::from(body.to_vec())));
+            (first, retried)
+        });
+        let recorder = Recorder::new(Client::new(mock_service, "default"), "kube".into())
+            .into_background(BackgroundConfig::default());
+        let (ev, reference) = (test_event("Coalesced"), test_reference());
+        for _ in 0..3 {
+            recorder.publish(&ev, &reference).unwrap();
+        }
+        recorder.shutdown().await;
+
+        // coalesced occurrences are counted once, however often the request is retried
+        let (first, retried) = spawned.await.unwrap();
+        assert_eq!(first.series.map(|series| series.count), Some(3));
+        assert_eq!(retried.series.map(|series| series.count), Some(3));
+    }
+
+    #[tokio::test]
+    async fn background_recorder_counts_events_that_fail_to_publish() {
+        let (mock_service, handle) = mock::pair::<Request<Body>, Response<Body>>();
+        let spawned = tokio::spawn(async move {
+            let mut handle = pin!(handle);
+            let (_req, send) = handle.next_request().await.expect("service not called");
+            let status = serde_json::json!({
+                "status": "Failure",
+                "message": "events is forbidden",
+                "reason": "Forbidden",
+                "code": 403,
+            });
+            send.send_response(
+                Response::builder()
+                    .status(403)
+                    .body(Body::from(serde_json::to_vec(&status).unwrap()))
+                    .unwrap(),
+            );
+        });
+        let recorder = Recorder::new(Client::new(mock_service, "default"), "kube".into())
+            .into_background(BackgroundConfig::default());
+        let (ev, reference) = (test_event("Forbidden"), test_reference());
+        for _ in 0..2 {
+            recorder.publish(&ev, &reference).unwrap();
+        }
+        recorder.clone().shutdown().await;
+        spawned.await.unwrap();
+
+        // both coalesced occurrences are lost with the request, which is not retried
+        assert_eq!(recorder.dropped_events(), 2);
+    }
+
+    #[test]
+    fn correlator_spam_filter_counts_suppressed_events() {
+        let mut correlator = Correlator::new(CorrelatorConfig {
+            spam_burst: 2,
+            ..CorrelatorConfig::default()
+        });
+        let now = Utc::now();
+        let key = correlation_key("a");
+        assert!(correlator.correlate(key.clone(), Some("boom"), 1, now).is_some());
+        assert!(correlator.correlate(key.clone(), Some("boom"), 1, now).is_some());
+        assert!(correlator.correlate(key.clone(), Some("boom"), 1, now).is_none());
+        assert!(correlator.correlate(key.clone(), Some("boom"), 1, now).is_none());
+        assert_eq!(correlator.suppressed_total, 2);
+
+        // other objects have their own bucket
+        assert!(correlator
+            .correlate(correlation_key("b"), Some("boom"), 1, now)
+            .is_some());
+
+        // a refilled token publishes the event along with the suppressed count
+        let later = now + Duration::minutes(5);
+        let correlated = correlator.correlate(key.clone(), Some("boom"), 1, later).unwrap();
+        assert_eq!(correlated.suppressed, 2);
+        assert_eq!(correlated.note.as_deref(), Some("boom"));
+        assert!(correlator.correlate(key, Some("boom"), 1, later).is_none());
+        assert_eq!(correlator.suppressed.len(), 1);
+
+        // suppressed counts expire with the bucket of their object
+        let much_later = later + Duration::hours(1);
+        assert!(correlator
+            .correlate(correlation_key("b"), Some("boom"), 1, much_later)
+            .is_some());
+        assert!(correlator.suppressed.is_empty());
+        assert_eq!(correlator.buckets.len(), 1);
+    }
+
+    #[test]
+    fn correlator_counts_every_coalesced_occurrence() {
+        let mut correlator = Correlator::new(CorrelatorConfig {
+            spam_burst: 2,
+            ..CorrelatorConfig::default()
+        });
+        let now = Utc::now();
+        let key = correlation_key("a");
+        // coalesced occurrences take a single token
+        assert!(correlator.correlate(key.clone(), Some("boom"), 3, now).is_some());
+        assert!(correlator.correlate(key.clone(), Some("boom"), 2, now).is_some());
+        assert!(correlator.correlate(key.clone(), Some("boom"), 4, now).is_none());
+        assert_eq!(correlator.suppressed_total, 4);
+
+        let later = now + Duration::minutes(5);
+        let correlated = correlator.correlate(key, Some("boom"), 1, later).unwrap();
+        assert_eq!(correlated.suppressed, 4);
+    }
+
+    #[test]
+    fn correlator_aggregates_similar_events() {
+        let mut correlator = Correlator::new(CorrelatorConfig {
+            max_similar_events: 3,
+            ..CorrelatorConfig::default()
+        });
+        let now = Utc::now();
+        let key = correlation_key("a");
+        let first = correlator
+            .correlate(key.clone(), Some("pod x failed"), 1, now)
+            .unwrap();
+        assert_eq!(first.key.note.as_deref(), Some("pod x failed"));
+        let second = correlator
+            .correlate(key.clone(), Some("pod y failed"), 1, now)
+            .unwrap();
+        assert_ne!(first.key, second.key);
+
+        let third = correlator
+            .correlate(key.clone(), Some("pod z failed"), 1, now)
+            .unwrap();
+        assert_eq!(third.key.note.as_deref(), Some(AGGREGATED_NOTE_PREFIX));
+        assert_eq!(
+            third.note.as_deref(),
+            Some("(combined from similar events): pod z failed")
+        );
+        let fourth = correlator
+            .correlate(key.clone(), Some("pod w failed"), 1, now)
+            .unwrap();
+        assert_eq!(third.key, fourth.key);
+
+        // similar events are forgotten after the window passes
+        let later = now + Duration::minutes(11);
+        let fresh = correlator.correlate(key, Some("pod v failed"), 1, later).unwrap();
+        assert_eq!(fresh.key.note.as_deref(), Some("pod v failed"));
+    }
 }
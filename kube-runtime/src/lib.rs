@@ -20,10 +20,17 @@
 // Triggered by nightly clippy on idiomatic code
 #![allow(clippy::let_underscore_untyped)]
 
+#[cfg(feature = "applyset")] pub mod applyset;
 pub mod controller;
+#[cfg(feature = "diff")] pub mod diff;
+pub mod discovery;
+pub mod drain;
 pub mod events;
 
 pub mod finalizer;
+pub mod logs;
+#[cfg(feature = "manifest")] pub mod manifest;
+pub mod rbac;
 pub mod reflector;
 pub mod scheduler;
 pub mod utils;
@@ -0,0 +1,275 @@
+//! Tail the logs of every container in a set of pods, like `stern` or `kubectl logs -l`
+use std::collections::HashMap;
+
+use futures::{
+    stream::{self, BoxStream, SelectAll},
+    AsyncBufReadExt, Stream, StreamExt,
+};
+use k8s_openapi::{
+    api::core::v1::{ContainerStatus, Pod},
+    chrono::{DateTime, Utc},
+};
+use kube_client::{
+    api::{Api, LogParams},
+    core::Selector,
+    Client, ResourceExt,
+};
+use thiserror::Error;
+
+use crate::{
+    watcher::{self, watcher},
+    WatchStreamExt,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to watch pods: {0}")]
+    Watch(#[source] watcher::Error),
+    #[error("failed to stream logs of {namespace}/{pod}/{container}: {source}")]
+    Logs {
+        namespace: String,
+        pod: String,
+        container: String,
+        #[source]
+        source: Box<kube_client::Error>,
+    },
+    #[error("failed to read logs of {namespace}/{pod}/{container}: {source}")]
+    ReadLogs {
+        namespace: String,
+        pod: String,
+        container: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// A single line of container output
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogLine {
+    /// Namespace of the pod
+    pub namespace: String,
+    /// Name of the pod
+    pub pod: String,
+    /// Name of the container within the pod
+    pub container: String,
+    /// When the line was written, if [`LogParams::timestamps`] was set
+    pub timestamp: Option<DateTime<Utc>>,
+    /// The line, without its timestamp or trailing newline
+    pub line: String,
+}
+
+impl LogLine {
+    fn parse(namespace: &str, pod: &str, container: &str, line: String, timestamps: bool) -> Self {
+        let (timestamp, line) = match line.split_once(' ') {
+            Some((ts, rest)) if timestamps => match DateTime::parse_from_rfc3339(ts) {
+                Ok(ts) => (Some(ts.with_timezone(&Utc)), rest.to_string()),
+                Err(_) => (None, line),
+            },
+            _ => (None, line),
+        };
+        Self {
+            namespace: namespace.to_string(),
+            pod: pod.to_string(),
+            container: container.to_string(),
+            timestamp,
+            line,
+        }
+    }
+}
+
+enum Item {
+    Pod(Box<watcher::Event<Pod>>),
+    Line(LogLine),
+}
+
+/// Follow the logs of all containers in all pods matching `selector`
+///
+/// Pods are discovered with a [`watcher`], so pods created later are picked up as well.
+/// Every container (including init containers) is followed once it has started, and followed again whenever it
+/// restarts. With [`LogParams::previous`], the logs of the previous instance of a container that had already
+/// restarted when it was discovered are included first.
+///
+/// All lines are merged into a single stream, in the order they are received. [`LogParams::container`] and
+/// [`LogParams::follow`] are ignored, the other parameters apply to every container.
+///
+/// Errors are yielded without ending the stream: watch errors are retried with the default backoff,
+/// and a container whose logs cannot be streamed is only retried if it restarts.
+///
+/// ```no_run
+/// use futures::StreamExt;
+/// use k8s_openapi::api::core::v1::Pod;
+/// use kube::{api::{Api, LogParams}, core::Selector, runtime::logs::tail_logs};
+/// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+/// # let client: kube::Client = todo!();
+///
+/// let pods: Api<Pod> = Api::default_namespaced(client);
+/// let selector: Selector = [("app", "web")].into_iter().collect();
+/// let lp = LogParams { timestamps: true, tail_lines: Some(10), ..LogParams::default() };
+/// let mut logs = std::pin::pin!(tail_logs(pods, &selector, lp));
+/// while let Some(line) = logs.next().await {
+///     let line = line?;
+///     println!("{}/{} {:?}: {}", line.pod, line.container, line.timestamp, line.line);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn tail_logs(
+    api: Api<Pod>,
+    selector: &Selector,
+    lp: LogParams,
+) -> impl Stream<Item = Result<LogLine, Error>> {
+    let client = api.clone().into_client();
+    let pods = watcher(api, watcher::Config::default().labels_from(selector))
+        .default_backoff()
+        .map(|event| event.map(|ev| Item::Pod(Box::new(ev))).map_err(Error::Watch));
+    async_stream::stream! {
+        let mut streams: SelectAll<BoxStream<'static, Result<Item, Error>>> = SelectAll::new();
+        streams.push(pods.boxed());
+        // Latest restart count that is being followed, per pod uid and container
+        let mut followed = HashMap::<(String, String), i32>::new();
+        while let Some(item) = streams.next().await {
+            let event = match item {
+                Ok(Item::Line(line)) => {
+                    yield Ok(line);
+                    continue;
+                }
+                Ok(Item::Pod(event)) => *event,
+                Err(err) => {
+                    yield Err(err);
+                    continue;
+                }
+            };
+            let pod = match event {
+                watcher::Event::Apply(pod) | watcher::Event::InitApply(pod) => pod,
+                watcher::Event::Delete(pod) => {
+                    let uid = pod.uid().unwrap_or_default();
+                    followed.retain(|(pod_uid, _), _| *pod_uid != uid);
+                    continue;
+                }
+                watcher::Event::Init | watcher::Event::InitDone => continue,
+            };
+            let uid = pod.uid().unwrap_or_default();
+            let statuses = pod.status.iter().flat_map(|status| {
+                let init = status.init_container_statuses.iter().flatten();
+                init.chain(status.container_statuses.iter().flatten())
+            });
+            for container in statuses.filter(|c| has_started(c)) {
+                let previously_followed = followed.get(&(uid.clone(), container.name.clone())).copied();
+                if previously_followed.is_some_and(|restarts| restarts >= container.restart_count) {
+                    continue;
+                }
+                followed.insert((uid.clone(), container.name.clone()), container.restart_count);
+                let include_previous = lp.previous && previously_followed.is_none() && container.restart_count > 0;
+                streams.push(
+                    follow_container(client.clone(), &pod, &container.name, &lp, include_previous)
+                        .map(|line| line.map(Item::Line))
+                        .boxed(),
+                );
+            }
+        }
+    }
+}
+
+fn has_started(container: &ContainerStatus) -> bool {
+    container
+        .state
+        .as_ref()
+        .is_some_and(|state| state.running.is_some() || state.terminated.is_some())
+}
+
+fn follow_container(
+    client: Client,
+    pod: &Pod,
+    container: &str,
+    lp: &LogParams,
+    include_previous: bool,
+) -> impl Stream<Item = Result<LogLine, Error>> {
+    let namespace = pod.namespace().unwrap_or_default();
+    let api: Api<Pod> = Api::namespaced(client, &namespace);
+    let current = LogParams {
+        container: Some(container.to_string()),
+        follow: true,
+        previous: false,
+        ..lp.clone()
+    };
+    let previous = include_previous.then(|| LogParams {
+        follow: false,
+        previous: true,
+        ..current.clone()
+    });
+    let name = pod.name_any();
+    let container = container.to_string();
+    stream::iter(previous.into_iter().chain([current]))
+        .map(move |lp| {
+            log_lines(
+                api.clone(),
+                namespace.clone(),
+                name.clone(),
+                container.clone(),
+                lp,
+            )
+        })
+        .flatten()
+}
+
+fn log_lines(
+    api: Api<Pod>,
+    namespace: String,
+    pod: String,
+    container: String,
+    lp: LogParams,
+) -> impl Stream<Item = Result<LogLine, Error>> {
+    async_stream::stream! {
+        let reader = match api.log_stream(&pod, &lp).await {
+            Ok(reader) => reader,
+            Err(source) => {
+                yield Err(Error::Logs { namespace, pod, container, source: Box::new(source) });
+                return;
+            }
+        };
+        let mut lines = std::pin::pin!(reader.lines());
+        while let Some(line) = lines.next().await {
+            match line {
+                Ok(line) => yield Ok(LogLine::parse(&namespace, &pod, &container, line, lp.timestamps)),
+                Err(source) => {
+                    yield Err(Error::ReadLogs { namespace, pod, container, source });
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LogLine;
+    use k8s_openapi::chrono::{TimeZone, Utc};
+
+    #[test]
+    fn log_lines_parse_timestamps() {
+        let line = LogLine::parse(
+            "ns",
+            "web-0",
+            "app",
+            "2024-05-01T10:20:30.123456789Z GET /healthz 200".into(),
+            true,
+        );
+        assert_eq!(line.line, "GET /healthz 200");
+        assert_eq!(
+            line.timestamp,
+            Some(
+                Utc.with_ymd_and_hms(2024, 5, 1, 10, 20, 30).unwrap()
+                    + std::time::Duration::from_nanos(123_456_789)
+            )
+        );
+
+        let line = LogLine::parse("ns", "web-0", "app", "no timestamp here".into(), true);
+        assert_eq!((line.timestamp, line.line.as_str()), (None, "no timestamp here"));
+
+        let line = LogLine::parse("ns", "web-0", "app", "2024-05-01T10:20:30Z kept".into(), false);
+        assert_eq!(
+            (line.timestamp, line.line.as_str()),
+            (None, "2024-05-01T10:20:30Z kept")
+        );
+    }
+}
@@ -0,0 +1,384 @@
+//! Apply bundles of multi-document YAML manifests, like `kubectl apply -f`
+//!
+//! [`apply_manifests`] resolves the kind of every object through discovery, and server-side applies them with
+//! `Namespace`s and `CustomResourceDefinition`s first, so that the rest of the bundle can use them.
+use std::time::Duration;
+
+use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition;
+use kube_client::{
+    api::{Api, DynamicObject, Patch, PatchParams},
+    core::GroupVersionKind,
+    discovery::{self, Discovery, Scope},
+    Client,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::wait::{self, await_condition, conditions};
+
+/// Errors from parsing and applying manifests
+#[derive(Debug, Error)]
+pub enum Error {
+    /// A YAML document of the bundle could not be parsed
+    #[error("failed to parse manifest document {index}: {source}")]
+    Parse {
+        /// The position of the document in the bundle, starting at 0
+        index: usize,
+        /// The underlying YAML error
+        #[source]
+        source: serde_yaml::Error,
+    },
+
+    /// A document has no usable `apiVersion` and `kind`
+    #[error("manifest document {index} has a missing or invalid apiVersion or kind")]
+    InvalidType {
+        /// The position of the document in the bundle, starting at 0
+        index: usize,
+    },
+
+    /// An object has no `metadata.name`
+    #[error("object has no name")]
+    MissingName,
+
+    /// The kind of an object could not be resolved through discovery
+    #[error("failed to discover api resources: {0}")]
+    Discovery(#[source] kube_client::Error),
+
+    /// The apiserver rejected an object
+    #[error("failed to apply object: {0}")]
+    Apply(#[source] kube_client::Error),
+
+    /// An applied `CustomResourceDefinition` did not become established
+    #[error("failed to wait for the CRD to be established: {0}")]
+    AwaitCrd(#[source] wait::Error),
+}
+
+/// Parameters for [`apply_manifests`]
+#[derive(Clone, Debug)]
+pub struct ApplyParams {
+    /// The field manager that owns the applied fields
+    pub field_manager: String,
+    /// Take ownership of fields that are managed by other field managers
+    pub force: bool,
+    /// Validate the objects on the server without persisting them
+    ///
+    /// Objects of a kind defined by a CRD in the same bundle cannot be resolved, unless the CRD already exists.
+    pub dry_run: bool,
+    /// Namespace for namespaced objects that do not set one, instead of the client's default namespace
+    pub namespace: Option<String>,
+    /// How long to wait for each applied CRD to be established
+    pub crd_timeout: Duration,
+}
+
+impl ApplyParams {
+    /// Apply objects as `field_manager`
+    #[must_use]
+    pub fn new(field_manager: &str) -> Self {
+        Self {
+            field_manager: field_manager.to_string(),
+            force: false,
+            dry_run: false,
+            namespace: None,
+            crd_timeout: Duration::from_secs(60),
+        }
+    }
+
+    /// Take ownership of conflicting fields
+    #[must_use]
+    pub fn force(mut self) -> Self {
+        self.force = true;
+        self
+    }
+
+    /// Only validate the objects on the server
+    #[must_use]
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Apply namespaced objects without a namespace to `namespace`
+    #[must_use]
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.namespace = Some(namespace.to_string());
+        self
+    }
+
+    /// Give up waiting for a CRD to be established after `timeout`
+    #[must_use]
+    pub fn crd_timeout(mut self, timeout: Duration) -> Self {
+        self.crd_timeout = timeout;
+        self
+    }
+}
+
+/// The outcome of applying one object from a manifest
+#[derive(Debug)]
+pub struct AppliedObject {
+    /// The kind of the object
+    pub gvk: GroupVersionKind,
+    /// The namespace the object was applied to, if it is namespaced
+    pub namespace: Option<String>,
+    /// The name of the object
+    pub name: Option<String>,
+    /// The object returned by the server, or why it could not be applied
+    pub result: Result<DynamicObject, Error>,
+}
+
+/// Parse the objects in a multi-document YAML manifest
+///
+/// Empty documents are skipped, and the items of `List` documents are returned individually.
+///
+/// # Errors
+///
+/// Fails if a document is not a Kubernetes object with an `apiVersion` and `kind`.
+pub fn parse_manifests(yaml: &str) -> Result<Vec<DynamicObject>, Error> {
+    let mut objects = Vec::new();
+    for (index, document) in serde_yaml::Deserializer::from_str(yaml).enumerate() {
+        let value =
+            serde_yaml::Value::deserialize(document).map_err(|source| Error::Parse { index, source })?;
+        if value.is_null() {
+            continue;
+        }
+        let object: DynamicObject =
+            serde_yaml::from_value(value).map_err(|source| Error::Parse { index, source })?;
+        let Some(kind) = object.types.as_ref().map(|t| t.kind.as_str()) else {
+            return Err(Error::InvalidType { index });
+        };
+        if kind.ends_with("List") && object.data.get("items").is_some_and(serde_json::Value::is_array) {
+            let items = object.data["items"].as_array().into_iter().flatten();
+            for item in items {
+                let item = DynamicObject::deserialize(item).map_err(|_| Error::InvalidType { index })?;
+                gvk_of(&item).ok_or(Error::InvalidType { index })?;
+                objects.push(item);
+            }
+            continue;
+        }
+        gvk_of(&object).ok_or(Error::InvalidType { index })?;
+        objects.push(object);
+    }
+    Ok(objects)
+}
+
+/// Server-side apply every object in a multi-document YAML manifest
+///
+/// `Namespace`s are applied first, then `CustomResourceDefinition`s, which are waited for until they are
+/// established. The remaining objects are applied in the order they appear in the manifest.
+/// Namespaced objects without a namespace are applied to [`ApplyParams::namespace`],
+/// or the default namespace of the client.
+///
+/// A result is returned for every object in manifest order, since objects that fail to apply do not stop the
+/// rest of the manifest from being applied.
+///
+/// ```no_run
+/// use kube::runtime::manifest::{apply_manifests, ApplyParams};
+/// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+/// # let client: kube::Client = todo!();
+///
+/// let yaml = std::fs::read_to_string("install.yaml")?;
+/// let params = ApplyParams::new("my-installer").force();
+/// for applied in apply_manifests(client, &yaml, &params).await? {
+///     match applied.result {
+///         Ok(_) => println!("applied {} {:?}", applied.gvk.kind, applied.name),
+///         Err(err) => eprintln!("failed to apply {} {:?}: {err}", applied.gvk.kind, applied.name),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Fails without applying anything if the manifest cannot be parsed, or if api discovery fails.
+pub async fn apply_manifests(
+    client: Client,
+    yaml: &str,
+    params: &ApplyParams,
+) -> Result<Vec<AppliedObject>, Error> {
+    let objects = parse_manifests(yaml)?;
+    let discovery = Discovery::new(client.clone())
+        .run()
+        .await
+        .map_err(Error::Discovery)?;
+    Ok(apply_objects(&client, &discovery, &objects, params).await)
+}
+
+/// Apply parsed objects in dependency order, returning their results in the original order
+pub(crate) async fn apply_objects(
+    client: &Client,
+    discovery: &Discovery,
+    objects: &[DynamicObject],
+    params: &ApplyParams,
+) -> Vec<AppliedObject> {
+    let mut order: Vec<usize> = (0..objects.len()).collect();
+    order.sort_by_key(|&i| apply_phase(&objects[i]));
+    let (early, late): (Vec<usize>, Vec<usize>) = order
+        .into_iter()
+        .partition(|&i| apply_phase(&objects[i]) < ApplyPhase::Other);
+
+    let mut results = Vec::with_capacity(objects.len());
+    for i in early {
+        let mut applied = apply_object(client, discovery, &objects[i], params).await;
+        if let (Ok(crd), false) = (&applied.result, params.dry_run) {
+            if apply_phase(crd) == ApplyPhase::CustomResourceDefinition {
+                if let Err(err) = await_crd(client, applied.name.as_deref(), params.crd_timeout).await {
+                    applied.result = Err(err);
+                }
+            }
+        }
+        results.push((i, applied));
+    }
+    for i in late {
+        results.push((i, apply_object(client, discovery, &objects[i], params).await));
+    }
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, applied)| applied).collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum ApplyPhase {
+    Namespace,
+    CustomResourceDefinition,
+    Other,
+}
+
+fn apply_phase(object: &DynamicObject) -> ApplyPhase {
+    match gvk_of(object) {
+        Some(gvk) if gvk.group.is_empty() && gvk.kind == "Namespace" => ApplyPhase::Namespace,
+        Some(gvk) if gvk.group == "apiextensions.k8s.io" && gvk.kind == "CustomResourceDefinition" => {
+            ApplyPhase::CustomResourceDefinition
+        }
+        _ => ApplyPhase::Other,
+    }
+}
+
+pub(crate) fn gvk_of(object: &DynamicObject) -> Option<GroupVersionKind> {
+    GroupVersionKind::try_from(object.types.as_ref()?).ok()
+}
+
+async fn apply_object(
+    client: &Client,
+    discovery: &Discovery,
+    object: &DynamicObject,
+    params: &ApplyParams,
+) -> AppliedObject {
+    // NB: parse_manifests only returns objects with a valid type
+    let gvk = gvk_of(object).unwrap_or_else(|| GroupVersionKind::gvk("", "", ""));
+    let name = object.metadata.name.clone();
+    let resolved = match discovery.resolve_gvk(&gvk) {
+        Some(resolved) => Ok(resolved),
+        // The kind may be defined by a CRD applied after discovery ran
+        None => discovery::pinned_kind(client, &gvk)
+            .await
+            .map_err(Error::Discovery),
+    };
+    let (ar, caps) = match resolved {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            return AppliedObject {
+                gvk,
+                namespace: object.metadata.namespace.clone(),
+                name,
+                result: Err(err),
+            }
+        }
+    };
+    let namespace = (caps.scope == Scope::Namespaced).then(|| {
+        object
+            .metadata
+            .namespace
+            .clone()
+            .or_else(|| params.namespace.clone())
+            .unwrap_or_else(|| client.default_namespace().to_string())
+    });
+    let api: Api<DynamicObject> = match &namespace {
+        Some(ns) => Api::namespaced_with(client.clone(), ns, &ar),
+        None => Api::all_with(client.clone(), &ar),
+    };
+    let mut pp = PatchParams::apply(&params.field_manager);
+    if params.force {
+        pp = pp.force();
+    }
+    if params.dry_run {
+        pp = pp.dry_run();
+    }
+    let result = match &name {
+        Some(name) => api
+            .patch(name, &pp, &Patch::Apply(object))
+            .await
+            .map_err(Error::Apply),
+        None => Err(Error::MissingName),
+    };
+    AppliedObject {
+        gvk,
+        namespace,
+        name,
+        result,
+    }
+}
+
+async fn await_crd(client: &Client, name: Option<&str>, timeout: Duration) -> Result<(), Error> {
+    let crds: Api<CustomResourceDefinition> = Api::all(client.clone());
+    let established = await_condition(crds, name.unwrap_or_default(), conditions::is_crd_established());
+    match tokio::time::timeout(timeout, established).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(err)) => Err(Error::AwaitCrd(err)),
+        Err(_) => Err(Error::AwaitCrd(wait::Error::TimedOut)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_phase, parse_manifests, ApplyPhase, Error};
+
+    #[test]
+    fn parses_multi_document_manifests() {
+        let objects = parse_manifests(
+            r"
+---
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: settings
+data:
+  key: value
+---
+# only a comment
+---
+apiVersion: v1
+kind: List
+items:
+- apiVersion: apiextensions.k8s.io/v1
+  kind: CustomResourceDefinition
+  metadata:
+    name: foos.clux.dev
+- apiVersion: v1
+  kind: Namespace
+  metadata:
+    name: foo
+",
+        )
+        .unwrap();
+        let names: Vec<_> = objects
+            .iter()
+            .map(|o| o.metadata.name.as_deref().unwrap())
+            .collect();
+        assert_eq!(names, ["settings", "foos.clux.dev", "foo"]);
+        let phases: Vec<_> = objects.iter().map(apply_phase).collect();
+        assert_eq!(phases, [
+            ApplyPhase::Other,
+            ApplyPhase::CustomResourceDefinition,
+            ApplyPhase::Namespace
+        ]);
+        assert_eq!(objects[0].data["data"]["key"], "value");
+    }
+
+    #[test]
+    fn rejects_documents_without_a_type() {
+        let err =
+            parse_manifests("apiVersion: v1\nkind: Pod\n---\nmetadata:\n  name: untyped\n").unwrap_err();
+        assert!(matches!(err, Error::InvalidType { index: 1 }), "{err:?}");
+        let err = parse_manifests("kind: [").unwrap_err();
+        assert!(matches!(err, Error::Parse { index: 0, .. }), "{err:?}");
+    }
+}
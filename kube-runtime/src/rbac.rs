@@ -0,0 +1,444 @@
+//! Derive RBAC rules for controllers and verify them against the apiserver
+//!
+//! A [`Controller`](crate::Controller) knows which resources it watches, so the permissions
+//! it needs can be collected from its configuration via [`Controller::permissions`](crate::Controller::permissions).
+//! Anything the reconciler does on top (creating children, patching status, publishing events)
+//! can be declared through [`Permissions::allow`] and friends.
+//!
+//! The collected [`Permissions`] can be rendered as a [`ClusterRole`] or [`Role`],
+//! or checked at startup with [`Permissions::preflight`].
+//!
+//! ```no_run
+//! use k8s_openapi::api::{apps::v1::Deployment, core::v1::ConfigMap};
+//! use kube::{
+//!     runtime::{rbac::Permissions, watcher, Controller},
+//!     Api, Client,
+//! };
+//!
+//! # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = Client::try_default().await?;
+//! let controller = Controller::new(Api::<Deployment>::all(client.clone()), watcher::Config::default())
+//!     .owns(Api::<ConfigMap>::all(client.clone()), watcher::Config::default())
+//!     .with_permissions(
+//!         Permissions::new()
+//!             .allow_resource::<ConfigMap, _>(&(), ["create", "patch"])
+//!             .allow("events.k8s.io", "events", ["create", "patch"]),
+//!     );
+//!
+//! // generate the ClusterRole for your deployment manifests
+//! let role = controller.permissions().cluster_role("my-controller");
+//!
+//! // or fail fast when the running service account lacks permissions
+//! controller.permissions().preflight(client).await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display},
+};
+
+use futures::future;
+use k8s_openapi::{
+    api::{
+        authorization::v1::{ResourceAttributes, SelfSubjectAccessReview, SelfSubjectAccessReviewSpec},
+        rbac::v1::{ClusterRole, PolicyRule, Role},
+    },
+    apimachinery::pkg::apis::meta::v1::ObjectMeta,
+};
+use kube_client::{
+    api::{Api, PostParams},
+    Client, Resource,
+};
+use thiserror::Error;
+
+/// The verbs needed to run a [`watcher`](crate::watcher()) against a resource
+pub(crate) const WATCH_VERBS: [&str; 2] = ["list", "watch"];
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to review access: {0}")]
+    ReviewFailed(#[source] kube_client::Error),
+    #[error("missing permissions: {}", DisplayMissing(.0))]
+    MissingPermissions(Vec<MissingPermission>),
+}
+
+/// A resource (or subresource) that permissions are requested for
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct ResourceKey {
+    group: String,
+    resource: String,
+    namespace: Option<String>,
+}
+
+/// A set of API permissions required by a controller
+///
+/// Permissions are tracked per `(group, resource, namespace)`, where `resource` is the plural
+/// resource name, optionally followed by a subresource (e.g. `deployments/status`).
+/// A `namespace` of `None` means the permission is needed across the cluster.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Permissions {
+    rules: BTreeMap<ResourceKey, BTreeSet<String>>,
+}
+
+impl Permissions {
+    /// Create an empty set of permissions
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow `verbs` on a resource in an api group across the cluster
+    ///
+    /// The core api group is denoted by the empty string, and subresources can be specified
+    /// using the `resource/subresource` form.
+    #[must_use]
+    pub fn allow<V: Into<String>>(
+        self,
+        group: impl Into<String>,
+        resource: impl Into<String>,
+        verbs: impl IntoIterator<Item = V>,
+    ) -> Self {
+        self.allow_in(None, group.into(), resource.into(), verbs)
+    }
+
+    /// Allow `verbs` on a resource in an api group within a single namespace
+    ///
+    /// Same as [`Permissions::allow`], but scoped to `namespace`.
+    #[must_use]
+    pub fn allow_namespaced<V: Into<String>>(
+        self,
+        namespace: &str,
+        group: impl Into<String>,
+        resource: impl Into<String>,
+        verbs: impl IntoIterator<Item = V>,
+    ) -> Self {
+        self.allow_in(Some(namespace.into()), group.into(), resource.into(), verbs)
+    }
+
+    /// Allow `verbs` on the resource `K` across the cluster
+    #[must_use]
+    pub fn allow_resource<K: Resource, V: Into<String>>(
+        self,
+        dyntype: &K::DynamicType,
+        verbs: impl IntoIterator<Item = V>,
+    ) -> Self {
+        self.allow_in(None, K::group(dyntype).into(), K::plural(dyntype).into(), verbs)
+    }
+
+    /// Allow `verbs` on the resource `K` in the scope of an [`Api`]
+    ///
+    /// The permission is namespaced if the `Api` is namespaced, and cluster-wide otherwise.
+    #[must_use]
+    pub fn allow_api<K: Resource, V: Into<String>>(
+        self,
+        api: &Api<K>,
+        dyntype: &K::DynamicType,
+        verbs: impl IntoIterator<Item = V>,
+    ) -> Self {
+        self.allow_in(
+            api.namespace().map(String::from),
+            K::group(dyntype).into(),
+            K::plural(dyntype).into(),
+            verbs,
+        )
+    }
+
+    fn allow_in<V: Into<String>>(
+        mut self,
+        namespace: Option<String>,
+        group: String,
+        resource: String,
+        verbs: impl IntoIterator<Item = V>,
+    ) -> Self {
+        let key = ResourceKey {
+            group,
+            resource,
+            namespace,
+        };
+        self.rules
+            .entry(key)
+            .or_default()
+            .extend(verbs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Merge another set of permissions into this one
+    #[must_use]
+    pub fn merge(mut self, other: Permissions) -> Self {
+        for (key, verbs) in other.rules {
+            self.rules.entry(key).or_default().extend(verbs);
+        }
+        self
+    }
+
+    /// Whether no permissions have been requested
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The requested verbs per `(group, resource)` of the entries matching `filter`, merging namespaces
+    fn by_resource(&self, filter: impl Fn(&ResourceKey) -> bool) -> BTreeMap<(&str, &str), BTreeSet<&str>> {
+        let mut by_resource: BTreeMap<(&str, &str), BTreeSet<&str>> = BTreeMap::new();
+        for (key, verbs) in self.rules.iter().filter(|(key, _)| filter(key)) {
+            by_resource
+                .entry((&key.group, &key.resource))
+                .or_default()
+                .extend(verbs.iter().map(String::as_str));
+        }
+        by_resource
+    }
+
+    /// Render the permissions as RBAC [`PolicyRule`]s
+    ///
+    /// Resources within the same api group that need the same verbs are collapsed into a single rule.
+    /// Namespaces are not represented in a `PolicyRule`, they are determined by where the role is bound.
+    #[must_use]
+    pub fn policy_rules(&self) -> Vec<PolicyRule> {
+        self.rules_where(|_| true)
+    }
+
+    fn rules_where(&self, filter: impl Fn(&ResourceKey) -> bool) -> Vec<PolicyRule> {
+        let mut grouped: BTreeMap<(&str, BTreeSet<&str>), Vec<&str>> = BTreeMap::new();
+        for ((group, resource), verbs) in self.by_resource(filter) {
+            grouped.entry((group, verbs)).or_default().push(resource);
+        }
+        grouped
+            .into_iter()
+            .map(|((group, verbs), resources)| PolicyRule {
+                api_groups: Some(vec![group.into()]),
+                resources: Some(resources.into_iter().map(String::from).collect()),
+                verbs: verbs.into_iter().map(String::from).collect(),
+                ..PolicyRule::default()
+            })
+            .collect()
+    }
+
+    /// Render the permissions as a [`ClusterRole`] named `name`
+    #[must_use]
+    pub fn cluster_role(&self, name: &str) -> ClusterRole {
+        ClusterRole {
+            metadata: ObjectMeta {
+                name: Some(name.into()),
+                ..ObjectMeta::default()
+            },
+            rules: Some(self.policy_rules()),
+            ..ClusterRole::default()
+        }
+    }
+
+    /// Render the permissions requested in `namespace` as a [`Role`] named `name` in that namespace
+    ///
+    /// A `Role` can only grant access within its own namespace, so permissions requested cluster-wide
+    /// or in other namespaces are left out. Grant those through [`Permissions::cluster_role`] instead.
+    #[must_use]
+    pub fn role(&self, name: &str, namespace: &str) -> Role {
+        Role {
+            metadata: ObjectMeta {
+                name: Some(name.into()),
+                namespace: Some(namespace.into()),
+                ..ObjectMeta::default()
+            },
+            rules: Some(self.rules_where(|key| key.namespace.as_deref() == Some(namespace))),
+        }
+    }
+
+    /// Find the permissions that the current user lacks
+    ///
+    /// Issues one [`SelfSubjectAccessReview`] per requested verb and resource.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`](kube_client::Error) if a review could not be created.
+    pub async fn missing(&self, client: Client) -> Result<Vec<MissingPermission>, kube_client::Error> {
+        let reviews: &Api<SelfSubjectAccessReview> = &Api::all(client);
+        let checks = self.rules.iter().flat_map(|(key, verbs)| {
+            verbs.iter().map(move |verb| {
+                let reviews = reviews.clone();
+                let (resource, subresource) = match key.resource.split_once('/') {
+                    Some((resource, subresource)) => (resource, Some(subresource.to_string())),
+                    None => (key.resource.as_str(), None),
+                };
+                let review = SelfSubjectAccessReview {
+                    spec: SelfSubjectAccessReviewSpec {
+                        resource_attributes: Some(ResourceAttributes {
+                            group: Some(key.group.clone()),
+                            resource: Some(resource.into()),
+                            subresource,
+                            namespace: key.namespace.clone(),
+                            verb: Some(verb.clone()),
+                            ..ResourceAttributes::default()
+                        }),
+                        ..SelfSubjectAccessReviewSpec::default()
+                    },
+                    ..SelfSubjectAccessReview::default()
+                };
+                async move {
+                    let status = reviews.create(&PostParams::default(), &review).await?.status;
+                    let allowed = status.as_ref().is_some_and(|s| s.allowed);
+                    Ok::<_, kube_client::Error>((!allowed).then(|| MissingPermission {
+                        group: key.group.clone(),
+                        resource: key.resource.clone(),
+                        namespace: key.namespace.clone(),
+                        verb: verb.clone(),
+                        reason: status.and_then(|s| s.reason).filter(|r| !r.is_empty()),
+                    }))
+                }
+            })
+        });
+        let results = future::try_join_all(checks).await?;
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    /// Verify that the current user has all the requested permissions
+    ///
+    /// Intended to be called at startup, so that a controller with insufficient RBAC
+    /// fails immediately with a clear report, rather than later with a `403` from a watcher.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MissingPermissions`] listing every missing permission,
+    /// or [`Error::ReviewFailed`] if the access reviews could not be performed.
+    pub async fn preflight(&self, client: Client) -> Result<(), Error> {
+        let missing = self.missing(client).await.map_err(Error::ReviewFailed)?;
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::MissingPermissions(missing))
+        }
+    }
+}
+
+/// A permission that was denied by a [`SelfSubjectAccessReview`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingPermission {
+    /// The api group of the resource, empty for the core group
+    pub group: String,
+    /// The plural resource name, optionally followed by `/subresource`
+    pub resource: String,
+    /// The namespace the permission was checked in, `None` for cluster-wide
+    pub namespace: Option<String>,
+    /// The denied verb
+    pub verb: String,
+    /// The reason given by the authorizer, if any
+    pub reason: Option<String>,
+}
+
+impl Display for MissingPermission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot {} {}", self.verb, self.resource)?;
+        if !self.group.is_empty() {
+            write!(f, " in api group {:?}", self.group)?;
+        }
+        match &self.namespace {
+            Some(ns) => write!(f, " in namespace {ns:?}")?,
+            None => write!(f, " at the cluster scope")?,
+        }
+        if let Some(reason) = &self.reason {
+            write!(f, " ({reason})")?;
+        }
+        Ok(())
+    }
+}
+
+struct DisplayMissing<'a>(&'a [MissingPermission]);
+
+impl Display for DisplayMissing<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, missing) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            missing.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MissingPermission, Permissions};
+    use k8s_openapi::api::{apps::v1::Deployment, core::v1::ConfigMap};
+
+    #[test]
+    fn policy_rules_collapse_resources_with_identical_verbs() {
+        let perms = Permissions::new()
+            .allow_resource::<Deployment, _>(&(), ["list", "watch"])
+            .allow_resource::<ConfigMap, _>(&(), ["list", "watch"])
+            .allow_namespaced("kube-system", "", "secrets", ["list", "watch"])
+            .allow_resource::<ConfigMap, _>(&(), ["create"])
+            .allow("apps", "deployments/status", ["patch"]);
+
+        let rules = perms.policy_rules();
+        assert_eq!(rules.len(), 4);
+        assert_eq!(rules[0].api_groups, Some(vec![String::new()]));
+        assert_eq!(rules[0].resources, Some(vec!["configmaps".to_string()]));
+        assert_eq!(rules[0].verbs, vec!["create", "list", "watch"]);
+        assert_eq!(rules[1].resources, Some(vec!["secrets".to_string()]));
+        assert_eq!(rules[1].verbs, vec!["list", "watch"]);
+        assert_eq!(rules[2].api_groups, Some(vec!["apps".to_string()]));
+        assert_eq!(rules[2].resources, Some(vec!["deployments".to_string()]));
+        assert_eq!(rules[2].verbs, vec!["list", "watch"]);
+        assert_eq!(rules[3].resources, Some(vec!["deployments/status".to_string()]));
+        assert_eq!(rules[3].verbs, vec!["patch"]);
+
+        let collapsed = Permissions::new()
+            .allow_resource::<Deployment, _>(&(), ["list", "watch"])
+            .allow("apps", "statefulsets", ["watch", "list"])
+            .policy_rules();
+        assert_eq!(collapsed.len(), 1);
+        assert_eq!(
+            collapsed[0].resources,
+            Some(vec!["deployments".to_string(), "statefulsets".to_string()])
+        );
+    }
+
+    #[test]
+    fn roles_carry_rules_and_metadata() {
+        let perms = Permissions::new()
+            .allow("events.k8s.io", "events", ["create", "patch"])
+            .allow_namespaced("ns", "", "configmaps", ["get"])
+            .allow_namespaced("ns", "", "secrets", ["get"])
+            .allow_namespaced("other", "", "pods", ["list"]);
+        let cr = perms.cluster_role("ctrl");
+        assert_eq!(cr.metadata.name.as_deref(), Some("ctrl"));
+        assert_eq!(cr.rules, Some(perms.policy_rules()));
+
+        // only the permissions requested in the role's own namespace are granted by the role
+        let role = perms.role("ctrl", "ns");
+        assert_eq!(role.metadata.name.as_deref(), Some("ctrl"));
+        assert_eq!(role.metadata.namespace.as_deref(), Some("ns"));
+        let rules = role.rules.unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].api_groups, Some(vec![String::new()]));
+        assert_eq!(
+            rules[0].resources,
+            Some(vec!["configmaps".to_string(), "secrets".to_string()])
+        );
+        assert_eq!(rules[0].verbs, vec!["get"]);
+        assert_eq!(perms.role("ctrl", "empty").rules, Some(vec![]));
+    }
+
+    #[test]
+    fn merge_unions_verbs() {
+        let a = Permissions::new().allow("", "pods", ["get"]);
+        let b = Permissions::new().allow("", "pods", ["list"]);
+        assert_eq!(a.merge(b), Permissions::new().allow("", "pods", ["get", "list"]));
+    }
+
+    #[test]
+    fn missing_permission_display() {
+        let missing = MissingPermission {
+            group: "apps".into(),
+            resource: "deployments".into(),
+            namespace: Some("default".into()),
+            verb: "watch".into(),
+            reason: None,
+        };
+        assert_eq!(
+            missing.to_string(),
+            r#"cannot watch deployments in api group "apps" in namespace "default""#
+        );
+    }
+}
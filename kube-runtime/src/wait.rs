@@ -1,7 +1,7 @@
 //! Waits for objects to reach desired states
-use std::{future, pin::pin};
+use std::{future, pin::pin, time::Duration};
 
-use futures::TryStreamExt;
+use futures::{Stream, TryStreamExt};
 use kube_client::{Api, Resource};
 use serde::de::DeserializeOwned;
 use std::fmt::Debug;
@@ -13,6 +13,8 @@ use crate::watcher::{self, watch_object};
 pub enum Error {
     #[error("failed to probe for whether the condition is fulfilled yet: {0}")]
     ProbeFailed(#[source] watcher::Error),
+    #[error("timed out waiting for the condition to be fulfilled")]
+    TimedOut,
 }
 
 /// Watch an object, and wait for some condition `cond` to return `true`.
@@ -68,6 +70,67 @@ where
     Ok(obj)
 }
 
+/// Watch an object until some condition `cond` returns `true`, or `timeout` elapses.
+///
+/// Unlike [`await_condition`], this returns a stream of every observed state of the object,
+/// so that callers can report on intermediate progress. The stream ends after yielding the first
+/// state that fulfills the condition.
+///
+/// `cond` is passed `Some` if the object is found, otherwise `None`.
+///
+/// # Errors
+///
+/// Yields [`Error::TimedOut`] and ends if the condition is not fulfilled within `timeout`.
+/// Otherwise fails like [`await_condition`].
+///
+/// # Usage
+///
+/// ```no_run
+/// use futures::TryStreamExt;
+/// use k8s_openapi::api::batch::v1::Job;
+/// use kube::{Api, runtime::wait::{await_condition_progress, conditions}};
+/// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+/// # let client: kube::Client = todo!();
+///
+/// let jobs: Api<Job> = Api::default_namespaced(client);
+/// let timeout = std::time::Duration::from_secs(60);
+/// let mut progress = std::pin::pin!(await_condition_progress(jobs, "pi", conditions::is_job_completed(), timeout));
+/// while let Some(job) = progress.try_next().await? {
+///     let succeeded = job.and_then(|j| j.status).and_then(|s| s.succeeded);
+///     println!("job succeeded pods: {succeeded:?}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn await_condition_progress<K>(
+    api: Api<K>,
+    name: &str,
+    cond: impl Condition<K>,
+    timeout: Duration,
+) -> impl Stream<Item = Result<Option<K>, Error>>
+where
+    K: Clone + Debug + Send + DeserializeOwned + Resource + 'static,
+{
+    let updates = watch_object(api, name);
+    async_stream::try_stream! {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut updates = pin!(updates);
+        loop {
+            let obj = tokio::time::timeout_at(deadline, updates.try_next())
+                .await
+                .map_err(|_| Error::TimedOut)?
+                .map_err(Error::ProbeFailed)?;
+            // watch never actually terminates
+            let Some(obj) = obj else { break };
+            let done = cond.matches_object(obj.as_ref());
+            yield obj;
+            if done {
+                break;
+            }
+        }
+    }
+}
+
 /// A trait for condition functions to be used by [`await_condition`]
 ///
 /// Note that this is auto-implemented for functions of type `fn(Option<&K>) -> bool`.
@@ -161,12 +224,20 @@ impl<K, F: Fn(Option<&K>) -> bool> Condition<K> for F {
 
 /// Common conditions to wait for
 pub mod conditions {
+    use super::rollout::{Rollout, RolloutStatus};
     pub use super::Condition;
     use k8s_openapi::{
-        api::{batch::v1::Job, core::v1::Pod},
+        api::{
+            apps::v1::{DaemonSet, Deployment, StatefulSet},
+            batch::v1::Job,
+            core::v1::Pod,
+        },
         apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition,
     };
     use kube_client::Resource;
+    use serde::Serialize;
+    use serde_json::Value;
+    #[cfg(feature = "jsonpath")] use thiserror::Error;
 
     /// An await condition that returns `true` once the object has been deleted.
     ///
@@ -237,6 +308,180 @@ pub mod conditions {
         }
     }
 
+    /// An await condition that returns `true` once the rollout of a workload has completed
+    ///
+    /// Equivalent to a successful `kubectl rollout status`. See [`Rollout`] for the per-type semantics.
+    #[must_use]
+    pub fn is_rolled_out<K: Rollout>() -> impl Condition<K> {
+        |obj: Option<&K>| obj.is_some_and(|o| o.rollout_status() == RolloutStatus::Complete)
+    }
+
+    /// An await condition for `Deployment` that returns `true` once its rollout has completed
+    #[must_use]
+    pub fn is_deployment_rolled_out() -> impl Condition<Deployment> {
+        is_rolled_out()
+    }
+
+    /// An await condition for `StatefulSet` that returns `true` once its rollout has completed
+    #[must_use]
+    pub fn is_statefulset_rolled_out() -> impl Condition<StatefulSet> {
+        is_rolled_out()
+    }
+
+    /// An await condition for `DaemonSet` that returns `true` once its rollout has completed
+    #[must_use]
+    pub fn is_daemonset_rolled_out() -> impl Condition<DaemonSet> {
+        is_rolled_out()
+    }
+
+    /// An await condition that returns `true` once the rollout of a workload has either completed or failed
+    ///
+    /// Useful in combination with [`await_condition`](super::await_condition), to avoid waiting forever on a
+    /// `Deployment` that exceeded its progress deadline.
+    #[must_use]
+    pub fn is_rollout_finished<K: Rollout>() -> impl Condition<K> {
+        |obj: Option<&K>| {
+            obj.is_some_and(|o| {
+                matches!(
+                    o.rollout_status(),
+                    RolloutStatus::Complete | RolloutStatus::Failed(_)
+                )
+            })
+        }
+    }
+
+    /// An await condition that returns `true` once the object has a status condition of type `type_` with `status`
+    ///
+    /// Equivalent to `kubectl wait --for=condition=<type_>=<status>`, and works on any serializable resource
+    /// (including [`DynamicObject`](kube_client::api::DynamicObject)) that follows the `.status.conditions` convention.
+    /// Both `type_` and `status` are compared case-insensitively. Conditions reporting an `observedGeneration`
+    /// older than the object's `generation` are considered stale and do not match.
+    #[must_use]
+    pub fn has_condition<'a, K: Serialize>(type_: &'a str, status: &'a str) -> impl Condition<K> + 'a {
+        move |obj: Option<&K>| {
+            let Some(json) = obj.and_then(|o| serde_json::to_value(o).ok()) else {
+                return false;
+            };
+            let generation = json.pointer("/metadata/generation").and_then(Value::as_i64);
+            let Some(conds) = json.pointer("/status/conditions").and_then(Value::as_array) else {
+                return false;
+            };
+            conds.iter().any(|c| {
+                let field_is = |field: &str, expected: &str| {
+                    c.get(field)
+                        .and_then(Value::as_str)
+                        .is_some_and(|v| v.eq_ignore_ascii_case(expected))
+                };
+                let stale = match (c.get("observedGeneration").and_then(Value::as_i64), generation) {
+                    (Some(observed), Some(generation)) => observed < generation,
+                    _ => false,
+                };
+                field_is("type", type_) && field_is("status", status) && !stale
+            })
+        }
+    }
+
+    /// An invalid `JSONPath` expression was passed to a [`JsonPathCondition`]
+    #[cfg(feature = "jsonpath")]
+    #[derive(Debug, Error)]
+    #[error("invalid jsonpath expression {expr:?}: {reason}")]
+    pub struct InvalidJsonPath {
+        expr: String,
+        reason: String,
+    }
+
+    /// An await condition on the value found at a `JSONPath` expression
+    ///
+    /// Equivalent to `kubectl wait --for=jsonpath='{.status.phase}'=Ready`, and works on any serializable resource
+    /// (including [`DynamicObject`](kube_client::api::DynamicObject)).
+    ///
+    /// ```
+    /// use kube::{api::DynamicObject, runtime::wait::conditions::JsonPathCondition};
+    /// # fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+    /// let ready = JsonPathCondition::equals("{.status.phase}", "Ready")?;
+    /// let has_endpoint = JsonPathCondition::exists(".status.loadBalancer.ingress[0].ip")?;
+    /// # let _ = (ready, has_endpoint);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "jsonpath")]
+    #[derive(Clone, Debug)]
+    pub struct JsonPathCondition {
+        path: jsonpath_rust::JsonPath,
+        value: Option<String>,
+    }
+
+    #[cfg(feature = "jsonpath")]
+    impl JsonPathCondition {
+        /// A condition that holds once the expression resolves to a single value equal to `value`
+        ///
+        /// The expression can be given in the kubectl form (`{.status.phase}`), or as a plain path
+        /// (`.status.phase` or `$.status.phase`). Non-string values are compared using their JSON representation,
+        /// so `true` matches a boolean and `3` matches a number.
+        ///
+        /// # Errors
+        ///
+        /// Returns [`InvalidJsonPath`] if the expression cannot be parsed.
+        pub fn equals(expr: &str, value: &str) -> Result<Self, InvalidJsonPath> {
+            Ok(Self {
+                path: parse_jsonpath(expr)?,
+                value: Some(value.into()),
+            })
+        }
+
+        /// A condition that holds once the expression resolves to any value
+        ///
+        /// # Errors
+        ///
+        /// Returns [`InvalidJsonPath`] if the expression cannot be parsed.
+        pub fn exists(expr: &str) -> Result<Self, InvalidJsonPath> {
+            Ok(Self {
+                path: parse_jsonpath(expr)?,
+                value: None,
+            })
+        }
+    }
+
+    #[cfg(feature = "jsonpath")]
+    fn parse_jsonpath(expr: &str) -> Result<jsonpath_rust::JsonPath, InvalidJsonPath> {
+        let trimmed = expr.trim().trim_start_matches('{').trim_end_matches('}');
+        let path = if trimmed.starts_with('$') {
+            trimmed.to_string()
+        } else {
+            format!("${trimmed}")
+        };
+        path.parse().map_err(
+            |err: jsonpath_rust::parser::JsonPathParserError| InvalidJsonPath {
+                expr: expr.into(),
+                reason: err.to_string(),
+            },
+        )
+    }
+
+    #[cfg(feature = "jsonpath")]
+    impl<K: Serialize> Condition<K> for JsonPathCondition {
+        fn matches_object(&self, obj: Option<&K>) -> bool {
+            let Some(json) = obj.and_then(|o| serde_json::to_value(o).ok()) else {
+                return false;
+            };
+            let found = self
+                .path
+                .find_slice(&json)
+                .into_iter()
+                .filter(jsonpath_rust::JsonPathValue::has_value)
+                .map(jsonpath_rust::JsonPathValue::to_data)
+                .collect::<Vec<_>>();
+            match (&self.value, found.as_slice()) {
+                (None, found) => !found.is_empty(),
+                (Some(expected), [Value::String(actual)]) => actual == expected,
+                (Some(expected), [actual]) => {
+                    serde_json::from_str::<Value>(expected).is_ok_and(|v| v == *actual)
+                }
+                (Some(_), _) => false,
+            }
+        }
+    }
+
     /// See [`Condition::not`]
     #[derive(Copy, Clone, Debug, PartialEq, Eq)]
     pub struct Not<A>(pub(super) A);
@@ -271,6 +516,470 @@ pub mod conditions {
             self.0.matches_object(obj) || self.1.matches_object(obj)
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        #[cfg(feature = "jsonpath")] use super::JsonPathCondition;
+        use super::{has_condition, Condition};
+        use kube_client::api::DynamicObject;
+
+        fn object(json: serde_json::Value) -> DynamicObject {
+            serde_json::from_value(json).unwrap()
+        }
+
+        #[cfg(feature = "jsonpath")]
+        #[test]
+        fn jsonpath_conditions_on_dynamic_objects() {
+            let obj = object(serde_json::json!({
+                "apiVersion": "clux.dev/v1",
+                "kind": "Foo",
+                "metadata": { "name": "foo" },
+                "status": { "phase": "Ready", "replicas": 3, "paused": false }
+            }));
+            let obj = Some(&obj);
+            assert!(JsonPathCondition::equals("{.status.phase}", "Ready")
+                .unwrap()
+                .matches_object(obj));
+            assert!(!JsonPathCondition::equals(".status.phase", "Pending")
+                .unwrap()
+                .matches_object(obj));
+            assert!(JsonPathCondition::equals("$.status.replicas", "3")
+                .unwrap()
+                .matches_object(obj));
+            assert!(JsonPathCondition::equals("{.status.paused}", "false")
+                .unwrap()
+                .matches_object(obj));
+            assert!(JsonPathCondition::exists("{.status.phase}")
+                .unwrap()
+                .matches_object(obj));
+            assert!(!JsonPathCondition::exists("{.status.missing}")
+                .unwrap()
+                .matches_object(obj));
+            assert!(!JsonPathCondition::exists("{.status.phase}")
+                .unwrap()
+                .matches_object(None::<&DynamicObject>));
+            assert!(JsonPathCondition::exists("{.status[}").is_err());
+        }
+
+        #[test]
+        fn has_condition_on_dynamic_objects() {
+            let obj = object(serde_json::json!({
+                "apiVersion": "clux.dev/v1",
+                "kind": "Foo",
+                "metadata": { "name": "foo", "generation": 2 },
+                "status": { "conditions": [
+                    { "type": "Available", "status": "True", "observedGeneration": 2 },
+                    { "type": "Degraded", "status": "True", "observedGeneration": 1 },
+                ]}
+            }));
+            let obj = Some(&obj);
+            assert!(has_condition("Available", "True").matches_object(obj));
+            assert!(has_condition("available", "true").matches_object(obj));
+            assert!(!has_condition("Available", "False").matches_object(obj));
+            // stale conditions do not count
+            assert!(!has_condition("Degraded", "True").matches_object(obj));
+            assert!(!has_condition("Progressing", "True").matches_object(obj));
+        }
+    }
+}
+
+/// Rollout status for `apps/v1` workloads
+///
+/// Mirrors the checks done by `kubectl rollout status`.
+pub mod rollout {
+    use super::{await_condition_progress, conditions, Error};
+    use futures::{Stream, StreamExt};
+    use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, StatefulSet};
+    use kube_client::{Api, Resource, ResourceExt};
+    use serde::de::DeserializeOwned;
+    use std::{fmt::Debug, time::Duration};
+
+    /// The state of a workload rollout
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum RolloutStatus {
+        /// The rollout is still in progress, with a description of what it is waiting for
+        Progressing(String),
+        /// The rollout has completed
+        Complete,
+        /// The rollout has failed, or its status cannot be tracked
+        Failed(String),
+    }
+
+    /// Workloads whose rollout status can be determined from the object
+    pub trait Rollout: Resource {
+        /// Determine the rollout status of the object
+        fn rollout_status(&self) -> RolloutStatus;
+    }
+
+    /// Whether the controller has observed the latest spec of an object
+    fn observed<K: Resource>(obj: &K, observed_generation: Option<i64>) -> bool {
+        match (obj.meta().generation, observed_generation) {
+            (Some(generation), Some(observed)) => generation <= observed,
+            (None, _) => true,
+            (Some(_), None) => false,
+        }
+    }
+
+    impl Rollout for Deployment {
+        /// Compares the updated and available replicas to the desired replicas, once the latest
+        /// generation has been observed.
+        ///
+        /// Fails if the `Progressing` condition reports `ProgressDeadlineExceeded`.
+        fn rollout_status(&self) -> RolloutStatus {
+            let name = self.name_any();
+            let status = self.status.clone().unwrap_or_default();
+            if !observed(self, status.observed_generation) {
+                return RolloutStatus::Progressing(
+                    "waiting for deployment spec update to be observed".into(),
+                );
+            }
+            let deadline_exceeded =
+                status.conditions.iter().flatten().any(|c| {
+                    c.type_ == "Progressing" && c.reason.as_deref() == Some("ProgressDeadlineExceeded")
+                });
+            if deadline_exceeded {
+                return RolloutStatus::Failed(format!("deployment {name:?} exceeded its progress deadline"));
+            }
+            let updated = status.updated_replicas.unwrap_or_default();
+            let replicas = status.replicas.unwrap_or_default();
+            let available = status.available_replicas.unwrap_or_default();
+            if let Some(desired) = self.spec.as_ref().and_then(|s| s.replicas) {
+                if updated < desired {
+                    return RolloutStatus::Progressing(format!(
+                        "waiting for deployment {name:?} rollout to finish: {updated} out of {desired} new replicas have been updated"
+                    ));
+                }
+            }
+            if replicas > updated {
+                return RolloutStatus::Progressing(format!(
+                    "waiting for deployment {name:?} rollout to finish: {} old replicas are pending termination",
+                    replicas - updated
+                ));
+            }
+            if available < updated {
+                return RolloutStatus::Progressing(format!(
+                    "waiting for deployment {name:?} rollout to finish: {available} of {updated} updated replicas are available"
+                ));
+            }
+            RolloutStatus::Complete
+        }
+    }
+
+    impl Rollout for StatefulSet {
+        /// Compares the ready and updated replicas to the desired replicas (respecting partitions),
+        /// and the current revision to the update revision, once the latest generation has been observed.
+        ///
+        /// Fails for update strategies other than `RollingUpdate`.
+        fn rollout_status(&self) -> RolloutStatus {
+            let name = self.name_any();
+            let spec = self.spec.clone().unwrap_or_default();
+            let strategy = spec.update_strategy.unwrap_or_default();
+            if strategy.type_.as_deref().is_some_and(|t| t != "RollingUpdate") {
+                return RolloutStatus::Failed(
+                    "rollout status is only available for the RollingUpdate strategy type".into(),
+                );
+            }
+            let status = self.status.clone().unwrap_or_default();
+            if status.observed_generation.is_none() || !observed(self, status.observed_generation) {
+                return RolloutStatus::Progressing(
+                    "waiting for statefulset spec update to be observed".into(),
+                );
+            }
+            let ready = status.ready_replicas.unwrap_or_default();
+            let updated = status.updated_replicas.unwrap_or_default();
+            if let Some(desired) = spec.replicas {
+                if ready < desired {
+                    return RolloutStatus::Progressing(format!(
+                        "waiting for statefulset {name:?}: {} pods to be ready",
+                        desired - ready
+                    ));
+                }
+            }
+            if let Some(partition) = strategy.rolling_update.and_then(|r| r.partition) {
+                if let Some(desired) = spec.replicas {
+                    if updated < desired - partition {
+                        return RolloutStatus::Progressing(format!(
+                            "waiting for statefulset {name:?} partitioned rollout to finish: {updated} out of {} new pods have been updated",
+                            desired - partition
+                        ));
+                    }
+                }
+                return RolloutStatus::Complete;
+            }
+            if status.update_revision != status.current_revision {
+                return RolloutStatus::Progressing(format!(
+                    "waiting for statefulset {name:?} rolling update to complete {} pods at revision {}",
+                    status.updated_replicas.unwrap_or_default(),
+                    status.update_revision.unwrap_or_default()
+                ));
+            }
+            RolloutStatus::Complete
+        }
+    }
+
+    impl Rollout for DaemonSet {
+        /// Compares the updated and available pods to the desired number of scheduled pods,
+        /// once the latest generation has been observed.
+        ///
+        /// Fails for update strategies other than `RollingUpdate`.
+        fn rollout_status(&self) -> RolloutStatus {
+            let name = self.name_any();
+            let strategy = self
+                .spec
+                .as_ref()
+                .and_then(|s| s.update_strategy.as_ref())
+                .and_then(|s| s.type_.as_deref());
+            if strategy.is_some_and(|t| t != "RollingUpdate") {
+                return RolloutStatus::Failed(
+                    "rollout status is only available for the RollingUpdate strategy type".into(),
+                );
+            }
+            let status = self.status.clone().unwrap_or_default();
+            if !observed(self, status.observed_generation) {
+                return RolloutStatus::Progressing(
+                    "waiting for daemon set spec update to be observed".into(),
+                );
+            }
+            let desired = status.desired_number_scheduled;
+            let updated = status.updated_number_scheduled.unwrap_or_default();
+            let available = status.number_available.unwrap_or_default();
+            if updated < desired {
+                return RolloutStatus::Progressing(format!(
+                    "waiting for daemon set {name:?} rollout to finish: {updated} out of {desired} new pods have been updated"
+                ));
+            }
+            if available < desired {
+                return RolloutStatus::Progressing(format!(
+                    "waiting for daemon set {name:?} rollout to finish: {available} of {desired} updated pods are available"
+                ));
+            }
+            RolloutStatus::Complete
+        }
+    }
+
+    /// Watch the rollout of a workload, yielding its status until it completes, fails, or `timeout` elapses.
+    ///
+    /// Consecutive identical statuses are only yielded once. The stream ends after yielding
+    /// [`RolloutStatus::Complete`] or [`RolloutStatus::Failed`].
+    /// A missing object is reported as [`RolloutStatus::Progressing`].
+    ///
+    /// # Errors
+    ///
+    /// Yields [`Error::TimedOut`] if the rollout does not finish within `timeout`,
+    /// and otherwise fails like [`await_condition`](super::await_condition).
+    ///
+    /// # Usage
+    ///
+    /// ```no_run
+    /// use futures::TryStreamExt;
+    /// use k8s_openapi::api::apps::v1::Deployment;
+    /// use kube::{Api, runtime::wait::rollout::{await_rollout, RolloutStatus}};
+    /// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client: kube::Client = todo!();
+    ///
+    /// let deploys: Api<Deployment> = Api::default_namespaced(client);
+    /// let timeout = std::time::Duration::from_secs(300);
+    /// let mut rollout = std::pin::pin!(await_rollout(deploys, "nginx", timeout));
+    /// while let Some(status) = rollout.try_next().await? {
+    ///     match status {
+    ///         RolloutStatus::Progressing(msg) => println!("{msg}"),
+    ///         RolloutStatus::Complete => println!("rolled out"),
+    ///         RolloutStatus::Failed(msg) => return Err(msg.into()),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn await_rollout<K>(
+        api: Api<K>,
+        name: &str,
+        timeout: Duration,
+    ) -> impl Stream<Item = Result<RolloutStatus, Error>>
+    where
+        K: Rollout + Clone + Debug + Send + DeserializeOwned + 'static,
+    {
+        let name_owned = name.to_string();
+        await_condition_progress(api, name, conditions::is_rollout_finished(), timeout)
+            .map(move |obj| {
+                obj.map(|obj| {
+                    obj.map_or_else(
+                        || RolloutStatus::Progressing(format!("waiting for {name_owned:?} to be created")),
+                        |o| o.rollout_status(),
+                    )
+                })
+            })
+            .scan(None, |last, status| {
+                let duplicate = match (&status, &*last) {
+                    (Ok(status), Some(prev)) => status == prev,
+                    _ => false,
+                };
+                if let Ok(status) = &status {
+                    *last = Some(status.clone());
+                }
+                std::future::ready(Some((!duplicate).then_some(status)))
+            })
+            .filter_map(std::future::ready)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{Rollout, RolloutStatus};
+        use k8s_openapi::api::apps::v1::{
+            DaemonSet, DaemonSetStatus, Deployment, DeploymentCondition, DeploymentSpec, DeploymentStatus,
+            StatefulSet, StatefulSetSpec, StatefulSetStatus,
+        };
+        use kube_client::api::ObjectMeta;
+
+        fn meta(generation: i64) -> ObjectMeta {
+            ObjectMeta {
+                name: Some("app".into()),
+                generation: Some(generation),
+                ..ObjectMeta::default()
+            }
+        }
+
+        fn deployment(generation: i64, status: DeploymentStatus) -> Deployment {
+            Deployment {
+                metadata: meta(generation),
+                spec: Some(DeploymentSpec {
+                    replicas: Some(3),
+                    ..DeploymentSpec::default()
+                }),
+                status: Some(status),
+            }
+        }
+
+        #[test]
+        fn deployment_rollout_status() {
+            let unobserved = deployment(2, DeploymentStatus {
+                observed_generation: Some(1),
+                ..DeploymentStatus::default()
+            });
+            assert!(matches!(
+                unobserved.rollout_status(),
+                RolloutStatus::Progressing(_)
+            ));
+
+            let updating = deployment(2, DeploymentStatus {
+                observed_generation: Some(2),
+                replicas: Some(4),
+                updated_replicas: Some(1),
+                ..DeploymentStatus::default()
+            });
+            assert_eq!(
+                updating.rollout_status(),
+                RolloutStatus::Progressing(
+                    r#"waiting for deployment "app" rollout to finish: 1 out of 3 new replicas have been updated"#
+                        .into()
+                )
+            );
+
+            let terminating = deployment(2, DeploymentStatus {
+                observed_generation: Some(2),
+                replicas: Some(4),
+                updated_replicas: Some(3),
+                available_replicas: Some(3),
+                ..DeploymentStatus::default()
+            });
+            assert!(matches!(
+                terminating.rollout_status(),
+                RolloutStatus::Progressing(_)
+            ));
+
+            let complete = deployment(2, DeploymentStatus {
+                observed_generation: Some(2),
+                replicas: Some(3),
+                updated_replicas: Some(3),
+                available_replicas: Some(3),
+                ..DeploymentStatus::default()
+            });
+            assert_eq!(complete.rollout_status(), RolloutStatus::Complete);
+
+            let failed = deployment(2, DeploymentStatus {
+                observed_generation: Some(2),
+                conditions: Some(vec![DeploymentCondition {
+                    type_: "Progressing".into(),
+                    status: "False".into(),
+                    reason: Some("ProgressDeadlineExceeded".into()),
+                    ..DeploymentCondition::default()
+                }]),
+                ..DeploymentStatus::default()
+            });
+            assert!(matches!(failed.rollout_status(), RolloutStatus::Failed(_)));
+        }
+
+        #[test]
+        fn statefulset_rollout_status() {
+            let sts = |status: StatefulSetStatus| StatefulSet {
+                metadata: meta(1),
+                spec: Some(StatefulSetSpec {
+                    replicas: Some(2),
+                    ..StatefulSetSpec::default()
+                }),
+                status: Some(status),
+            };
+            let unready = sts(StatefulSetStatus {
+                observed_generation: Some(1),
+                ready_replicas: Some(1),
+                ..StatefulSetStatus::default()
+            });
+            assert!(matches!(unready.rollout_status(), RolloutStatus::Progressing(_)));
+
+            let revision_pending = sts(StatefulSetStatus {
+                observed_generation: Some(1),
+                ready_replicas: Some(2),
+                updated_replicas: Some(1),
+                current_revision: Some("app-1".into()),
+                update_revision: Some("app-2".into()),
+                ..StatefulSetStatus::default()
+            });
+            assert!(matches!(
+                revision_pending.rollout_status(),
+                RolloutStatus::Progressing(_)
+            ));
+
+            let complete = sts(StatefulSetStatus {
+                observed_generation: Some(1),
+                ready_replicas: Some(2),
+                updated_replicas: Some(2),
+                current_revision: Some("app-2".into()),
+                update_revision: Some("app-2".into()),
+                ..StatefulSetStatus::default()
+            });
+            assert_eq!(complete.rollout_status(), RolloutStatus::Complete);
+        }
+
+        #[test]
+        fn daemonset_rollout_status() {
+            let ds = |status: DaemonSetStatus| DaemonSet {
+                metadata: meta(3),
+                spec: None,
+                status: Some(status),
+            };
+            let updating = ds(DaemonSetStatus {
+                observed_generation: Some(3),
+                desired_number_scheduled: 5,
+                updated_number_scheduled: Some(5),
+                number_available: Some(4),
+                ..DaemonSetStatus::default()
+            });
+            assert_eq!(
+                updating.rollout_status(),
+                RolloutStatus::Progressing(
+                    r#"waiting for daemon set "app" rollout to finish: 4 of 5 updated pods are available"#
+                        .into()
+                )
+            );
+            let complete = ds(DaemonSetStatus {
+                observed_generation: Some(3),
+                desired_number_scheduled: 5,
+                updated_number_scheduled: Some(5),
+                number_available: Some(5),
+                ..DaemonSetStatus::default()
+            });
+            assert_eq!(complete.rollout_status(), RolloutStatus::Complete);
+        }
+    }
 }
 
 /// Utilities for deleting objects
@@ -8,14 +8,15 @@ use async_trait::async_trait;
 use backon::BackoffBuilder;
 use educe::Educe;
 use futures::{stream::BoxStream, Stream, StreamExt};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ListMeta;
 use kube_client::{
-    api::{ListParams, Resource, ResourceExt, VersionMatch, WatchEvent, WatchParams},
-    core::{metadata::PartialObjectMeta, ObjectList, Selector},
+    api::{ListParams, ListPart, Resource, ResourceExt, VersionMatch, WatchEvent, WatchParams},
+    core::{metadata::PartialObjectMeta, Selector},
     error::ErrorResponse,
-    Api, Error as ClientErr,
+    Api,
 };
 use serde::de::DeserializeOwned;
-use std::{clone::Clone, collections::VecDeque, fmt::Debug, future, time::Duration};
+use std::{clone::Clone, fmt::Debug, future, time::Duration};
 use thiserror::Error;
 use tracing::{debug, error, warn};
 
@@ -138,7 +139,13 @@ enum State<K> {
     /// The Watcher is in the process of paginating through the initial LIST
     InitPage {
         continue_token: Option<String>,
-        objects: VecDeque<K>,
+        last_bookmark: Option<String>,
+    },
+    /// The Watcher is returning the objects of a page of the initial LIST
+    InitPageItems {
+        #[educe(Debug(ignore))]
+        items: std::vec::IntoIter<K>,
+        continue_token: Option<String>,
         last_bookmark: Option<String>,
     },
     /// Kubernetes 1.27 Streaming Lists
@@ -168,7 +175,10 @@ enum State<K> {
 trait ApiMode {
     type Value: Clone;
 
-    async fn list(&self, lp: &ListParams) -> kube_client::Result<ObjectList<Self::Value>>;
+    async fn list(
+        &self,
+        lp: &ListParams,
+    ) -> kube_client::Result<BoxStream<'static, kube_client::Result<ListPart<Self::Value>>>>;
     async fn watch(
         &self,
         wp: &WatchParams,
@@ -443,8 +453,11 @@ where
 {
     type Value = K;
 
-    async fn list(&self, lp: &ListParams) -> kube_client::Result<ObjectList<Self::Value>> {
-        self.api.list(lp).await
+    async fn list(
+        &self,
+        lp: &ListParams,
+    ) -> kube_client::Result<BoxStream<'static, kube_client::Result<ListPart<Self::Value>>>> {
+        self.api.list_parts(lp).await.map(StreamExt::boxed)
     }
 
     async fn watch(
@@ -469,8 +482,11 @@ where
 {
     type Value = PartialObjectMeta<K>;
 
-    async fn list(&self, lp: &ListParams) -> kube_client::Result<ObjectList<Self::Value>> {
-        self.api.list_metadata(lp).await
+    async fn list(
+        &self,
+        lp: &ListParams,
+    ) -> kube_client::Result<BoxStream<'static, kube_client::Result<ListPart<Self::Value>>>> {
+        self.api.list_metadata_parts(lp).await.map(StreamExt::boxed)
     }
 
     async fn watch(
@@ -482,6 +498,28 @@ where
     }
 }
 
+/// Read a page of the initial LIST into memory
+///
+/// The page is decoded as it is received, so only the decoded objects are held, not the response body.
+/// It is read completely before any of its objects are returned, so that a slow consumer does not keep
+/// the response open until the apiserver times it out, at the cost of holding up to `page_size` objects.
+async fn read_page<K>(
+    response: impl future::Future<
+        Output = kube_client::Result<BoxStream<'static, kube_client::Result<ListPart<K>>>>,
+    >,
+) -> kube_client::Result<(Vec<K>, ListMeta)> {
+    let mut parts = response.await?;
+    let mut items = Vec::new();
+    let mut meta = ListMeta::default();
+    while let Some(part) = parts.next().await {
+        match part? {
+            ListPart::Item(obj) => items.push(obj),
+            ListPart::Metadata(list_meta) => meta = list_meta,
+        }
+    }
+    Ok((items, meta))
+}
+
 /// Progresses the watcher a single step, returning (event, state)
 ///
 /// This function should be trampolined: if event == `None`
@@ -500,13 +538,12 @@ where
         State::Empty => match wc.initial_list_strategy {
             InitialListStrategy::ListWatch => (Some(Ok(Event::Init)), State::InitPage {
                 continue_token: None,
-                objects: VecDeque::default(),
                 last_bookmark: None,
             }),
             InitialListStrategy::StreamingList => match api.watch(&wc.to_watch_params(), "0").await {
                 Ok(stream) => (None, State::InitialWatch { stream }),
                 Err(err) => {
-                    if std::matches!(err, ClientErr::Api(ErrorResponse { code: 403, .. })) {
+                    if err.is_forbidden() {
                         warn!("watch initlist error with 403: {err:?}");
                     } else {
                         debug!("watch initlist error: {err:?}");
@@ -517,16 +554,8 @@ where
         },
         State::InitPage {
             continue_token,
-            mut objects,
             last_bookmark,
         } => {
-            if let Some(next) = objects.pop_front() {
-                return (Some(Ok(Event::InitApply(next))), State::InitPage {
-                    continue_token,
-                    objects,
-                    last_bookmark,
-                });
-            }
             // check if we need to perform more pages
             if continue_token.is_none() {
                 if let Some(resource_version) = last_bookmark {
@@ -536,23 +565,15 @@ where
             }
             let mut lp = wc.to_list_params();
             lp.continue_token = continue_token;
-            match api.list(&lp).await {
-                Ok(list) => {
-                    let last_bookmark = list.metadata.resource_version.filter(|s| !s.is_empty());
-                    let continue_token = list.metadata.continue_.filter(|s| !s.is_empty());
-                    if last_bookmark.is_none() && continue_token.is_none() {
-                        return (Some(Err(Error::NoResourceVersion)), State::Empty);
-                    }
-                    // Buffer page here, causing us to return to this enum branch (State::InitPage)
-                    // until the objects buffer has drained
-                    (None, State::InitPage {
-                        continue_token,
-                        objects: list.items.into_iter().collect(),
-                        last_bookmark,
-                    })
-                }
+            match read_page(api.list(&lp)).await {
+                // Return to State::InitPageItems until the objects of the page have been returned
+                Ok((items, meta)) => (None, State::InitPageItems {
+                    items: items.into_iter(),
+                    continue_token: meta.continue_.filter(|s| !s.is_empty()),
+                    last_bookmark: meta.resource_version.filter(|s| !s.is_empty()),
+                }),
                 Err(err) => {
-                    if std::matches!(err, ClientErr::Api(ErrorResponse { code: 403, .. })) {
+                    if err.is_forbidden() {
                         warn!("watch list error with 403: {err:?}");
                     } else {
                         debug!("watch list error: {err:?}");
@@ -561,6 +582,26 @@ where
                 }
             }
         }
+        State::InitPageItems {
+            mut items,
+            continue_token,
+            last_bookmark,
+        } => {
+            if let Some(obj) = items.next() {
+                return (Some(Ok(Event::InitApply(obj))), State::InitPageItems {
+                    items,
+                    continue_token,
+                    last_bookmark,
+                });
+            }
+            if last_bookmark.is_none() && continue_token.is_none() {
+                return (Some(Err(Error::NoResourceVersion)), State::Empty);
+            }
+            (None, State::InitPage {
+                continue_token,
+                last_bookmark,
+            })
+        }
         State::InitialWatch { mut stream } => {
             match stream.next().await {
                 Some(Ok(WatchEvent::Added(obj) | WatchEvent::Modified(obj))) => {
@@ -598,7 +639,7 @@ where
                     (Some(Err(Error::WatchError(err))), new_state)
                 }
                 Some(Err(err)) => {
-                    if std::matches!(err, ClientErr::Api(ErrorResponse { code: 403, .. })) {
+                    if err.is_forbidden() {
                         warn!("watcher error 403: {err:?}");
                     } else {
                         debug!("watcher error: {err:?}");
@@ -615,7 +656,7 @@ where
                     stream,
                 }),
                 Err(err) => {
-                    if std::matches!(err, ClientErr::Api(ErrorResponse { code: 403, .. })) {
+                    if err.is_forbidden() {
                         warn!("watch initlist error with 403: {err:?}");
                     } else {
                         debug!("watch initlist error: {err:?}");
@@ -674,7 +715,7 @@ where
                 (Some(Err(Error::WatchError(err))), new_state)
             }
             Some(Err(err)) => {
-                if std::matches!(err, ClientErr::Api(ErrorResponse { code: 403, .. })) {
+                if err.is_forbidden() {
                     warn!("watcher error 403: {err:?}");
                 } else {
                     debug!("watcher error: {err:?}");